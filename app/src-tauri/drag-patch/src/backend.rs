@@ -0,0 +1,149 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::{CursorPosition, DragItem, DragResult, DropEffect, Error, Image, Options, Result};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Called once when a drag ends.
+pub type DropCallback = Box<dyn Fn(DragResult, CursorPosition) + Send>;
+
+/// Runs drags started from a window of type `W`.
+///
+/// [`NativeBackend`] uses the platform's drag and drop and is what [`start_drag`](crate::start_drag)
+/// uses; [`MockBackend`] records drags instead, so code starting drags can be tested
+/// without a window server.
+pub trait Backend<W: ?Sized> {
+    fn start_drag(
+        &self,
+        window: &W,
+        item: DragItem,
+        image: Image,
+        on_drop_callback: DropCallback,
+        options: Options,
+    ) -> Result<()>;
+}
+
+/// The platform's drag and drop.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NativeBackend;
+
+#[cfg(target_os = "linux")]
+impl Backend<gtk::ApplicationWindow> for NativeBackend {
+    fn start_drag(
+        &self,
+        window: &gtk::ApplicationWindow,
+        item: DragItem,
+        image: Image,
+        on_drop_callback: DropCallback,
+        options: Options,
+    ) -> Result<()> {
+        crate::platform_impl::start_drag(window, item, image, on_drop_callback, options)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl<W: raw_window_handle::HasWindowHandle> Backend<W> for NativeBackend {
+    fn start_drag(
+        &self,
+        handle: &W,
+        item: DragItem,
+        image: Image,
+        on_drop_callback: DropCallback,
+        options: Options,
+    ) -> Result<()> {
+        crate::platform_impl::start_drag(handle, item, image, on_drop_callback, options)
+    }
+}
+
+/// How a drag started with a [`MockBackend`] goes.
+#[derive(Debug, Clone, Copy)]
+pub enum MockOutcome {
+    /// The drop target performs the operation as soon as the drag starts.
+    Dropped(DropEffect),
+    /// The drag is cancelled as soon as it starts.
+    Cancelled,
+    /// The drag stays in progress until [`MockBackend::end_drag`] is called.
+    Pending,
+    /// Starting the drag fails with [`Error::FailedToStartDrag`].
+    Failed,
+}
+
+/// A drag started with a [`MockBackend`].
+pub struct RecordedDrag {
+    pub item: DragItem,
+    pub image: Image,
+    pub options: Options,
+}
+
+/// Records the drags started with it and ends them as scripted, for tests.
+///
+/// Drags without a scripted outcome are cancelled. The cursor position reported for
+/// every drag is (0, 0).
+#[derive(Default)]
+pub struct MockBackend {
+    outcomes: Mutex<VecDeque<MockOutcome>>,
+    drags: Mutex<Vec<RecordedDrag>>,
+    pending: Mutex<VecDeque<DropCallback>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Script the outcome of the next drag without one.
+    pub fn push_outcome(&self, outcome: MockOutcome) -> &Self {
+        self.outcomes.lock().unwrap().push_back(outcome);
+        self
+    }
+
+    /// The drags started so far, oldest first. Failed ones are included.
+    pub fn take_drags(&self) -> Vec<RecordedDrag> {
+        std::mem::take(&mut self.drags.lock().unwrap())
+    }
+
+    /// End the oldest [`MockOutcome::Pending`] drag. Returns false if there is none.
+    pub fn end_drag(&self, result: DragResult) -> bool {
+        let Some(on_drop_callback) = self.pending.lock().unwrap().pop_front() else {
+            return false;
+        };
+        on_drop_callback(result, CursorPosition { x: 0, y: 0 });
+        true
+    }
+}
+
+impl<W: ?Sized> Backend<W> for MockBackend {
+    fn start_drag(
+        &self,
+        _window: &W,
+        item: DragItem,
+        image: Image,
+        on_drop_callback: DropCallback,
+        options: Options,
+    ) -> Result<()> {
+        self.drags.lock().unwrap().push(RecordedDrag {
+            item,
+            image,
+            options,
+        });
+        let outcome = self
+            .outcomes
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or(MockOutcome::Cancelled);
+        let result = match outcome {
+            MockOutcome::Dropped(effect) => DragResult::Dropped(effect),
+            MockOutcome::Cancelled => DragResult::Cancel,
+            MockOutcome::Pending => {
+                self.pending.lock().unwrap().push_back(on_drop_callback);
+                return Ok(());
+            }
+            MockOutcome::Failed => return Err(Error::FailedToStartDrag),
+        };
+        on_drop_callback(result, CursorPosition { x: 0, y: 0 });
+        Ok(())
+    }
+}
@@ -85,10 +85,118 @@
 #[macro_use]
 extern crate objc;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+mod backend;
 mod platform_impl;
-pub use platform_impl::start_drag;
+mod session;
+
+pub use backend::{Backend, DropCallback, MockBackend, MockOutcome, NativeBackend, RecordedDrag};
+pub use session::{DragEvent, DragFuture, DragOutcome, EventHandler};
+
+/// Start a drag of `item` out of the window, showing `image` under the cursor.
+/// `on_drop_callback` is called once the drag ends, dropped or cancelled.
+#[cfg(target_os = "linux")]
+pub fn start_drag<F: Fn(DragResult, CursorPosition) + Send + 'static>(
+    window: &gtk::ApplicationWindow,
+    item: DragItem,
+    image: Image,
+    on_drop_callback: F,
+    options: Options,
+) -> Result<()> {
+    start_drag_with(
+        &NativeBackend,
+        window,
+        item,
+        image,
+        on_drop_callback,
+        options,
+    )
+}
+
+/// Start a drag of `item` out of the window, showing `image` under the cursor.
+/// `on_drop_callback` is called once the drag ends, dropped or cancelled.
+#[cfg(not(target_os = "linux"))]
+pub fn start_drag<
+    W: raw_window_handle::HasWindowHandle,
+    F: Fn(DragResult, CursorPosition) + Send + 'static,
+>(
+    handle: &W,
+    item: DragItem,
+    image: Image,
+    on_drop_callback: F,
+    options: Options,
+) -> Result<()> {
+    start_drag_with(
+        &NativeBackend,
+        handle,
+        item,
+        image,
+        on_drop_callback,
+        options,
+    )
+}
+
+/// Like [`start_drag`], but run by `backend`, e.g. a [`MockBackend`] in tests.
+pub fn start_drag_with<W, B, F>(
+    backend: &B,
+    window: &W,
+    item: DragItem,
+    image: Image,
+    on_drop_callback: F,
+    options: Options,
+) -> Result<()>
+where
+    W: ?Sized,
+    B: Backend<W> + ?Sized,
+    F: Fn(DragResult, CursorPosition) + Send + 'static,
+{
+    let session = session::Session::new(&options, on_drop_callback);
+    backend.start_drag(window, item, image, Box::new(session.callback()), options)?;
+    session.started();
+    Ok(())
+}
+
+/// Like [`start_drag`], but returns a future that resolves to the outcome instead of
+/// taking a callback.
+///
+/// - **Windows**: The drag runs inside this call, so the future is already resolved.
+#[cfg(target_os = "linux")]
+pub fn start_drag_async(
+    window: &gtk::ApplicationWindow,
+    item: DragItem,
+    image: Image,
+    options: Options,
+) -> Result<DragFuture> {
+    start_drag_async_with(&NativeBackend, window, item, image, options)
+}
+
+/// Like [`start_drag`], but returns a future that resolves to the outcome instead of
+/// taking a callback.
+///
+/// - **Windows**: The drag runs inside this call, so the future is already resolved.
+#[cfg(not(target_os = "linux"))]
+pub fn start_drag_async<W: raw_window_handle::HasWindowHandle>(
+    handle: &W,
+    item: DragItem,
+    image: Image,
+    options: Options,
+) -> Result<DragFuture> {
+    start_drag_async_with(&NativeBackend, handle, item, image, options)
+}
+
+/// Like [`start_drag_async`], but run by `backend`, e.g. a [`MockBackend`] in tests.
+pub fn start_drag_async_with<W: ?Sized, B: Backend<W> + ?Sized>(
+    backend: &B,
+    window: &W,
+    item: DragItem,
+    image: Image,
+    options: Options,
+) -> Result<DragFuture> {
+    let (future, on_drop_callback) = DragFuture::new();
+    start_drag_with(backend, window, item, image, on_drop_callback, options)?;
+    Ok(future)
+}
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -117,12 +225,28 @@ pub enum Error {
 #[derive(Debug, Clone, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum DragResult {
-    Dropped,
+    /// The items were dropped and the target performed the given operation.
+    Dropped(DropEffect),
     Cancel,
 }
 
+/// The operation a drop target performed with the dragged items.
+///
+/// Targets that accept a drop without reporting an operation are treated as having copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DropEffect {
+    Copy,
+    Move,
+    Link,
+}
+
 pub type DataProvider = Box<dyn Fn(&str) -> Option<Vec<u8>>>;
 
+/// Writes a promised file to the path chosen by the drop target.
+pub type FileWriter = Box<dyn Fn(&Path) -> std::io::Result<()>>;
+
 /// Item to be dragged.
 pub enum DragItem {
     /// A list of files to be dragged.
@@ -132,17 +256,72 @@ pub enum DragItem {
     /// Data to share with another app.
     ///
     /// - **Windows**: Not supported. Will result in a dummy drag operation of current folder that will be cancelled upon dropping.
-    /// - **Linux (gtk)**: Not supported. Will result in a dummy drag operation that contains nothing to drop.
+    /// - **Linux (gtk)**: Each type is offered as a GTK target and the provider is called
+    ///   when the drop target asks for it. macOS type identifiers (`public.utf8-plain-text`,
+    ///   `public.html`, `public.png`, `public.file-url`) are mapped to their MIME types, and
+    ///   plain text is also offered as `UTF8_STRING`.
     Data {
         provider: DataProvider,
         types: Vec<String>,
     },
+    /// A file that only exists once it is dropped: the drop target picks a location
+    /// and `writer` creates the file there.
+    ///
+    /// - **Linux (gtk)**: Uses the XDND Direct Save protocol (XDS), which Nautilus, Dolphin,
+    ///   Thunar and most other file managers understand. `file_name` is suggested to the
+    ///   target, which may change it.
+    /// - **macOS / Windows**: Not supported yet. The file is written to a temporary folder
+    ///   up front and dragged like [`DragItem::Files`].
+    PromisedFile {
+        file_name: String,
+        writer: FileWriter,
+    },
+    /// Several representations of the same content in one drag, e.g. files together with
+    /// their URLs as text. Every representation is advertised and the drop target picks
+    /// the richest one it understands; when two offer the same format the first wins.
+    ///
+    /// - **macOS / Windows**: Not supported yet. Only the first representation is dragged,
+    ///   so put the one most targets should get first.
+    Multi(Vec<DragItem>),
 }
 
+impl DragItem {
+    // Reduce the item to what backends without mixed drags and promised files support:
+    // the first representation, with a promised file written to a temporary folder
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn into_basic(self) -> Result<DragItem> {
+        match self {
+            DragItem::Multi(items) => items
+                .into_iter()
+                .next()
+                .ok_or(Error::FailedToStartDrag)?
+                .into_basic(),
+            DragItem::PromisedFile { file_name, writer } => {
+                let dir = std::env::temp_dir()
+                    .join("drag-promised")
+                    .join(std::process::id().to_string());
+                std::fs::create_dir_all(&dir)?;
+                let name = Path::new(&file_name)
+                    .file_name()
+                    .map(|name| name.to_owned())
+                    .unwrap_or_else(|| "file".into());
+                let path = dir.join(name);
+                writer(&path)?;
+                Ok(DragItem::Files(vec![path]))
+            }
+            item => Ok(item),
+        }
+    }
+}
+
+/// The operations offered to the drop target. With more than one, the target (usually
+/// guided by held modifier keys) picks the one that is performed, which is reported
+/// through [`DragResult::Dropped`].
 #[derive(Debug, Clone, Copy)]
 #[repr(u64)]
 pub enum DragMode {
     Copy = 1,  // NSDragOperationCopy
+    Link = 2,  // NSDragOperationLink
     Move = 16, // NSDragOperationMove
     CopyOrMove = 17, // NSDragOperationCopy | NSDragOperationMove
 }
@@ -164,6 +343,32 @@ unsafe impl objc::Encode for DragMode {
 pub struct Options {
     pub skip_animatation_on_cancel_or_failure: bool,
     pub mode: DragMode,
+    /// Point of the drag image under the cursor, in logical pixels from its top left
+    /// corner (of the image as shown, after `image_size`). By default the cursor is at
+    /// the top left corner on Linux and Windows and at the centre on macOS.
+    ///
+    /// - **macOS / Windows**: Not supported yet, the default is always used.
+    pub image_hotspot: Option<(f64, f64)>,
+    /// Logical size the drag image is shown at; the image is stretched to fit. By default
+    /// its pixel size divided by `image_scale`.
+    ///
+    /// - **macOS**: Not supported yet. The size recorded in the image is used, which for a
+    ///   PNG with a resolution (pHYs chunk) is already its logical size.
+    /// - **Windows**: Not supported yet, the image is shown at its pixel size.
+    pub image_size: Option<(f64, f64)>,
+    /// Image pixels per logical pixel, e.g. 2.0 for an image rendered for a window with a
+    /// scale factor of 2. Defaults to 1.0. Backends render the image at the screen's scale
+    /// factor, so an image rendered at that scale stays sharp.
+    ///
+    /// - **macOS / Windows**: Not supported yet, see `image_size`.
+    pub image_scale: Option<f64>,
+    /// Opacity of the drag image from 0.0 (invisible) to 1.0, applied on top of the
+    /// image's own alpha. Defaults to 1.0.
+    ///
+    /// - **macOS / Windows**: Not supported yet, the image is shown as is.
+    pub image_opacity: Option<f64>,
+    /// Called with the lifecycle events of the drag, on the thread running the drag.
+    pub on_event: Option<EventHandler>,
 }
 
 /// An image definition.
@@ -2,20 +2,25 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use crate::{CursorPosition, DragItem, DragMode, DragResult, Error, Image, Options};
+use crate::{
+    CursorPosition, DragEvent, DragItem, DragMode, DragResult, DropEffect, Error, EventHandler,
+    Image, Options,
+};
 use gdkx11::{
     gdk,
-    glib::{ObjectExt, Propagation, SignalHandlerId},
+    glib::{self, translate::ToGlibPtr, ObjectExt, Propagation, SignalHandlerId},
 };
 use gtk::{
     gdk_pixbuf,
     prelude::{
-        DeviceExt, DragContextExtManual, PixbufLoaderExt, SeatExt, WidgetExt, WidgetExtManual,
+        DeviceExt, DragContextExtManual, GdkPixbufExt, PixbufLoaderExt, SeatExt, WidgetExt,
+        WidgetExtManual,
     },
 };
 use std::{
     rc::Rc,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 pub fn start_drag<F: Fn(DragResult, CursorPosition) + Send + 'static>(
@@ -27,38 +32,33 @@ pub fn start_drag<F: Fn(DragResult, CursorPosition) + Send + 'static>(
 ) -> crate::Result<()> {
     log::debug!("Starting drag operation with mode: {:?}", options.mode);
     let handler_ids: Arc<Mutex<Vec<SignalHandlerId>>> = Arc::new(Mutex::new(vec![]));
-    let drag_action = match options.mode {
-        DragMode::Copy => gdk::DragAction::COPY,
-        DragMode::Move => gdk::DragAction::MOVE,
-    };
+    let drag_action = drag_actions(options.mode);
 
     log::debug!("Setting drag source with action: {:?}", drag_action);
     window.drag_source_set(gdk::ModifierType::BUTTON1_MASK, &[], drag_action);
 
-    match item {
-        DragItem::Files(paths) => {
-            log::debug!("Setting up file drag with {} paths", paths.len());
-            window.drag_source_add_uri_targets();
-            handler_ids
-                .lock()
-                .unwrap()
-                .push(window.connect_drag_data_get(move |_, _, data, _, _| {
-                    log::debug!("Preparing URIs for drag data");
-                    let uris: Vec<String> = paths
-                        .iter()
-                        .map(|path| format!("file://{}", path.display()))
-                        .collect();
-                    let uris: Vec<&str> = uris.iter().map(|s| s.as_str()).collect();
-                    log::debug!("Setting URIs: {:?}", uris);
-                    data.set_uris(&uris);
-                }));
-        }
-        DragItem::Data { .. } => {
-            // Currently leaving it as is as we can utilize it as a dummy dragging feature
-            // on_drop_callback(DragResult::Cancel, get_cursor_position(window).unwrap());
-            // return Ok(());
-        }
+    let mut targets = DragTargets::default();
+    targets.add(item);
+    if targets.entries.is_empty() {
+        return Err(crate::Error::EmptyTargetList);
     }
+    window.drag_source_set_target_list(Some(&gtk::TargetList::new(&targets.entries)));
+    let DragTargets {
+        handlers,
+        direct_save_name,
+        ..
+    } = targets;
+    handler_ids
+        .lock()
+        .unwrap()
+        .push(
+            window.connect_drag_data_get(move |_, context, data, info, _| {
+                let (representation, index) = ((info >> 16) as usize, (info & 0xFFFF) as usize);
+                if let Some(handler) = handlers.get(representation) {
+                    handler(context, data, index);
+                }
+            }),
+        );
 
     if let Some(target_list) = &window.drag_source_get_target_list() {
         log::debug!("Got target list, initiating drag");
@@ -71,9 +71,22 @@ pub fn start_drag<F: Fn(DragResult, CursorPosition) + Send + 'static>(
             -1,
         ) {
             log::debug!("Drag context created successfully");
+            if let Some(file_name) = &direct_save_name {
+                gdk::property_change(
+                    &drag_context.source_window(),
+                    &gdk::Atom::intern(XDS_TARGET),
+                    &gdk::Atom::intern(XDS_NAME_TYPE),
+                    8,
+                    gdk::PropMode::Replace,
+                    gdk::ChangeData::UChars(file_name.as_bytes()),
+                );
+            }
             let callback = Rc::new(on_drop_callback);
             on_drop_failed(callback.clone(), window, &handler_ids, &options);
             on_drop_performed(callback.clone(), window, &handler_ids, &drag_context);
+            if let Some(on_event) = &options.on_event {
+                track_motion(on_event.clone(), window, &handler_ids, &drag_context);
+            }
 
             log::debug!("Setting up drag icon");
             let icon_pixbuf: Option<gdk_pixbuf::Pixbuf> = match &image {
@@ -84,7 +97,10 @@ pub fn start_drag<F: Fn(DragResult, CursorPosition) + Send + 'static>(
                 },
             };
             if let Some(icon) = icon_pixbuf {
-                drag_context.drag_set_icon_pixbuf(&icon, 0, 0);
+                match drag_icon_surface(&icon, window.scale_factor(), &options) {
+                    Some(surface) => drag_context.drag_set_icon_surface(&surface),
+                    None => drag_context.drag_set_icon_pixbuf(&icon, 0, 0),
+                }
             }
 
             Ok(())
@@ -96,6 +112,153 @@ pub fn start_drag<F: Fn(DragResult, CursorPosition) + Send + 'static>(
     }
 }
 
+// X11 names for plain text that GTK converts to and from UTF-8 itself
+const TEXT_TARGETS: &[&str] = &["UTF8_STRING", "TEXT", "STRING", "COMPOUND_TEXT"];
+
+// The GTK targets offered for a data type; macOS type identifiers are mapped to
+// their MIME equivalents so the same `types` work on both platforms
+fn gtk_targets(data_type: &str) -> Vec<&str> {
+    match data_type {
+        "text/plain" | "text/plain;charset=utf-8" | "public.utf8-plain-text" => {
+            let mut targets = vec!["text/plain;charset=utf-8", "text/plain"];
+            targets.extend_from_slice(TEXT_TARGETS);
+            targets
+        }
+        "public.html" => vec!["text/html"],
+        "public.png" => vec!["image/png"],
+        "public.file-url" | "public.url" => vec!["text/uri-list"],
+        other => vec![other],
+    }
+}
+
+type DataGetHandler = Box<dyn Fn(&gdk::DragContext, &gtk::SelectionData, usize)>;
+
+// The targets offered for a drag and how to answer a request for each. Every
+// representation of the item gets a handler; a target's info holds the handler's
+// index in the high 16 bits and the index of the requested type in the low ones.
+#[derive(Default)]
+struct DragTargets {
+    entries: Vec<gtk::TargetEntry>,
+    handlers: Vec<DataGetHandler>,
+    // Suggested name of a promised file, announced on the drag's source window
+    direct_save_name: Option<String>,
+}
+
+impl DragTargets {
+    // Must be called before the representation's handler is pushed
+    fn add_target(&mut self, target: &str, flags: gtk::TargetFlags, index: usize) {
+        // The first representation offering a target serves it
+        if self.entries.iter().any(|entry| entry.target() == target) {
+            return;
+        }
+        let info = ((self.handlers.len() as u32) << 16) | index as u32;
+        self.entries
+            .push(gtk::TargetEntry::new(target, flags, info));
+    }
+
+    fn add(&mut self, item: DragItem) {
+        match item {
+            DragItem::Files(paths) => {
+                log::debug!("Offering {} file(s)", paths.len());
+                self.add_target("text/uri-list", gtk::TargetFlags::empty(), 0);
+                self.handlers.push(Box::new(move |_, data, _| {
+                    let uris: Vec<String> = paths
+                        .iter()
+                        .map(|path| format!("file://{}", path.display()))
+                        .collect();
+                    let uris: Vec<&str> = uris.iter().map(|s| s.as_str()).collect();
+                    log::debug!("Setting URIs: {:?}", uris);
+                    data.set_uris(&uris);
+                }));
+            }
+            DragItem::Data { provider, types } => {
+                log::debug!("Offering data of types {:?}", types);
+                for (index, data_type) in types.iter().enumerate() {
+                    for target in gtk_targets(data_type) {
+                        self.add_target(target, gtk::TargetFlags::empty(), index);
+                    }
+                }
+                self.handlers.push(Box::new(move |_, data, index| {
+                    // The provider is only asked once the drop target requests a format
+                    let Some(format) = types.get(index) else {
+                        return;
+                    };
+                    log::debug!("Providing drag data for {}", format);
+                    if let Some(bytes) = provider(format) {
+                        set_selection_data(data, &bytes);
+                    }
+                }));
+            }
+            DragItem::PromisedFile { file_name, writer } => {
+                log::debug!("Offering direct save of {}", file_name);
+                self.add_target(XDS_TARGET, gtk::TargetFlags::OTHER_APP, 0);
+                self.direct_save_name.get_or_insert(file_name);
+                self.handlers.push(Box::new(move |context, data, _| {
+                    let reply = direct_save(context, &writer);
+                    data.set(&data.target(), 8, reply.as_bytes());
+                }));
+            }
+            DragItem::Multi(items) => {
+                for item in items {
+                    self.add(item);
+                }
+            }
+        }
+    }
+}
+
+fn set_selection_data(data: &gtk::SelectionData, bytes: &[u8]) {
+    let target = data.target();
+    if TEXT_TARGETS.contains(&target.name().as_str()) {
+        data.set_text(&String::from_utf8_lossy(bytes));
+    } else {
+        data.set(&target, 8, bytes);
+    }
+}
+
+// XDND Direct Save: the source puts the suggested file name in this property of its
+// window; the target replaces it with the URI to save to and then requests the
+// target of the same name, which is answered with "S" (saved) or "E" (error)
+const XDS_TARGET: &str = "XdndDirectSave0";
+const XDS_NAME_TYPE: &str = "text/plain";
+
+fn direct_save(context: &gdk::DragContext, writer: &crate::FileWriter) -> &'static str {
+    let window = context.source_window();
+    let property = gdk::Atom::intern(XDS_TARGET);
+    let Some((_, _, uri)) = gdk::property_get(
+        &window,
+        &property,
+        &gdk::Atom::intern(XDS_NAME_TYPE),
+        0,
+        4096,
+        0,
+    ) else {
+        log::warn!("Drop target did not set a direct save location");
+        return "E";
+    };
+    gdk::property_delete(&window, &property);
+
+    let uri = String::from_utf8_lossy(&uri);
+    let path = match glib::filename_from_uri(uri.trim_end_matches('\0')) {
+        Ok((path, host)) if host.map_or(true, |host| host == "localhost") => path,
+        // Only local files can be written; remote hosts are left to the target
+        _ => {
+            log::warn!("Unsupported direct save location: {}", uri);
+            return "E";
+        }
+    };
+    match writer(&path) {
+        Ok(()) => {
+            log::debug!("Saved promised file to {}", path.display());
+            "S"
+        }
+        Err(e) => {
+            log::error!("Failed to save promised file to {}: {}", path.display(), e);
+            "E"
+        }
+    }
+}
+
 fn image_binary_to_pixbuf(data: &[u8]) -> Option<gdk_pixbuf::Pixbuf> {
     let loader = gdk_pixbuf::PixbufLoader::new();
     loader
@@ -106,6 +269,73 @@ fn image_binary_to_pixbuf(data: &[u8]) -> Option<gdk_pixbuf::Pixbuf> {
         .ok()
 }
 
+// The drag icon as a surface at the window's scale factor: resized to the logical size
+// from the options, faded to their opacity, with the hotspot as device offset
+fn drag_icon_surface(
+    pixbuf: &gdk_pixbuf::Pixbuf,
+    window_scale: i32,
+    options: &Options,
+) -> Option<gdk::cairo::Surface> {
+    let image_scale = options
+        .image_scale
+        .filter(|scale| scale.is_finite() && *scale > 0.0)
+        .unwrap_or(1.0);
+    let (width, height) = options.image_size.unwrap_or((
+        pixbuf.width() as f64 / image_scale,
+        pixbuf.height() as f64 / image_scale,
+    ));
+    let (hot_x, hot_y) = options.image_hotspot.unwrap_or((0.0, 0.0));
+    let opacity = options.image_opacity.unwrap_or(1.0).clamp(0.0, 1.0);
+    log::debug!(
+        "Drag icon of {}x{} logical px, hotspot ({}, {}), opacity {}",
+        width,
+        height,
+        hot_x,
+        hot_y,
+        opacity
+    );
+
+    let device_scale = window_scale.max(1) as f64;
+    let pixel_width = (width * device_scale).round().max(1.0) as i32;
+    let pixel_height = (height * device_scale).round().max(1.0) as i32;
+    let scaled = if (pixel_width, pixel_height) == (pixbuf.width(), pixbuf.height()) {
+        pixbuf.clone()
+    } else {
+        pixbuf.scale_simple(pixel_width, pixel_height, gdk_pixbuf::InterpType::Bilinear)?
+    };
+    let icon = if opacity < 1.0 {
+        let faded = gdk_pixbuf::Pixbuf::new(
+            gdk_pixbuf::Colorspace::Rgb,
+            true,
+            8,
+            pixel_width,
+            pixel_height,
+        )?;
+        faded.fill(0);
+        scaled.composite(
+            &faded,
+            0,
+            0,
+            pixel_width,
+            pixel_height,
+            0.0,
+            0.0,
+            1.0,
+            1.0,
+            gdk_pixbuf::InterpType::Nearest,
+            (opacity * 255.0).round() as i32,
+        );
+        faded
+    } else {
+        scaled
+    };
+
+    let surface = icon.create_surface(window_scale.max(1), None::<&gdk::Window>)?;
+    // GTK puts the cursor where the surface's user space origin is
+    surface.set_device_offset(-hot_x * device_scale, -hot_y * device_scale);
+    Some(surface)
+}
+
 fn clear_signal_handlers(window: &gtk::ApplicationWindow, handler_ids: &mut Vec<SignalHandlerId>) {
     for handler_id in handler_ids.drain(..) {
         window.disconnect(handler_id);
@@ -165,14 +395,80 @@ fn on_drop_performed<F: Fn(DragResult, CursorPosition) + Send + 'static>(
     let handler_ids = handler_ids.clone();
 
     drag_context.connect_drop_performed(move |context, _| {
-        log::debug!("Drop performed successfully");
-        log::trace!("Selected action: {:?}", context.selected_action());
-        log::trace!("Suggested action: {:?}", context.suggested_action());
+        log::debug!(
+            "Drop performed with action {:?} (suggested {:?})",
+            context.selected_action(),
+            context.suggested_action()
+        );
         cleanup_signal_handlers(&handler_ids, &window);
-        callback(DragResult::Dropped, get_cursor_position(&window).unwrap());
+        callback(
+            DragResult::Dropped(drop_effect(context.selected_action())),
+            get_cursor_position(&window).unwrap(),
+        );
     });
 }
 
+const MOTION_INTERVAL: Duration = Duration::from_millis(16);
+
+// GTK tells the drag source nothing about the cursor, so poll its position and the
+// window under it while the drag runs, i.e. until the signal handlers are cleaned up
+fn track_motion(
+    on_event: EventHandler,
+    window: &gtk::ApplicationWindow,
+    handler_ids: &Arc<Mutex<Vec<SignalHandlerId>>>,
+    drag_context: &gdk::DragContext,
+) {
+    let window = window.clone();
+    let handler_ids = handler_ids.clone();
+    let drag_context = drag_context.clone();
+    let mut last_position = None;
+    let mut last_target = std::ptr::null_mut();
+
+    glib::timeout_add_local(MOTION_INTERVAL, move || {
+        if handler_ids.lock().unwrap().is_empty() {
+            return glib::ControlFlow::Break;
+        }
+        let Ok(position) = get_cursor_position(&window) else {
+            return glib::ControlFlow::Continue;
+        };
+        if last_position != Some((position.x, position.y)) {
+            last_position = Some((position.x, position.y));
+            on_event(DragEvent::Moved(position.clone()));
+        }
+        // The safe binding panics while the cursor is over no window at all
+        let target =
+            unsafe { gdk::ffi::gdk_drag_context_get_dest_window(drag_context.to_glib_none().0) };
+        if target != last_target {
+            last_target = target;
+            if !target.is_null() && drag_context.protocol() != gdk::DragProtocol::None {
+                on_event(DragEvent::EnteredTarget(position));
+            }
+        }
+        glib::ControlFlow::Continue
+    });
+}
+
+// The actions offered to the drop target, which picks one of them
+fn drag_actions(mode: DragMode) -> gdk::DragAction {
+    match mode {
+        DragMode::Copy => gdk::DragAction::COPY,
+        DragMode::Link => gdk::DragAction::LINK,
+        DragMode::Move => gdk::DragAction::MOVE,
+        DragMode::CopyOrMove => gdk::DragAction::COPY | gdk::DragAction::MOVE,
+    }
+}
+
+// The action the drop target selected
+fn drop_effect(action: gdk::DragAction) -> DropEffect {
+    if action.contains(gdk::DragAction::MOVE) {
+        DropEffect::Move
+    } else if action.contains(gdk::DragAction::LINK) {
+        DropEffect::Link
+    } else {
+        DropEffect::Copy
+    }
+}
+
 fn get_cursor_position(window: &gtk::ApplicationWindow) -> Result<CursorPosition, Error> {
     if let Some(cursor) = window
         .display()
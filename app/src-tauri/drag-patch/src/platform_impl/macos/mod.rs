@@ -19,7 +19,7 @@ use objc::{
 };
 use raw_window_handle::{HasWindowHandle, RawWindowHandle};
 
-use crate::{CursorPosition, DragItem, DragMode, DragResult, Image, Options};
+use crate::{CursorPosition, DragItem, DragMode, DragResult, DropEffect, Image, Options};
 
 const UTF8_ENCODING: usize = 4;
 
@@ -60,6 +60,7 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
     on_drop_callback: F,
     options: Options,
 ) -> crate::Result<()> {
+    let item = item.into_basic()?;
     if let Ok(RawWindowHandle::AppKit(w)) = handle.window_handle().map(|h| h.as_raw()) {
         unsafe {
             let window: id = msg_send![w.ns_view.as_ptr() as id, window];
@@ -191,6 +192,9 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
 
                     let _: () = msg_send![dragging_items, addObject: item];
                 }
+                DragItem::PromisedFile { .. } | DragItem::Multi(_) => {
+                    unreachable!("reduced to a basic item before the drag starts")
+                }
             }
 
             let drag_event: id = msg_send![class!(NSEvent), alloc];
@@ -236,10 +240,8 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
                             let mode = *this.get_ivar::<DragMode>("drag_mode");
                             let () = msg_send![dragging_session, setAnimatesToStartingPositionsOnCancelOrFail: *animates];
 
-                            match mode {
-                                DragMode::Copy => 1,  // NSDragOperationCopy
-                                DragMode::Move => 16, // NSDragOperationMove
-                            }
+                            // The discriminants are the NSDragOperation masks
+                            mode as NSUInteger
                         }
                     }
 
@@ -261,11 +263,23 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
                             let callback_closure =
                                 &*(*callback as *mut Box<dyn Fn(DragResult, CursorPosition)>);
 
-                            if operation == 0 {
+                            match operation {
                                 // NSDragOperationNone
-                                callback_closure(DragResult::Cancel, mouse_location);
-                            } else {
-                                callback_closure(DragResult::Dropped, mouse_location);
+                                0 => callback_closure(DragResult::Cancel, mouse_location),
+                                // NSDragOperationLink
+                                2 => callback_closure(
+                                    DragResult::Dropped(DropEffect::Link),
+                                    mouse_location,
+                                ),
+                                // NSDragOperationMove, or NSDragOperationDelete for the trash
+                                16 | 32 => callback_closure(
+                                    DragResult::Dropped(DropEffect::Move),
+                                    mouse_location,
+                                ),
+                                _ => callback_closure(
+                                    DragResult::Dropped(DropEffect::Copy),
+                                    mouse_location,
+                                ),
                             }
 
                             drop(Box::from_raw(*callback as *mut Box<dyn Fn(DragResult)>));
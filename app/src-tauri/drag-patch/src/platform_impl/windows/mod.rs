@@ -4,7 +4,7 @@
 
 use raw_window_handle::{HasWindowHandle, RawWindowHandle};
 
-use crate::{CursorPosition, DragItem, DragMode, DragResult, Image, Options};
+use crate::{CursorPosition, DragItem, DragMode, DragResult, DropEffect, Image, Options};
 
 use std::{
     ffi::c_void,
@@ -19,10 +19,12 @@ use windows::{
         Foundation::*,
         Graphics::Gdi::{GetObjectW, BITMAP},
         System::Com::*,
+        System::DataExchange::RegisterClipboardFormatW,
         System::Memory::*,
-        System::Ole::{DoDragDrop, OleInitialize},
+        System::Ole::{DoDragDrop, OleInitialize, ReleaseStgMedium},
         System::Ole::{
-            IDropSource, IDropSource_Impl, CF_HDROP, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_MOVE,
+            IDropSource, IDropSource_Impl, CF_HDROP, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_LINK,
+            DROPEFFECT_MOVE,
         },
         System::SystemServices::{MK_LBUTTON, MODIFIERKEYS_FLAGS},
         UI::{
@@ -219,6 +221,7 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
     on_drop_callback: F,
     options: Options,
 ) -> crate::Result<()> {
+    let item = item.into_basic()?;
     if let Ok(RawWindowHandle::Win32(_w)) = handle.window_handle().map(|h| h.as_raw()) {
         match item {
             DragItem::Files(files) => {
@@ -250,6 +253,7 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
                     let mut out_dropeffect = DROPEFFECT::default();
                     let effect = match options.mode {
                         DragMode::Copy => DROPEFFECT_COPY,
+                        DragMode::Link => DROPEFFECT_LINK,
                         DragMode::Move => DROPEFFECT_MOVE,
                         DragMode::CopyOrMove => DROPEFFECT_COPY | DROPEFFECT_MOVE,
                     };
@@ -259,7 +263,13 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
                     let mut pt = POINT { x: 0, y: 0 };
                     GetCursorPos(&mut pt)?;
                     if drop_result == DRAGDROP_S_DROP {
-                        on_drop_callback(DragResult::Dropped, CursorPosition { x: pt.x, y: pt.y });
+                        let performed = performed_drop_effect(&data_object)
+                            .filter(|effect| effect.0 != 0)
+                            .unwrap_or(out_dropeffect);
+                        on_drop_callback(
+                            DragResult::Dropped(drop_effect(performed)),
+                            CursorPosition { x: pt.x, y: pt.y },
+                        );
                     } else {
                         // DRAGDROP_S_CANCEL
                         on_drop_callback(DragResult::Cancel, CursorPosition { x: pt.x, y: pt.y });
@@ -299,13 +309,19 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
                     let mut pt = POINT { x: 0, y: 0 };
                     GetCursorPos(&mut pt)?;
                     if drop_result == DRAGDROP_S_DROP {
-                        on_drop_callback(DragResult::Dropped, CursorPosition { x: pt.x, y: pt.y });
+                        on_drop_callback(
+                            DragResult::Dropped(DropEffect::Copy),
+                            CursorPosition { x: pt.x, y: pt.y },
+                        );
                     } else {
                         // DRAGDROP_S_CANCEL
                         on_drop_callback(DragResult::Cancel, CursorPosition { x: pt.x, y: pt.y });
                     }
                 }
             }
+            DragItem::PromisedFile { .. } | DragItem::Multi(_) => {
+                unreachable!("reduced to a basic item before the drag starts")
+            }
         }
         Ok(())
     } else {
@@ -313,6 +329,38 @@ pub fn start_drag<W: HasWindowHandle, F: Fn(DragResult, CursorPosition) + Send +
     }
 }
 
+fn drop_effect(effect: DROPEFFECT) -> DropEffect {
+    if effect.0 & DROPEFFECT_MOVE.0 != 0 {
+        DropEffect::Move
+    } else if effect.0 & DROPEFFECT_LINK.0 != 0 {
+        DropEffect::Link
+    } else {
+        DropEffect::Copy
+    }
+}
+
+// Explorer reports an optimized move (within a volume) as DROPEFFECT_NONE and stores
+// what it actually did in the "Performed DropEffect" format of the data object.
+unsafe fn performed_drop_effect(data_object: &IDataObject) -> Option<DROPEFFECT> {
+    let format = RegisterClipboardFormatW(w!("Performed DropEffect"));
+    if format == 0 {
+        return None;
+    }
+    let format_etc = FORMATETC {
+        cfFormat: format as u16,
+        ptd: std::ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL.0 as u32,
+    };
+    let mut medium = data_object.GetData(&format_etc).ok()?;
+    let ptr = GlobalLock(medium.u.hGlobal) as *const u32;
+    let effect = (!ptr.is_null()).then(|| DROPEFFECT(ptr.read_unaligned()));
+    let _ = GlobalUnlock(medium.u.hGlobal);
+    ReleaseStgMedium(&mut medium);
+    effect
+}
+
 fn get_drag_image(image: Image) -> Option<SHDRAGIMAGE> {
     let hbitmap = match image {
         Image::Raw(bytes) => image::read_bytes_to_hbitmap(&bytes).ok(),
@@ -0,0 +1,237 @@
+// Copyright 2023-2023 CrabNebula Ltd.
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+use crate::{CursorPosition, DragResult, DropEffect, Options};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// Receives the [`DragEvent`]s of a drag, see [`Options::on_event`].
+pub type EventHandler = Arc<dyn Fn(DragEvent) + Send + Sync>;
+
+/// A step in the lifecycle of a drag. Every drag that starts successfully reports
+/// `Started` first and `Ended` last.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum DragEvent {
+    Started,
+    /// The cursor moved to a new position.
+    ///
+    /// - **macOS / Windows**: Not reported yet.
+    Moved(CursorPosition),
+    /// The cursor entered a window that takes drops, possibly ours.
+    ///
+    /// - **macOS / Windows**: Not reported yet.
+    EnteredTarget(CursorPosition),
+    Ended(DragOutcome),
+}
+
+/// How a drag ended.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct DragOutcome {
+    pub result: DragResult,
+    /// The operation the drop target performed, `None` if the drag was cancelled.
+    pub action: Option<DropEffect>,
+    /// Where the cursor was when the drag ended.
+    pub position: CursorPosition,
+    /// Time from the start of the drag to its end.
+    pub duration: Duration,
+}
+
+impl DragOutcome {
+    fn new(result: DragResult, position: CursorPosition, started: Instant) -> Self {
+        DragOutcome {
+            result,
+            action: match result {
+                DragResult::Dropped(effect) => Some(effect),
+                DragResult::Cancel => None,
+            },
+            position,
+            duration: started.elapsed(),
+        }
+    }
+}
+
+// Reports a drag's `Started` and `Ended` events around its drop callback. `Started`
+// waits for the backend to start the drag, and backends that run the whole drag
+// before returning (Windows) end it first, so an early end is held back until then.
+pub(crate) struct Session<F> {
+    tracked: Arc<Mutex<Tracked<F>>>,
+}
+
+struct Tracked<F> {
+    on_drop_callback: F,
+    on_event: Option<EventHandler>,
+    started_at: Instant,
+    started: bool,
+    early_end: Option<(DragResult, CursorPosition)>,
+}
+
+impl<F: Fn(DragResult, CursorPosition)> Tracked<F> {
+    fn end(&self, result: DragResult, position: CursorPosition) {
+        if let Some(on_event) = &self.on_event {
+            on_event(DragEvent::Ended(DragOutcome::new(
+                result,
+                position.clone(),
+                self.started_at,
+            )));
+        }
+        (self.on_drop_callback)(result, position);
+    }
+}
+
+impl<F: Fn(DragResult, CursorPosition) + Send + 'static> Session<F> {
+    pub(crate) fn new(options: &Options, on_drop_callback: F) -> Self {
+        Session {
+            tracked: Arc::new(Mutex::new(Tracked {
+                on_drop_callback,
+                on_event: options.on_event.clone(),
+                started_at: Instant::now(),
+                started: false,
+                early_end: None,
+            })),
+        }
+    }
+
+    /// The drop callback to hand to the backend.
+    pub(crate) fn callback(&self) -> impl Fn(DragResult, CursorPosition) + Send + 'static {
+        let tracked = self.tracked.clone();
+        move |result, position| {
+            let mut tracked = tracked.lock().unwrap();
+            if tracked.started {
+                tracked.end(result, position);
+            } else {
+                tracked.early_end = Some((result, position));
+            }
+        }
+    }
+
+    /// The backend started the drag: report it, and its end if that came first.
+    pub(crate) fn started(self) {
+        let mut tracked = self.tracked.lock().unwrap();
+        tracked.started = true;
+        if let Some(on_event) = &tracked.on_event {
+            on_event(DragEvent::Started);
+        }
+        if let Some((result, position)) = tracked.early_end.take() {
+            tracked.end(result, position);
+        }
+    }
+}
+
+#[derive(Default)]
+struct Shared {
+    outcome: Option<DragOutcome>,
+    waker: Option<Waker>,
+}
+
+/// Resolves to the [`DragOutcome`] when the drag ends. Returned by
+/// [`start_drag_async`](crate::start_drag_async).
+///
+/// The drag runs whether or not the future is polled; dropping it only discards the
+/// outcome.
+pub struct DragFuture {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl DragFuture {
+    // The future, and the drop callback that resolves it
+    pub(crate) fn new() -> (Self, impl Fn(DragResult, CursorPosition) + Send + 'static) {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let future = DragFuture {
+            shared: shared.clone(),
+        };
+        let started = Instant::now();
+        let callback = move |result, position| {
+            let mut shared = shared.lock().unwrap();
+            shared.outcome = Some(DragOutcome::new(result, position, started));
+            if let Some(waker) = shared.waker.take() {
+                waker.wake();
+            }
+        };
+        (future, callback)
+    }
+}
+
+impl Future for DragFuture {
+    type Output = DragOutcome;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<DragOutcome> {
+        let mut shared = self.shared.lock().unwrap();
+        match shared.outcome.take() {
+            Some(outcome) => Poll::Ready(outcome),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{start_drag_with, DragItem, Image, MockBackend, MockOutcome};
+
+    type Log = Arc<Mutex<Vec<&'static str>>>;
+
+    // Start a drag that logs its events and its drop callback
+    fn start(backend: &MockBackend, log: &Log) -> crate::Result<()> {
+        let events = log.clone();
+        let options = Options {
+            on_event: Some(Arc::new(move |event| {
+                events.lock().unwrap().push(match event {
+                    DragEvent::Started => "started",
+                    DragEvent::Ended(_) => "ended",
+                    DragEvent::Moved(_) | DragEvent::EnteredTarget(_) => "moved",
+                })
+            })),
+            ..Default::default()
+        };
+        let dropped = log.clone();
+        start_drag_with(
+            backend,
+            &(),
+            DragItem::Files(Vec::new()),
+            Image::Raw(Vec::new()),
+            move |_, _| dropped.lock().unwrap().push("dropped"),
+            options,
+        )
+    }
+
+    #[test]
+    fn drag_ended_by_the_backend_reports_start_first() {
+        let backend = MockBackend::new();
+        backend.push_outcome(MockOutcome::Dropped(DropEffect::Copy));
+        let log = Log::default();
+
+        start(&backend, &log).unwrap();
+        assert_eq!(*log.lock().unwrap(), ["started", "ended", "dropped"]);
+    }
+
+    #[test]
+    fn drag_in_progress_reports_end_later() {
+        let backend = MockBackend::new();
+        backend.push_outcome(MockOutcome::Pending);
+        let log = Log::default();
+
+        start(&backend, &log).unwrap();
+        assert_eq!(*log.lock().unwrap(), ["started"]);
+        assert!(backend.end_drag(DragResult::Cancel));
+        assert_eq!(*log.lock().unwrap(), ["started", "ended", "dropped"]);
+    }
+
+    #[test]
+    fn failed_drag_reports_nothing() {
+        let backend = MockBackend::new();
+        backend.push_outcome(MockOutcome::Failed);
+        let log = Log::default();
+
+        assert!(start(&backend, &log).is_err());
+        assert!(log.lock().unwrap().is_empty());
+    }
+}
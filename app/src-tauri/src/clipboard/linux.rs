@@ -0,0 +1,241 @@
+use super::{file_uris_to_paths, ClipboardContent};
+use gtk::prelude::*;
+use gtk::{Clipboard, TargetEntry, TargetFlags};
+use std::ffi::CString;
+use std::os::raw::{c_int, c_uchar, c_ulong};
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Once;
+use x11::xlib;
+
+const GNOME_COPIED_FILES: &str = "x-special/gnome-copied-files";
+const URI_LIST: &str = "text/uri-list";
+const UTF8_TEXT: &str = "text/plain;charset=utf-8";
+
+// X11 has no clipboard sequence number, so count GTK's owner-change signals instead
+static CHANGES: AtomicU64 = AtomicU64::new(0);
+static TRACK_CHANGES: Once = Once::new();
+
+fn clipboard() -> Clipboard {
+    Clipboard::get(&gdk::SELECTION_CLIPBOARD)
+}
+
+fn targets(clipboard: &Clipboard) -> Vec<String> {
+    clipboard
+        .wait_for_targets()
+        .unwrap_or_default()
+        .iter()
+        .map(|atom| atom.name().to_string())
+        .collect()
+}
+
+fn contents(clipboard: &Clipboard, target: &str) -> Option<Vec<u8>> {
+    let data = clipboard.wait_for_contents(&gdk::Atom::intern(target))?;
+    (data.length() > 0).then(|| data.data())
+}
+
+pub fn read() -> Result<Option<ClipboardContent>, String> {
+    let clipboard = clipboard();
+    let targets = targets(&clipboard);
+    let has = |target: &str| targets.iter().any(|t| t == target);
+
+    if has(GNOME_COPIED_FILES) || has(URI_LIST) {
+        let uris = clipboard.wait_for_uris();
+        let paths = file_uris_to_paths(uris.iter().map(|uri| uri.as_str()));
+        if !paths.is_empty() {
+            return Ok(Some(ClipboardContent::Files(paths)));
+        }
+    }
+
+    if let Some(png) = has("image/png")
+        .then(|| contents(&clipboard, "image/png"))
+        .flatten()
+    {
+        return Ok(Some(ClipboardContent::Image(png)));
+    }
+    if let Some(pixbuf) = clipboard.wait_for_image() {
+        let png = pixbuf
+            .save_to_bufferv("png", &[])
+            .map_err(|e| format!("Failed to encode clipboard image: {}", e))?;
+        return Ok(Some(ClipboardContent::Image(png)));
+    }
+
+    let text = clipboard.wait_for_text().map(|text| text.to_string());
+    if let Some(html) = has("text/html")
+        .then(|| contents(&clipboard, "text/html"))
+        .flatten()
+    {
+        return Ok(Some(ClipboardContent::Html {
+            html: decode_html(&html),
+            text,
+        }));
+    }
+
+    Ok(text
+        .filter(|text| !text.is_empty())
+        .map(ClipboardContent::Text))
+}
+
+// Firefox and Chromium put `text/html` on the clipboard (and in drags) as UTF-16 with a BOM
+pub(crate) fn decode_html(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFF, 0xFE, rest @ ..] => {
+            let units: Vec<u16> = rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+pub fn write_files(paths: &[PathBuf]) -> Result<(), String> {
+    let uris: Vec<String> = paths
+        .iter()
+        .filter_map(|path| reqwest::Url::from_file_path(path).ok())
+        .map(|url| url.to_string())
+        .collect();
+    if uris.is_empty() {
+        return Err("No valid file paths to copy".to_string());
+    }
+    // Nautilus and friends expect the operation on the first line
+    let gnome_files = format!("copy\n{}", uris.join("\n"));
+    let plain = paths
+        .iter()
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let targets = [
+        TargetEntry::new(GNOME_COPIED_FILES, TargetFlags::empty(), 0),
+        TargetEntry::new(URI_LIST, TargetFlags::empty(), 1),
+        TargetEntry::new(UTF8_TEXT, TargetFlags::empty(), 2),
+        TargetEntry::new("UTF8_STRING", TargetFlags::empty(), 2),
+    ];
+    let set = clipboard().set_with_data(&targets, move |_, selection, info| match info {
+        0 => selection.set(&selection.target(), 8, gnome_files.as_bytes()),
+        1 => {
+            let uris: Vec<&str> = uris.iter().map(String::as_str).collect();
+            selection.set_uris(&uris);
+        }
+        _ => {
+            selection.set_text(&plain);
+        }
+    });
+    if set {
+        Ok(())
+    } else {
+        Err("Failed to take ownership of the clipboard".to_string())
+    }
+}
+
+pub fn write_text(text: &str) -> Result<(), String> {
+    let clipboard = clipboard();
+    clipboard.set_text(text);
+    // Keep the text around after we quit if a clipboard manager is running
+    clipboard.store();
+    Ok(())
+}
+
+pub fn write_image(_rgba: &image::RgbaImage, png: &[u8]) -> Result<(), String> {
+    let png = png.to_vec();
+    let targets = [TargetEntry::new("image/png", TargetFlags::empty(), 0)];
+    let set = clipboard().set_with_data(&targets, move |_, selection, _| {
+        selection.set(&selection.target(), 8, &png);
+    });
+    if set {
+        Ok(())
+    } else {
+        Err("Failed to take ownership of the clipboard".to_string())
+    }
+}
+
+pub fn track_changes() {
+    TRACK_CHANGES.call_once(|| {
+        clipboard().connect_local("owner-change", false, |_| {
+            CHANGES.fetch_add(1, Ordering::Relaxed);
+            None
+        });
+    });
+}
+
+pub fn change_count() -> u64 {
+    CHANGES.load(Ordering::Relaxed)
+}
+
+// KDE's convention, also set by KeePassXC and Bitwarden
+pub fn is_concealed() -> bool {
+    targets(&clipboard())
+        .iter()
+        .any(|target| target == "x-kde-passwordManagerHint")
+}
+
+// X11 selections only name a window, so the owner is the process in the window's
+// `_NET_WM_PID`, or in that of its client leader (toolkits own selections from hidden
+// windows). Wayland-native apps have no X window and are never found.
+pub fn owner_app() -> Option<String> {
+    unsafe {
+        let display = xlib::XOpenDisplay(ptr::null());
+        if display.is_null() {
+            return None;
+        }
+        let pid = selection_owner_pid(display);
+        xlib::XCloseDisplay(display);
+        crate::utils::process_name(pid?)
+    }
+}
+
+unsafe fn selection_owner_pid(display: *mut xlib::Display) -> Option<u32> {
+    let owner = xlib::XGetSelectionOwner(display, intern(display, "CLIPBOARD"));
+    if owner == 0 {
+        return None;
+    }
+    let net_wm_pid = intern(display, "_NET_WM_PID");
+    let pid = cardinal_property(display, owner, net_wm_pid, xlib::XA_CARDINAL).or_else(|| {
+        let leader = intern(display, "WM_CLIENT_LEADER");
+        let leader = cardinal_property(display, owner, leader, xlib::XA_WINDOW)?;
+        cardinal_property(display, leader, net_wm_pid, xlib::XA_CARDINAL)
+    })?;
+    Some(pid as u32)
+}
+
+unsafe fn intern(display: *mut xlib::Display, name: &str) -> xlib::Atom {
+    let name = CString::new(name).unwrap();
+    xlib::XInternAtom(display, name.as_ptr(), xlib::False)
+}
+
+// The first value of a 32-bit window property
+unsafe fn cardinal_property(
+    display: *mut xlib::Display,
+    window: xlib::Window,
+    property: xlib::Atom,
+    kind: xlib::Atom,
+) -> Option<c_ulong> {
+    let mut actual_type = 0;
+    let mut actual_format = 0;
+    let (mut items, mut bytes_after) = (0, 0);
+    let mut data: *mut c_uchar = ptr::null_mut();
+    let status = xlib::XGetWindowProperty(
+        display,
+        window,
+        property,
+        0,
+        1,
+        xlib::False,
+        kind,
+        &mut actual_type,
+        &mut actual_format,
+        &mut items,
+        &mut bytes_after,
+        &mut data,
+    );
+    if status != xlib::Success as c_int || data.is_null() {
+        return None;
+    }
+    // Xlib hands out 32-bit properties as longs
+    let value = (actual_format == 32 && items > 0).then(|| *(data as *const c_ulong));
+    xlib::XFree(data as *mut _);
+    value
+}
@@ -0,0 +1,155 @@
+use super::{file_uris_to_paths, to_png, ClipboardContent};
+use objc2::rc::Retained;
+use objc2::runtime::ProtocolObject;
+use objc2_app_kit::{NSPasteboard, NSPasteboardItem, NSPasteboardWriting, NSWorkspace};
+use objc2_foundation::{NSArray, NSData, NSString};
+use std::path::PathBuf;
+
+const FILE_URL: &str = "public.file-url";
+const PNG: &str = "public.png";
+const TIFF: &str = "public.tiff";
+const HTML: &str = "public.html";
+const TEXT: &str = "public.utf8-plain-text";
+
+fn pasteboard() -> Retained<NSPasteboard> {
+    NSPasteboard::generalPasteboard()
+}
+
+fn has_type(pasteboard: &NSPasteboard, pasteboard_type: &str) -> bool {
+    let pasteboard_type = NSString::from_str(pasteboard_type);
+    let types = NSArray::from_slice(&[&*pasteboard_type]);
+    pasteboard.availableTypeFromArray(&types).is_some()
+}
+
+fn data(pasteboard: &NSPasteboard, pasteboard_type: &str) -> Option<Vec<u8>> {
+    pasteboard
+        .dataForType(&NSString::from_str(pasteboard_type))
+        .map(|data| data.to_vec())
+}
+
+fn string(pasteboard: &NSPasteboard, pasteboard_type: &str) -> Option<String> {
+    pasteboard
+        .stringForType(&NSString::from_str(pasteboard_type))
+        .map(|string| string.to_string())
+}
+
+pub fn read() -> Result<Option<ClipboardContent>, String> {
+    let pasteboard = pasteboard();
+
+    if has_type(&pasteboard, FILE_URL) {
+        // Each copied file is its own pasteboard item
+        let file_url_type = NSString::from_str(FILE_URL);
+        let uris: Vec<String> = pasteboard
+            .pasteboardItems()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.stringForType(&file_url_type))
+                    .map(|uri| uri.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let paths = file_uris_to_paths(uris.iter().map(String::as_str));
+        if !paths.is_empty() {
+            return Ok(Some(ClipboardContent::Files(paths)));
+        }
+    }
+
+    if let Some(png) = data(&pasteboard, PNG) {
+        return Ok(Some(ClipboardContent::Image(png)));
+    }
+    if let Some(png) =
+        data(&pasteboard, TIFF).and_then(|tiff| to_png(&tiff, image::ImageFormat::Tiff))
+    {
+        return Ok(Some(ClipboardContent::Image(png)));
+    }
+
+    let text = string(&pasteboard, TEXT);
+    if let Some(html) = string(&pasteboard, HTML) {
+        return Ok(Some(ClipboardContent::Html { html, text }));
+    }
+
+    Ok(text
+        .filter(|text| !text.is_empty())
+        .map(ClipboardContent::Text))
+}
+
+fn write_items(items: Vec<Retained<NSPasteboardItem>>) -> Result<(), String> {
+    let pasteboard = pasteboard();
+    pasteboard.clearContents();
+    let objects: Vec<Retained<ProtocolObject<dyn NSPasteboardWriting>>> = items
+        .into_iter()
+        .map(ProtocolObject::from_retained)
+        .collect();
+    if pasteboard.writeObjects(&NSArray::from_retained_slice(&objects)) {
+        Ok(())
+    } else {
+        Err("Failed to write to the pasteboard".to_string())
+    }
+}
+
+pub fn write_files(paths: &[PathBuf]) -> Result<(), String> {
+    let file_url_type = NSString::from_str(FILE_URL);
+    let text_type = NSString::from_str(TEXT);
+    let items = paths
+        .iter()
+        .filter_map(|path| reqwest::Url::from_file_path(path).ok())
+        .map(|url| {
+            let item = NSPasteboardItem::new();
+            item.setString_forType(&NSString::from_str(url.as_str()), &file_url_type);
+            item
+        })
+        .collect::<Vec<_>>();
+    if items.is_empty() {
+        return Err("No valid file paths to copy".to_string());
+    }
+    // Text editors get the paths instead of the file contents
+    if let Some(first) = items.first() {
+        let paths = paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        first.setString_forType(&NSString::from_str(&paths), &text_type);
+    }
+    write_items(items)
+}
+
+pub fn write_text(text: &str) -> Result<(), String> {
+    let item = NSPasteboardItem::new();
+    item.setString_forType(&NSString::from_str(text), &NSString::from_str(TEXT));
+    write_items(vec![item])
+}
+
+pub fn write_image(_rgba: &image::RgbaImage, png: &[u8]) -> Result<(), String> {
+    let item = NSPasteboardItem::new();
+    item.setData_forType(&NSData::with_bytes(png), &NSString::from_str(PNG));
+    write_items(vec![item])
+}
+
+pub fn track_changes() {}
+
+pub fn change_count() -> u64 {
+    pasteboard().changeCount() as u64
+}
+
+// See http://nspasteboard.org: password managers mark their entries so clipboard
+// tools leave them alone
+pub fn is_concealed() -> bool {
+    let pasteboard = pasteboard();
+    [
+        "org.nspasteboard.ConcealedType",
+        "org.nspasteboard.TransientType",
+        "org.nspasteboard.AutoGeneratedType",
+    ]
+    .iter()
+    .any(|pasteboard_type| has_type(&pasteboard, pasteboard_type))
+}
+
+// The pasteboard doesn't record its writer; the frontmost app is almost always it
+pub fn owner_app() -> Option<String> {
+    let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+    app.localizedName()
+        .or_else(|| app.bundleIdentifier())
+        .map(|name| name.to_string())
+}
@@ -0,0 +1,138 @@
+// Native system clipboard access, so shelf items can be copied out and clipboard
+// content pulled in without going through the webview.
+//
+// All functions must be called on the main thread (GTK requires it on Linux), which
+// is where Tauri runs synchronous commands.
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod mac;
+#[cfg(target_os = "windows")]
+mod win;
+
+pub mod watcher;
+
+#[cfg(target_os = "linux")]
+use linux as platform;
+#[cfg(target_os = "linux")]
+pub(crate) use linux::decode_html;
+#[cfg(target_os = "macos")]
+use mac as platform;
+#[cfg(target_os = "windows")]
+use win as platform;
+#[cfg(target_os = "windows")]
+pub(crate) use win::dib_to_bmp;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set when we write to the clipboard ourselves, so the watcher doesn't collect it
+static OWN_WRITE: AtomicBool = AtomicBool::new(false);
+
+/// The richest thing currently on the clipboard.
+#[derive(Debug, Clone, Hash)]
+pub enum ClipboardContent {
+    /// Files and folders copied in a file manager
+    Files(Vec<PathBuf>),
+    /// Image data, re-encoded as PNG
+    Image(Vec<u8>),
+    /// Rich text; `html` is the raw platform payload (CF_HTML on Windows)
+    Html {
+        html: String,
+        text: Option<String>,
+    },
+    Text(String),
+}
+
+/// Read the clipboard, preferring files over images over HTML over plain text.
+/// Returns `None` when the clipboard is empty or holds nothing we understand.
+pub fn read() -> Result<Option<ClipboardContent>, String> {
+    platform::read()
+}
+
+/// Put files on the clipboard the way file managers do, so they can be pasted
+/// into one.
+pub fn write_files(paths: &[PathBuf]) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("No files to copy".to_string());
+    }
+    own_write(|| platform::write_files(paths))
+}
+
+pub fn write_text(text: &str) -> Result<(), String> {
+    own_write(|| platform::write_text(text))
+}
+
+/// Put an image on the clipboard. `bytes` can be in any format the `image` crate
+/// decodes; it is offered as PNG (and as a bitmap on Windows).
+pub fn write_image(bytes: &[u8]) -> Result<(), String> {
+    let image = image::load_from_memory(bytes).map_err(|e| format!("Invalid image: {}", e))?;
+    let png = encode_png(&image)?;
+    own_write(|| platform::write_image(&image.to_rgba8(), &png))
+}
+
+/// Start counting clipboard changes where the platform has no sequence number of
+/// its own (GTK). Safe to call more than once.
+pub fn track_changes() {
+    platform::track_changes()
+}
+
+/// A number that changes whenever the clipboard contents change. Cheap, and callable
+/// from any thread.
+pub fn change_count() -> u64 {
+    platform::change_count()
+}
+
+/// Whether the current content is marked as sensitive or not meant for clipboard
+/// history (password managers set these hints).
+pub fn is_concealed() -> bool {
+    platform::is_concealed()
+}
+
+/// Best guess at the name of the application that put the current content on the
+/// clipboard.
+pub fn owner_app() -> Option<String> {
+    platform::owner_app()
+}
+
+// Flag the write before making it, so the watcher can't see the change first
+fn own_write(write: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    OWN_WRITE.store(true, Ordering::Relaxed);
+    let result = write();
+    if result.is_err() {
+        OWN_WRITE.store(false, Ordering::Relaxed);
+    }
+    result
+}
+
+// Whether the last change was made by us; resets the flag
+fn take_own_write() -> bool {
+    OWN_WRITE.swap(false, Ordering::Relaxed)
+}
+
+fn encode_png(image: &image::DynamicImage) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(png)
+}
+
+// Convert image data in another format (TIFF on macOS, BMP on Windows) to PNG
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn to_png(bytes: &[u8], format: image::ImageFormat) -> Option<Vec<u8>> {
+    let image = image::load_from_memory_with_format(bytes, format).ok()?;
+    encode_png(&image).ok()
+}
+
+// Turn `file://` URIs from a URI list into local paths, skipping anything else
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+pub(crate) fn file_uris_to_paths<'a>(uris: impl IntoIterator<Item = &'a str>) -> Vec<PathBuf> {
+    uris.into_iter()
+        .map(str::trim)
+        .filter(|uri| !uri.is_empty() && !uri.starts_with('#'))
+        .filter_map(|uri| reqwest::Url::parse(uri).ok())
+        .filter_map(|url| url.to_file_path().ok())
+        .collect()
+}
@@ -0,0 +1,207 @@
+// Opt-in clipboard watcher that collects every new clipboard entry into the
+// clipboard shelf, a separate list next to the main shelf.
+//
+// A background thread polls the platform's change counter; the clipboard itself is
+// only read on the main thread, and only after checking the concealed-type hints
+// and the ignored apps.
+
+use super::ClipboardContent;
+use crate::commands::clipboard_ops::content_items;
+use crate::config::AppConfig;
+use crate::shelf::{next_id, ShelfItem};
+use crate::utils::same_app_name;
+use crate::FileList;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{debug, info, warn};
+
+// How many recent entries are remembered to skip repeated copies
+const RECENT_ENTRIES: usize = 50;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Items collected from the clipboard, newest last.
+#[derive(Default)]
+pub struct ClipboardShelf {
+    pub items: FileList,
+    recent: Mutex<VecDeque<u64>>,
+}
+
+impl ClipboardShelf {
+    pub fn clear(&self) -> Result<(), String> {
+        self.items
+            .lock()
+            .map_err(|_| "Failed to acquire lock".to_string())?
+            .clear();
+        if let Ok(mut recent) = self.recent.lock() {
+            recent.clear();
+        }
+        Ok(())
+    }
+
+    // Remember the content's hash; false if it was collected recently
+    fn remember(&self, content: &ClipboardContent) -> bool {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let Ok(mut recent) = self.recent.lock() else {
+            return true;
+        };
+        if recent.contains(&hash) {
+            return false;
+        }
+        if recent.len() == RECENT_ENTRIES {
+            recent.pop_front();
+        }
+        recent.push_back(hash);
+        true
+    }
+
+    fn push(&self, items: Vec<ShelfItem>, max_items: usize) -> Result<(), String> {
+        let mut list = self
+            .items
+            .lock()
+            .map_err(|_| "Failed to acquire lock".to_string())?;
+        for mut item in items {
+            item.set_id(next_id(&list));
+            list.push(item);
+        }
+        let excess = list.len().saturating_sub(max_items.max(1));
+        list.drain(..excess);
+        Ok(())
+    }
+}
+
+/// Owns the polling thread; at most one runs at a time.
+#[derive(Default)]
+pub struct ClipboardWatcher {
+    stop: Mutex<Option<Arc<AtomicBool>>>,
+}
+
+impl ClipboardWatcher {
+    /// Start or stop the watcher to match the configuration.
+    pub fn apply(&self, app: &AppHandle, enabled: bool) {
+        if enabled {
+            self.start(app);
+        } else {
+            self.stop();
+        }
+    }
+
+    fn start(&self, app: &AppHandle) {
+        let Ok(mut stop) = self.stop.lock() else {
+            return;
+        };
+        if stop.is_some() {
+            return;
+        }
+        info!("Starting clipboard watcher");
+
+        let _ = app.run_on_main_thread(super::track_changes);
+        let flag = Arc::new(AtomicBool::new(false));
+        *stop = Some(flag.clone());
+        let app = app.clone();
+        thread::spawn(move || watch(app, flag));
+    }
+
+    fn stop(&self) {
+        if let Some(flag) = self.stop.lock().ok().and_then(|mut stop| stop.take()) {
+            info!("Stopping clipboard watcher");
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+fn watch(app: AppHandle, stop: Arc<AtomicBool>) {
+    let mut last_count = super::change_count();
+
+    while !stop.load(Ordering::Relaxed) {
+        let Some(config) = current_config(&app) else {
+            break;
+        };
+        thread::sleep(Duration::from_millis(
+            config.clipboard_watch.poll_interval_ms.max(50),
+        ));
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+
+        let count = super::change_count();
+        if count == last_count {
+            continue;
+        }
+        last_count = count;
+        if super::take_own_write() {
+            continue;
+        }
+
+        match read_on_main_thread(&app, config.clipboard_watch.ignored_apps.clone()) {
+            Ok(Some(content)) => {
+                if let Err(e) = collect(&app, content, &config) {
+                    warn!("Failed to collect clipboard content: {}", e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to read clipboard: {}", e),
+        }
+    }
+}
+
+fn current_config(app: &AppHandle) -> Option<AppConfig> {
+    let config = app.try_state::<Arc<Mutex<AppConfig>>>()?;
+    let config = config.lock().ok()?;
+    Some(config.clone())
+}
+
+fn read_on_main_thread(
+    app: &AppHandle,
+    ignored_apps: Vec<String>,
+) -> Result<Option<ClipboardContent>, String> {
+    let (tx, rx) = mpsc::channel();
+    app.run_on_main_thread(move || {
+        let _ = tx.send(read_unless_ignored(&ignored_apps));
+    })
+    .map_err(|e| e.to_string())?;
+    rx.recv_timeout(READ_TIMEOUT)
+        .map_err(|_| "Timed out reading the clipboard".to_string())?
+}
+
+fn read_unless_ignored(ignored_apps: &[String]) -> Result<Option<ClipboardContent>, String> {
+    if super::is_concealed() {
+        debug!("Skipping concealed clipboard content");
+        return Ok(None);
+    }
+    if let Some(owner) = super::owner_app() {
+        if ignored_apps
+            .iter()
+            .any(|app| same_app_name(&owner, app.trim()))
+        {
+            debug!("Skipping clipboard content from ignored app {}", owner);
+            return Ok(None);
+        }
+    }
+    super::read()
+}
+
+fn collect(app: &AppHandle, content: ClipboardContent, config: &AppConfig) -> Result<(), String> {
+    let shelf = app.state::<ClipboardShelf>();
+    if !shelf.remember(&content) {
+        debug!("Skipping repeated clipboard content");
+        return Ok(());
+    }
+
+    let items = content_items(content, config)?;
+    if items.is_empty() {
+        return Ok(());
+    }
+    info!("Collected {} item(s) from the clipboard", items.len());
+    shelf.push(items, config.clipboard_watch.max_items)?;
+    app.emit("clipboard_shelf_updated", ())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,289 @@
+use super::{to_png, ClipboardContent};
+use std::ffi::OsString;
+use std::os::windows::ffi::{OsStrExt, OsStringExt};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{GlobalFree, HANDLE, HGLOBAL};
+use windows::Win32::System::DataExchange::{
+    CloseClipboard, EmptyClipboard, GetClipboardData, GetClipboardOwner,
+    GetClipboardSequenceNumber, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+};
+use windows::Win32::System::Memory::{
+    GlobalAlloc, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+};
+use windows::Win32::UI::Shell::{DragQueryFileW, HDROP};
+use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+const CF_UNICODETEXT: u32 = 13;
+const CF_HDROP: u32 = 15;
+const CF_DIB: u32 = 8;
+const DROPEFFECT_COPY: u32 = 1;
+
+// Another process may hold the clipboard open for a moment, so retry briefly
+struct OpenClipboardGuard;
+
+impl OpenClipboardGuard {
+    fn open() -> Result<Self, String> {
+        for _ in 0..10 {
+            if unsafe { OpenClipboard(None) }.is_ok() {
+                return Ok(Self);
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        Err("The clipboard is in use by another application".to_string())
+    }
+}
+
+impl Drop for OpenClipboardGuard {
+    fn drop(&mut self) {
+        let _ = unsafe { CloseClipboard() };
+    }
+}
+
+fn register_format(name: PCWSTR) -> Option<u32> {
+    let format = unsafe { RegisterClipboardFormatW(name) };
+    (format != 0).then_some(format)
+}
+
+// Copy the data of a clipboard format out of its global memory block
+unsafe fn get_data(format: u32) -> Option<Vec<u8>> {
+    let handle = GetClipboardData(format).ok()?;
+    let hglobal = HGLOBAL(handle.0);
+    let ptr = GlobalLock(hglobal) as *const u8;
+    if ptr.is_null() {
+        return None;
+    }
+    let bytes = std::slice::from_raw_parts(ptr, GlobalSize(hglobal)).to_vec();
+    let _ = GlobalUnlock(hglobal);
+    Some(bytes)
+}
+
+// Hand a copy of `bytes` to the clipboard, which takes ownership of the memory only
+// when it accepts it
+unsafe fn set_data(format: u32, bytes: &[u8]) -> Result<(), String> {
+    let hglobal = GlobalAlloc(GMEM_MOVEABLE, bytes.len()).map_err(|e| e.to_string())?;
+    let ptr = GlobalLock(hglobal) as *mut u8;
+    if ptr.is_null() {
+        let _ = GlobalFree(Some(hglobal));
+        return Err("Failed to lock clipboard memory".to_string());
+    }
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+    let _ = GlobalUnlock(hglobal);
+    if let Err(e) = SetClipboardData(format, Some(HANDLE(hglobal.0))) {
+        let _ = GlobalFree(Some(hglobal));
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+pub fn read() -> Result<Option<ClipboardContent>, String> {
+    let _guard = OpenClipboardGuard::open()?;
+
+    unsafe {
+        if let Ok(handle) = GetClipboardData(CF_HDROP) {
+            let paths = read_hdrop(HDROP(handle.0));
+            if !paths.is_empty() {
+                return Ok(Some(ClipboardContent::Files(paths)));
+            }
+        }
+
+        // Browsers and Office put the original PNG next to the bitmap
+        if let Some(png) = register_format(w!("PNG")).and_then(|format| get_data(format)) {
+            return Ok(Some(ClipboardContent::Image(png)));
+        }
+        if let Some(png) = get_data(CF_DIB)
+            .and_then(|dib| dib_to_bmp(&dib))
+            .and_then(|bmp| to_png(&bmp, image::ImageFormat::Bmp))
+        {
+            return Ok(Some(ClipboardContent::Image(png)));
+        }
+
+        let text = get_data(CF_UNICODETEXT).map(|bytes| utf16_bytes_to_string(&bytes));
+        if let Some(html) = register_format(w!("HTML Format")).and_then(|format| get_data(format)) {
+            let end = html.iter().position(|&b| b == 0).unwrap_or(html.len());
+            return Ok(Some(ClipboardContent::Html {
+                html: String::from_utf8_lossy(&html[..end]).into_owned(),
+                text,
+            }));
+        }
+
+        Ok(text
+            .filter(|text| !text.is_empty())
+            .map(ClipboardContent::Text))
+    }
+}
+
+unsafe fn read_hdrop(hdrop: HDROP) -> Vec<PathBuf> {
+    let count = DragQueryFileW(hdrop, 0xFFFFFFFF, None);
+    let mut paths = Vec::new();
+    for i in 0..count {
+        let len = DragQueryFileW(hdrop, i, None);
+        if len == 0 {
+            continue;
+        }
+        let mut buffer: Vec<u16> = vec![0; (len + 1) as usize];
+        DragQueryFileW(hdrop, i, Some(&mut buffer));
+        buffer.truncate(len as usize);
+        paths.push(PathBuf::from(OsString::from_wide(&buffer)));
+    }
+    paths
+}
+
+fn utf16_bytes_to_string(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|&unit| unit != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+// CF_DIB is a BMP file without its 14 byte file header; put one in front so the
+// `image` crate can decode it
+pub(crate) fn dib_to_bmp(dib: &[u8]) -> Option<Vec<u8>> {
+    let read_u32 = |offset: usize| -> Option<u32> {
+        Some(u32::from_le_bytes(
+            dib.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    let header_size = read_u32(0)?;
+    let bit_count = u16::from_le_bytes(dib.get(14..16)?.try_into().ok()?);
+    let compression = read_u32(16)?;
+    let colors_used = read_u32(32)?;
+
+    const BI_BITFIELDS: u32 = 3;
+    let masks = if compression == BI_BITFIELDS && header_size == 40 {
+        12
+    } else {
+        0
+    };
+    let palette = match colors_used {
+        0 if bit_count <= 8 => (1u32 << bit_count) * 4,
+        n => n * 4,
+    };
+    let pixel_offset = 14 + header_size + masks + palette;
+    let file_size = 14 + dib.len() as u32;
+
+    let mut bmp = Vec::with_capacity(file_size as usize);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&file_size.to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&pixel_offset.to_le_bytes());
+    bmp.extend_from_slice(dib);
+    Some(bmp)
+}
+
+// 32-bit bottom-up BGRA bitmap with a BITMAPINFOHEADER, for apps that only
+// understand CF_DIB
+fn rgba_to_dib(rgba: &image::RgbaImage) -> Vec<u8> {
+    let (width, height) = rgba.dimensions();
+    let mut dib = Vec::with_capacity(40 + (width * height * 4) as usize);
+    dib.extend_from_slice(&40u32.to_le_bytes()); // biSize
+    dib.extend_from_slice(&(width as i32).to_le_bytes());
+    dib.extend_from_slice(&(height as i32).to_le_bytes()); // positive: bottom-up rows
+    dib.extend_from_slice(&1u16.to_le_bytes()); // biPlanes
+    dib.extend_from_slice(&32u16.to_le_bytes()); // biBitCount
+    dib.extend_from_slice(&0u32.to_le_bytes()); // BI_RGB
+    dib.extend_from_slice(&(width * height * 4).to_le_bytes());
+    dib.extend_from_slice(&[0; 16]); // resolution and palette fields
+    for row in rgba.rows().rev() {
+        for pixel in row {
+            let [r, g, b, a] = pixel.0;
+            dib.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    dib
+}
+
+pub fn write_files(paths: &[PathBuf]) -> Result<(), String> {
+    // DROPFILES header: offset of the file list, drop point, non-client flag, wide chars
+    let mut data = Vec::new();
+    data.extend_from_slice(&20u32.to_le_bytes());
+    data.extend_from_slice(&[0; 12]);
+    data.extend_from_slice(&1u32.to_le_bytes());
+    // Double NUL terminated list of NUL terminated paths
+    for path in paths {
+        for unit in path.as_os_str().encode_wide().chain(Some(0)) {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+    data.extend_from_slice(&0u16.to_le_bytes());
+
+    let _guard = OpenClipboardGuard::open()?;
+    unsafe {
+        EmptyClipboard().map_err(|e| e.to_string())?;
+        set_data(CF_HDROP, &data)?;
+        // Tell Explorer this is a copy, not a cut
+        if let Some(format) = register_format(w!("Preferred DropEffect")) {
+            set_data(format, &DROPEFFECT_COPY.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_text(text: &str) -> Result<(), String> {
+    let data: Vec<u8> = text
+        .encode_utf16()
+        .chain(Some(0))
+        .flat_map(u16::to_le_bytes)
+        .collect();
+
+    let _guard = OpenClipboardGuard::open()?;
+    unsafe {
+        EmptyClipboard().map_err(|e| e.to_string())?;
+        set_data(CF_UNICODETEXT, &data)
+    }
+}
+
+pub fn write_image(rgba: &image::RgbaImage, png: &[u8]) -> Result<(), String> {
+    let dib = rgba_to_dib(rgba);
+
+    let _guard = OpenClipboardGuard::open()?;
+    unsafe {
+        EmptyClipboard().map_err(|e| e.to_string())?;
+        set_data(CF_DIB, &dib)?;
+        // Keeps transparency for apps that understand it
+        if let Some(format) = register_format(w!("PNG")) {
+            set_data(format, png)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn track_changes() {}
+
+pub fn change_count() -> u64 {
+    unsafe { GetClipboardSequenceNumber() as u64 }
+}
+
+// Formats password managers and other sensitive sources set to keep clipboard
+// history and monitors away
+pub fn is_concealed() -> bool {
+    let Ok(_guard) = OpenClipboardGuard::open() else {
+        return false;
+    };
+    unsafe {
+        let present = |name: PCWSTR| {
+            register_format(name).is_some_and(|format| GetClipboardData(format).is_ok())
+        };
+        if present(w!("ExcludeClipboardContentFromMonitorProcessing"))
+            || present(w!("Clipboard Viewer Ignore"))
+        {
+            return true;
+        }
+        register_format(w!("CanIncludeInClipboardHistory"))
+            .and_then(|format| get_data(format))
+            .is_some_and(|value| value.iter().all(|&b| b == 0))
+    }
+}
+
+pub fn owner_app() -> Option<String> {
+    unsafe {
+        let hwnd = GetClipboardOwner().ok()?;
+        let mut process_id = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
+        crate::utils::process_name(process_id)
+    }
+}
@@ -0,0 +1,248 @@
+use super::file_ops::{link_format, materialize_shelf_item};
+use crate::clipboard::watcher::{ClipboardShelf, ClipboardWatcher};
+use crate::clipboard::{self, ClipboardContent};
+use crate::config::AppConfig;
+use crate::file::staging_dir;
+use crate::file_drop::{add_items_to_shelf, add_paths_to_shelf};
+use crate::ingest::html as html_ingest;
+use crate::ingest::links;
+use crate::ingest::text::{self as text_ingest, TextFormat};
+use crate::shelf::{ImageItem, ShelfItem, TextItem, UrlItem};
+use crate::FileList;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, State};
+use tracing::info;
+
+/// How shelf items are put on the clipboard.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClipboardFormat {
+    /// As files, for pasting into a file manager
+    Files,
+    /// As text: snippet contents, URLs, or file paths
+    Text,
+    /// As image data (only the first item is used)
+    Image,
+}
+
+#[tauri::command]
+pub fn copy_items_to_clipboard(
+    file_list: State<'_, FileList>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    item_ids: Vec<u64>,
+    format: ClipboardFormat,
+) -> Result<(), String> {
+    if item_ids.is_empty() {
+        return Err("No items selected".to_string());
+    }
+    info!(
+        "Copying {} item(s) to clipboard as {:?}",
+        item_ids.len(),
+        format
+    );
+
+    match format {
+        ClipboardFormat::Files => {
+            let link_format = link_format(&config)?;
+            let paths = item_ids
+                .iter()
+                .map(|&id| materialize_shelf_item(&file_list, id, link_format))
+                .collect::<Result<Vec<_>, _>>()?;
+            clipboard::write_files(&paths)
+        }
+        ClipboardFormat::Text => {
+            let list = file_list
+                .lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?;
+            let text = item_ids
+                .iter()
+                .map(|&id| {
+                    let item = list
+                        .iter()
+                        .find(|item| item.id() == id)
+                        .ok_or_else(|| format!("Item with ID {} not found", id))?;
+                    Ok(match item {
+                        ShelfItem::Text(text) => text.text.clone(),
+                        ShelfItem::Url(url) => url.url.clone(),
+                        item => item
+                            .path()
+                            .map(|path| path.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| item.name().to_string()),
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()?
+                .join("\n");
+            drop(list);
+            clipboard::write_text(&text)
+        }
+        ClipboardFormat::Image => {
+            let id = item_ids[0];
+            let bytes = {
+                let list = file_list
+                    .lock()
+                    .map_err(|_| "Failed to acquire lock".to_string())?;
+                match list.iter().find(|item| item.id() == id) {
+                    Some(ShelfItem::Image(image)) => image.bytes.clone(),
+                    Some(item) => {
+                        let path = item
+                            .path()
+                            .ok_or_else(|| format!("Item {} is not an image", id))?;
+                        std::fs::read(path).map_err(|e| e.to_string())?
+                    }
+                    None => return Err(format!("Item with ID {} not found", id)),
+                }
+            };
+            clipboard::write_image(&bytes)
+        }
+    }
+}
+
+/// Add whatever is on the system clipboard to the shelf and return the ids of the new
+/// items.
+#[tauri::command]
+pub fn paste_from_clipboard(
+    app_handle: AppHandle,
+    file_list: State<'_, FileList>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+) -> Result<Vec<u64>, String> {
+    let content = clipboard::read()?.ok_or("The clipboard is empty")?;
+    let config = config
+        .lock()
+        .map_err(|_| "Failed to acquire config lock".to_string())?
+        .clone();
+    let _ = app_handle.emit("file_added", ());
+
+    let added = match content {
+        ClipboardContent::Files(paths) => {
+            info!("Pasting {} file(s) from clipboard", paths.len());
+            add_paths_to_shelf(&paths, &file_list, &app_handle)?
+        }
+        content => add_items_to_shelf(content_items(content, &config)?, &file_list, &app_handle)?,
+    };
+
+    Ok(added.iter().map(ShelfItem::id).collect())
+}
+
+/// Turn clipboard content into shelf items: files are referenced in place, images
+/// and text are kept in memory and HTML is saved according to the HTML ingestion
+/// settings.
+pub(crate) fn content_items(
+    content: ClipboardContent,
+    config: &AppConfig,
+) -> Result<Vec<ShelfItem>, String> {
+    let paths = match content {
+        ClipboardContent::Files(paths) => paths,
+        ClipboardContent::Image(png) => {
+            let name = format!("clipboard_{}", chrono::Local::now().format("%H%M%S"));
+            let image = ImageItem::new(name, "image/png".to_string(), png, "png".to_string());
+            return Ok(vec![ShelfItem::Image(image)]);
+        }
+        ClipboardContent::Html { html, text } => {
+            let html_config = &config.html_ingest;
+            if !html_config.save_html && !html_config.save_markdown {
+                return Ok(text_items(&text.unwrap_or(html), config));
+            }
+            let fragment = html_ingest::parse_cf_html(&html);
+            html_ingest::save_fragment(
+                &fragment,
+                &staging_dir()?,
+                html_config.save_html,
+                html_config.save_markdown,
+            )?
+        }
+        ClipboardContent::Text(text) => return Ok(text_items(&text, config)),
+    };
+
+    paths
+        .iter()
+        .map(|path| ShelfItem::from_path(path).map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_clipboard_shelf(shelf: State<'_, ClipboardShelf>) -> Result<Vec<ShelfItem>, String> {
+    let items = shelf
+        .items
+        .lock()
+        .map_err(|_| "Failed to acquire lock".to_string())?;
+    Ok(items.clone())
+}
+
+#[tauri::command]
+pub fn clear_clipboard_shelf(
+    app_handle: AppHandle,
+    shelf: State<'_, ClipboardShelf>,
+) -> Result<(), String> {
+    shelf.clear()?;
+    app_handle
+        .emit("clipboard_shelf_updated", ())
+        .map_err(|e| e.to_string())
+}
+
+/// Move collected items onto the main shelf and return their new ids there.
+#[tauri::command]
+pub fn move_clipboard_items_to_shelf(
+    app_handle: AppHandle,
+    file_list: State<'_, FileList>,
+    shelf: State<'_, ClipboardShelf>,
+    item_ids: Vec<u64>,
+) -> Result<Vec<u64>, String> {
+    let items = {
+        let mut collected = shelf
+            .items
+            .lock()
+            .map_err(|_| "Failed to acquire lock".to_string())?;
+        let (moved, kept): (Vec<_>, Vec<_>) = collected
+            .drain(..)
+            .partition(|item| item_ids.contains(&item.id()));
+        *collected = kept;
+        moved
+    };
+    let added = add_items_to_shelf(items, &file_list, &app_handle)?;
+    let _ = app_handle.emit("clipboard_shelf_updated", ());
+    Ok(added.iter().map(ShelfItem::id).collect())
+}
+
+/// Turn the clipboard watcher on or off and persist the choice.
+#[tauri::command]
+pub fn set_clipboard_watch(
+    app_handle: AppHandle,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    watcher: State<'_, ClipboardWatcher>,
+    enabled: bool,
+) -> Result<(), String> {
+    {
+        let mut config = config
+            .lock()
+            .map_err(|_| "Failed to acquire config lock".to_string())?;
+        config.clipboard_watch.enabled = enabled;
+        config.save(&app_handle)?;
+    }
+    watcher.apply(&app_handle, enabled);
+    #[cfg(desktop)]
+    crate::tray::set_clipboard_watch_checked(&app_handle, enabled);
+    Ok(())
+}
+
+// Copied links become URL items, anything else a text snippet
+fn text_items(text: &str, config: &AppConfig) -> Vec<ShelfItem> {
+    if let Some(urls) = links::text_as_urls(text) {
+        return urls
+            .into_iter()
+            .map(|url| ShelfItem::Url(UrlItem::new(url.to_string(), None)))
+            .collect();
+    }
+
+    let format = if config.pasted_text.detect_format {
+        text_ingest::classify(text)
+    } else {
+        TextFormat::Plain
+    };
+    let name = format!("clipboard_{}", chrono::Local::now().format("%H%M%S"));
+    vec![ShelfItem::Text(TextItem::new(
+        name,
+        text.to_string(),
+        format,
+    ))]
+}
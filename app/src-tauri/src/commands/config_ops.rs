@@ -1,4 +1,5 @@
 use crate::analytics;
+use crate::clipboard::watcher::ClipboardWatcher;
 use crate::config::AppConfig;
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Listener, Manager, State};
@@ -29,7 +30,16 @@ pub fn save_config(
         .lock()
         .map_err(|e| format!("Failed to lock config: {}", e))?;
     *config = new_config;
-    config.save(&app_handle)
+    config.save(&app_handle)?;
+
+    let watch_clipboard = config.clipboard_watch.enabled;
+    drop(config);
+    app_handle
+        .state::<ClipboardWatcher>()
+        .apply(&app_handle, watch_clipboard);
+    #[cfg(desktop)]
+    crate::tray::set_clipboard_watch_checked(&app_handle, watch_clipboard);
+    Ok(())
 }
 
 #[tauri::command]
@@ -59,8 +69,42 @@ pub fn set_autostart(app_handle: AppHandle, enabled: bool) -> Result<(), String>
     Ok(())
 }
 
+/// A hotkey string such as `Ctrl+Shift+N`, split into its modifiers and key. Each
+/// platform maps the key name to its own key code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParsedHotkey {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+    /// The last part that is not a modifier, upper-cased
+    pub key: Option<String>,
+}
+
+impl ParsedHotkey {
+    pub fn parse(shortcut: &str) -> Self {
+        let mut hotkey = ParsedHotkey::default();
+        for part in shortcut.split('+') {
+            let part = part.trim().to_uppercase();
+            match part.as_str() {
+                "" => {}
+                "CTRL" | "CONTROL" => hotkey.ctrl = true,
+                "SHIFT" => hotkey.shift = true,
+                "ALT" | "OPT" | "OPTION" => hotkey.alt = true,
+                "META" | "WIN" | "SUPER" | "CMD" | "COMMAND" => hotkey.meta = true,
+                _ => hotkey.key = Some(part),
+            }
+        }
+        hotkey
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !(self.ctrl || self.shift || self.alt || self.meta) && self.key.is_none()
+    }
+}
+
 #[cfg(target_os = "macos")]
-fn parse_keycode(key: &str) -> Option<i64> {
+pub(crate) fn parse_keycode(key: &str) -> Option<i64> {
     let key_upper = key.to_uppercase();
     match key_upper.as_str() {
         "A" => Some(0),
@@ -186,23 +230,25 @@ fn register_hotkey_windows(app_handle: AppHandle, shortcut_str: String) -> Resul
     let app_handle_clone = app_handle.clone();
     info!("Registering Windows hotkey: {}", shortcut_str);
 
+    let hotkey = ParsedHotkey::parse(&shortcut_str);
     let mut modifiers = Modifiers::empty();
-    let mut code = Code::KeyN;
+    for (held, modifier) in [
+        (hotkey.ctrl, Modifiers::CONTROL),
+        (hotkey.shift, Modifiers::SHIFT),
+        (hotkey.alt, Modifiers::ALT),
+        (hotkey.meta, Modifiers::META),
+    ] {
+        if held {
+            modifiers |= modifier;
+        }
+    }
 
-    for part in shortcut_str.split('+') {
-        let part = part.trim();
-        match part.to_uppercase().as_str() {
-            "CTRL" | "CONTROL" => modifiers |= Modifiers::CONTROL,
-            "SHIFT" => modifiers |= Modifiers::SHIFT,
-            "ALT" => modifiers |= Modifiers::ALT,
-            "META" | "WIN" | "COMMAND" => modifiers |= Modifiers::META,
-            key => {
-                if let Some(parsed_code) = parse_windows_code(key) {
-                    code = parsed_code;
-                } else {
-                    warn!("Failed to parse Windows hotkey key: {}", key);
-                }
-            }
+    let mut code = Code::KeyN;
+    if let Some(key) = hotkey.key.as_deref() {
+        if let Some(parsed_code) = parse_windows_code(key) {
+            code = parsed_code;
+        } else {
+            warn!("Failed to parse Windows hotkey key: {}", key);
         }
     }
 
@@ -249,26 +295,20 @@ fn register_hotkey_mac(app_handle: AppHandle, shortcut_str: String) -> Result<()
     let app_handle_clone = app_handle.clone();
     info!("Registering macOS hotkey: {}", shortcut_str);
 
+    let hotkey = ParsedHotkey::parse(&shortcut_str);
     let mut mac_modifiers = MacModifiers::empty();
-    let mut keycode: Option<i64> = None;
-
-    for part in shortcut_str.split('+') {
-        let part = part.trim();
-        match part.to_uppercase().as_str() {
-            "CTRL" | "CONTROL" => mac_modifiers.control = true,
-            "SHIFT" => mac_modifiers.shift = true,
-            "ALT" | "OPT" | "OPTION" => mac_modifiers.option = true,
-            "META" | "CMD" | "COMMAND" => mac_modifiers.command = true,
-            key => {
-                if let Some(kc) = parse_keycode(key) {
-                    keycode = Some(kc);
-                } else {
-                    warn!("Failed to parse macOS hotkey key: {}", key);
-                }
-            }
+    mac_modifiers.control = hotkey.ctrl;
+    mac_modifiers.shift = hotkey.shift;
+    mac_modifiers.option = hotkey.alt;
+    mac_modifiers.command = hotkey.meta;
+
+    let keycode = hotkey.key.as_deref().and_then(|key| {
+        let keycode = parse_keycode(key);
+        if keycode.is_none() {
+            warn!("Failed to parse macOS hotkey key: {}", key);
         }
-    }
-
+        keycode
+    });
     let keycode = keycode.ok_or_else(|| "No valid keycode found".to_string())?;
 
     let event_name = format!("hotkey-{}", shortcut_str.replace('+', "-").to_lowercase());
@@ -396,3 +436,64 @@ pub fn check_input_monitoring_permission(_app_handle: AppHandle) -> Result<bool,
 pub fn open_input_monitoring_settings(_app_handle: AppHandle) -> Result<(), String> {
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_key() {
+        assert_eq!(
+            ParsedHotkey::parse("Ctrl+Shift+N"),
+            ParsedHotkey {
+                ctrl: true,
+                shift: true,
+                key: Some("N".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            ParsedHotkey::parse(" cmd + option + arrowup "),
+            ParsedHotkey {
+                alt: true,
+                meta: true,
+                key: Some("ARROWUP".to_string()),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn parses_modifier_aliases() {
+        for (shortcut, ctrl, alt, meta) in [
+            ("Control", true, false, false),
+            ("Alt", false, true, false),
+            ("Opt", false, true, false),
+            ("Win", false, false, true),
+            ("Super", false, false, true),
+            ("Command", false, false, true),
+            ("Meta", false, false, true),
+        ] {
+            let hotkey = ParsedHotkey::parse(shortcut);
+            assert_eq!(
+                (hotkey.ctrl, hotkey.alt, hotkey.meta, hotkey.key),
+                (ctrl, alt, meta, None),
+                "{}",
+                shortcut
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_last_key() {
+        assert_eq!(ParsedHotkey::parse("Shift+A+F5").key.as_deref(), Some("F5"));
+    }
+
+    #[test]
+    fn empty_shortcuts_are_empty() {
+        assert!(ParsedHotkey::parse("").is_empty());
+        assert!(ParsedHotkey::parse(" + +").is_empty());
+        assert!(!ParsedHotkey::parse("Shift").is_empty());
+        assert!(!ParsedHotkey::parse("Space").is_empty());
+    }
+}
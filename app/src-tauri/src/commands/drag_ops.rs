@@ -1,16 +1,37 @@
-use crate::config::AppConfig;
-use crate::file::FileMetadata;
+use super::file_ops::{link_format, materialize_shelf_item};
+use crate::config::{AppConfig, DragOutConfig, DragOutMode, LinkFormat, RemoveAfterDrop};
+use crate::drag_image;
+use crate::shelf::ShelfItem;
+use crate::FileList;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State, WebviewWindow};
 use tracing::{error, info, warn};
 
-type FileList = Arc<Mutex<Vec<FileMetadata>>>;
-
 #[tauri::command]
 pub fn start_multi_drag(
     app: AppHandle,
-    _file_list: State<'_, FileList>,
-    _config: State<'_, Arc<Mutex<AppConfig>>>,
+    file_list: State<'_, FileList>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    file_paths: Vec<String>,
+    drag_image: Option<String>,
+) -> Result<(), String> {
+    let window = main_window_for_drag(&app)?;
+    let source = DragSource {
+        backend: &drag::NativeBackend,
+        window: &native_window(&window)?,
+        scale: window.scale_factor().unwrap_or(1.0),
+        app: &app,
+        file_list: &file_list,
+        config: &config,
+    };
+    multi_drag(&source, file_paths, drag_image)
+}
+
+/// Drag the files at `file_paths`, which may belong to shelf items, out of the window.
+pub(crate) fn multi_drag<R: Runtime, W: ?Sized>(
+    source: &DragSource<'_, R, W>,
     file_paths: Vec<String>,
     drag_image: Option<String>,
 ) -> Result<(), String> {
@@ -37,38 +58,69 @@ pub fn start_multi_drag(
         return Err("No valid files to drag".to_string());
     }
 
-    // Use the drag image from the frontend if provided, otherwise generate one
-    let image = if let Some(base64_data) = drag_image {
-        // Remove data URL prefix if present (e.g., "data:image/png;base64,")
-        let base64_str = if let Some(comma_pos) = base64_data.find(',') {
-            &base64_data[comma_pos + 1..]
-        } else {
-            &base64_data
-        };
-
-        match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, base64_str) {
-            Ok(bytes) => {
-                info!("Using frontend-provided drag image ({} bytes)", bytes.len());
-                drag::Image::Raw(bytes)
-            }
-            Err(e) => {
-                warn!(
-                    "Failed to decode drag image, falling back to generated image: {}",
-                    e
-                );
-                generate_drag_image(valid_paths.len())
-            }
-        }
-    } else {
-        generate_drag_image(valid_paths.len())
-    };
+    let (image, image_size) =
+        choose_drag_image(drag_image, source.scale, &valid_paths, valid_paths.len());
 
-    let item = drag::DragItem::Files(valid_paths.clone());
+    let item_ids = shelf_ids_for_paths(source.file_list, &file_paths, &valid_paths);
+    let item = files_drag_item(source.file_list, &item_ids, valid_paths.clone());
     info!(
         "Prepared drag item with {} valid file(s)",
         valid_paths.len()
     );
 
+    match source.start(item, image, image_size, item_ids) {
+        Ok(()) => {
+            info!("Native drag started successfully");
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to start native drag: {}", e);
+            Err(format!("Failed to start multi-file drag operation: {}", e))
+        }
+    }
+}
+
+/// Everything a drag out of the shelf needs besides what is dragged. The commands use
+/// the main window and the platform's drag and drop; tests can use a
+/// `drag::MockBackend`, which takes any value as the window, and a mock app.
+pub(crate) struct DragSource<'a, R: Runtime, W: ?Sized> {
+    pub backend: &'a dyn drag::Backend<W>,
+    pub window: &'a W,
+    /// Scale factor of the window, for generated drag images
+    pub scale: f64,
+    pub app: &'a AppHandle<R>,
+    pub file_list: &'a FileList,
+    pub config: &'a Mutex<AppConfig>,
+}
+
+impl<R: Runtime, W: ?Sized> DragSource<'_, R, W> {
+    // Start the drag with the configured drag-out behaviour, and finish it off in the
+    // background once it ends
+    fn start(
+        &self,
+        item: drag::DragItem,
+        image: drag::Image,
+        image_size: f64,
+        item_ids: Vec<u64>,
+    ) -> Result<(), String> {
+        let drag_out = drag_out_config(self.config)?;
+        let options = drag::Options {
+            on_event: Some(forward_drag_events(self.app.clone())),
+            ..drag_options(drag_mode(drag_out.mode), image_size)
+        };
+        let outcome = drag::start_drag_async_with(self.backend, self.window, item, image, options)
+            .map_err(|e| format!("{:?}", e))?;
+
+        let app = self.app.clone();
+        let file_list = self.file_list.clone();
+        tauri::async_runtime::spawn(async move {
+            finish_drag(&app, &file_list, &drag_out, &item_ids, outcome.await);
+        });
+        Ok(())
+    }
+}
+
+fn main_window_for_drag(app: &AppHandle) -> Result<WebviewWindow, String> {
     let window = app
         .get_webview_window("main")
         .ok_or("Main window not found")?;
@@ -86,255 +138,586 @@ pub fn start_multi_drag(
         std::thread::sleep(std::time::Duration::from_millis(50));
     }
 
-    let app_clone = app.clone();
+    Ok(window)
+}
 
-    let on_drop_callback = move |result: drag::DragResult, _: drag::CursorPosition| {
-        if matches!(result, drag::DragResult::Cancel) {
-            return;
-        }
+// The window as the native drag backend takes it
+#[cfg(target_os = "linux")]
+fn native_window(window: &WebviewWindow) -> Result<gtk::ApplicationWindow, String> {
+    window.gtk_window().map_err(|e| e.to_string())
+}
 
-        // check if the popup window is open
-        if app_clone.get_webview_window("popup").is_some() {
-            if let Err(e) = super::window_ops::close_popup_window(app_clone.clone()) {
-                error!("Failed to close popup window after drag: {}", e);
-            }
-        }
-        if let Some(main_window) = app_clone.get_webview_window("main") {
-            if let Err(e) = main_window.hide() {
-                error!("Failed to hide main window after drag: {}", e);
-            }
-        }
-    };
+#[cfg(not(target_os = "linux"))]
+fn native_window(window: &WebviewWindow) -> Result<WebviewWindow, String> {
+    Ok(window.clone())
+}
 
-    // On macOS, the drag crate only supports Copy or Move individually, not combined.
-    // Using Copy as default (standard macOS behavior where Option key changes to Move).
-    #[cfg(target_os = "macos")]
-    let mode = drag::DragMode::CopyOrMove;
-    #[cfg(not(target_os = "macos"))]
-    let mode = drag::DragMode::CopyOrMove;
-
-    match drag::start_drag(
-        &window,
-        item,
-        image,
-        on_drop_callback,
-        drag::Options {
-            skip_animatation_on_cancel_or_failure: true,
-            mode,
-        },
-    ) {
-        Ok(_) => {
-            info!("Native drag started successfully");
-            Ok(())
-        }
-        Err(e) => {
-            error!("Failed to start native drag: {:?}", e);
-            Err(format!(
-                "Failed to start multi-file drag operation: {:?}",
-                e
-            ))
-        }
+/// Progress of a drag out of the shelf, sent as `drag_session` events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum DragSessionEvent {
+    Started,
+    /// The cursor moved, in screen coordinates (not reported on macOS and Windows)
+    Moved {
+        x: i32,
+        y: i32,
+    },
+    /// The cursor entered a window that takes drops (not reported on macOS and Windows)
+    EnteredTarget {
+        x: i32,
+        y: i32,
+    },
+    Ended {
+        /// What the drop target did, or `None` if the drag was cancelled
+        effect: Option<drag::DropEffect>,
+        /// Shelf items removed because of the drop
+        removed: Vec<u64>,
+        duration_ms: u64,
+    },
+}
+
+// Forward the drag's progress to the frontend. The end is reported by `finish_drag`,
+// once the shelf has been updated.
+fn forward_drag_events<R: Runtime>(app: AppHandle<R>) -> drag::EventHandler {
+    Arc::new(move |event| {
+        let event = match event {
+            drag::DragEvent::Started => DragSessionEvent::Started,
+            drag::DragEvent::Moved(position) => DragSessionEvent::Moved {
+                x: position.x,
+                y: position.y,
+            },
+            drag::DragEvent::EnteredTarget(position) => DragSessionEvent::EnteredTarget {
+                x: position.x,
+                y: position.y,
+            },
+            drag::DragEvent::Ended(_) => return,
+        };
+        let _ = app.emit("drag_session", event);
+    })
+}
+
+fn drag_out_config(config: &Mutex<AppConfig>) -> Result<DragOutConfig, String> {
+    config
+        .lock()
+        .map(|config| config.drag_out.clone())
+        .map_err(|e| format!("Failed to lock config: {}", e))
+}
+
+fn drag_mode(mode: DragOutMode) -> drag::DragMode {
+    match mode {
+        DragOutMode::Copy => drag::DragMode::Copy,
+        DragOutMode::Move => drag::DragMode::Move,
+        DragOutMode::Link => drag::DragMode::Link,
+        DragOutMode::Auto => drag::DragMode::CopyOrMove,
     }
 }
 
-/// Generate a simple drag image with file count badge using the `image` crate.
-/// Returns a PNG-encoded drag::Image::Raw at 128x128 (good enough for Retina).
-fn generate_drag_image(file_count: usize) -> drag::Image {
-    use image::{Rgba, RgbaImage};
-
-    let size = 128u32;
-    let mut img = RgbaImage::new(size, size);
-
-    // Draw a simple file icon (white rectangle with gray border and folded corner)
-    let margin = 16u32;
-    let fold = 24u32;
-    let border_color = Rgba([160, 160, 160, 255]);
-    let fill_color = Rgba([245, 245, 245, 255]);
-    let fold_color = Rgba([200, 200, 200, 255]);
-
-    // Fill the file body
-    for y in margin..size - margin {
-        for x in margin..size - margin {
-            // Skip the folded corner area
-            if y < margin + fold && x > size - margin - fold {
-                continue;
-            }
-            img.put_pixel(x, y, fill_color);
+// Ids of the shelf items behind the dragged paths, as given or canonicalized
+fn shelf_ids_for_paths(file_list: &FileList, paths: &[String], canonical: &[PathBuf]) -> Vec<u64> {
+    let Ok(list) = file_list.lock() else {
+        return Vec::new();
+    };
+    list.iter()
+        .filter(|item| {
+            item.path().is_some_and(|path| {
+                paths.iter().any(|p| Path::new(p) == path) || canonical.iter().any(|p| p == path)
+            })
+        })
+        .map(ShelfItem::id)
+        .collect()
+}
+
+// The dragged files, plus their links and snippets as text when the selection holds
+// URL or text items, so a browser or editor gets the URL rather than a .url file
+fn files_drag_item(file_list: &FileList, item_ids: &[u64], paths: Vec<PathBuf>) -> drag::DragItem {
+    let text = file_list.lock().ok().and_then(|list| {
+        let selected: Vec<&ShelfItem> = list
+            .iter()
+            .filter(|item| item_ids.contains(&item.id()))
+            .collect();
+        if !selected
+            .iter()
+            .any(|item| matches!(item, ShelfItem::Url(_) | ShelfItem::Text(_)))
+        {
+            return None;
         }
-    }
+        let lines: Vec<String> = selected
+            .iter()
+            .filter_map(|item| match item {
+                ShelfItem::Url(url) => Some(url.url.clone()),
+                ShelfItem::Text(text) => Some(text.text.clone()),
+                other => other.path().map(|path| path.to_string_lossy().into_owned()),
+            })
+            .collect();
+        Some(lines.join("\n"))
+    });
 
-    // Draw fold triangle
-    for y in margin..margin + fold {
-        for x in (size - margin - fold)..(size - margin) {
-            let dx = x - (size - margin - fold);
-            let dy = y - margin;
-            if dx + dy <= fold {
-                img.put_pixel(x, y, fold_color);
-            }
+    let files = drag::DragItem::Files(paths);
+    let Some(text) = text else {
+        return files;
+    };
+    let provider: drag::DataProvider = Box::new(move |format: &str| -> Option<Vec<u8>> {
+        TEXT_TYPES
+            .contains(&format)
+            .then(|| text.as_bytes().to_vec())
+    });
+    drag::DragItem::Multi(vec![
+        files,
+        drag::DragItem::Data {
+            provider,
+            types: TEXT_TYPES.iter().map(|t| t.to_string()).collect(),
+        },
+    ])
+}
+
+/// Handle the end of a drag of the shelf items `item_ids`: remove them from the shelf
+/// and hide the window as configured, and report what happened.
+fn finish_drag<R: Runtime>(
+    app: &AppHandle<R>,
+    file_list: &FileList,
+    config: &DragOutConfig,
+    item_ids: &[u64],
+    outcome: drag::DragOutcome,
+) {
+    let ended = |effect, removed| DragSessionEvent::Ended {
+        effect,
+        removed,
+        duration_ms: outcome.duration.as_millis() as u64,
+    };
+    let Some(effect) = outcome.action else {
+        info!("Drag was cancelled after {:?}", outcome.duration);
+        let _ = app.emit("drag_session", ended(None, Vec::new()));
+        return;
+    };
+    info!("Dragged items were dropped ({:?})", effect);
+
+    let remove = match config.remove_items {
+        RemoveAfterDrop::Never => false,
+        RemoveAfterDrop::Always => true,
+        RemoveAfterDrop::IfMoved => effect == drag::DropEffect::Move,
+    };
+    let mut removed = Vec::new();
+    if remove {
+        if let Ok(mut list) = file_list.lock() {
+            list.retain(|item| {
+                let dragged = item_ids.contains(&item.id());
+                if dragged {
+                    removed.push(item.id());
+                }
+                !dragged
+            });
+        }
+        if !removed.is_empty() {
+            info!("Removed {} dropped item(s) from the shelf", removed.len());
+            let _ = app.emit("files_updated", ());
         }
     }
+    let _ = app.emit("drag_session", ended(Some(effect), removed));
 
-    // Draw border
-    for x in margin..size - margin - fold {
-        img.put_pixel(x, margin, border_color); // top
-    }
-    for x in margin..size - margin {
-        img.put_pixel(x, size - margin - 1, border_color); // bottom
+    if !config.hide_window {
+        return;
     }
-    for y in margin..size - margin {
-        img.put_pixel(margin, y, border_color); // left
-        img.put_pixel(size - margin - 1, y, border_color); // right
+    // check if the popup window is open
+    if let Some(popup_window) = app.get_webview_window("popup") {
+        if let Err(e) = popup_window.close() {
+            error!("Failed to close popup window after drag: {}", e);
+        }
     }
-    // Fold diagonal
-    for i in 0..fold {
-        let x = size - margin - fold + i;
-        let y = margin + fold - i;
-        if x < size && y < size {
-            img.put_pixel(x, y, border_color);
+    if let Some(main_window) = app.get_webview_window("main") {
+        if let Err(e) = main_window.hide() {
+            error!("Failed to hide main window after drag: {}", e);
         }
     }
+}
 
-    // If multiple files, draw a badge circle with count
-    if file_count > 1 {
-        let badge_radius = 18i32;
-        let badge_cx = (size - margin) as i32;
-        let badge_cy = (size - margin) as i32;
-        let badge_color = Rgba([59, 130, 246, 255]); // Blue
-        let _badge_text_color = Rgba([255, 255, 255, 255]);
-
-        // Draw badge circle
-        for y in 0..size as i32 {
-            for x in 0..size as i32 {
-                let dx = x - badge_cx;
-                let dy = y - badge_cy;
-                if dx * dx + dy * dy <= badge_radius * badge_radius {
-                    img.put_pixel(x as u32, y as u32, badge_color);
-                }
-            }
-        }
+// Logical size of the frontend's drag images, which are drawn at 256 px for 2x screens
+const FRONTEND_DRAG_IMAGE_SIZE: f64 = 128.0;
+
+// Options for a drag showing a square image of `image_size` logical pixels, centred on
+// the cursor as macOS does, at the same size whatever the screen's scale factor
+fn drag_options(mode: drag::DragMode, image_size: f64) -> drag::Options {
+    drag::Options {
+        skip_animatation_on_cancel_or_failure: true,
+        mode,
+        image_hotspot: Some((image_size / 2.0, image_size / 2.0)),
+        image_size: Some((image_size, image_size)),
+        ..Default::default()
+    }
+}
 
-        // Draw count number (simple pixel art for single/double digit)
-        let count_str = if file_count > 99 {
-            "99+".to_string()
+// The drag image from the frontend if it decodes, otherwise a generated one for the
+// first few `paths` and `count` items, with the logical size it is shown at
+fn choose_drag_image(
+    drag_image: Option<String>,
+    scale: f64,
+    paths: &[PathBuf],
+    count: usize,
+) -> (drag::Image, f64) {
+    if let Some(base64_data) = drag_image {
+        // Remove data URL prefix if present (e.g., "data:image/png;base64,")
+        let base64_str = if let Some(comma_pos) = base64_data.find(',') {
+            &base64_data[comma_pos + 1..]
         } else {
-            file_count.to_string()
+            &base64_data
         };
-        // For simplicity, just draw a small dot pattern - the badge itself is informative
-        let _ = count_str; // Count shown by badge presence; actual text rendering is complex with `image` crate
-
-        // Draw a simple "+" or number shape in the badge center
-        // For now, just the badge alone indicates multiple files
-    }
 
-    // Encode to PNG
-    let mut png_bytes: Vec<u8> = Vec::new();
-    if let Err(e) = img.write_to(
-        &mut std::io::Cursor::new(&mut png_bytes),
-        image::ImageFormat::Png,
-    ) {
-        error!("Failed to encode generated drag image: {}", e);
-        // Fallback: return a 1x1 transparent PNG
-        return drag::Image::Raw(vec![]);
+        match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, base64_str) {
+            Ok(bytes) => {
+                info!("Using frontend-provided drag image ({} bytes)", bytes.len());
+                return (drag::Image::Raw(bytes), FRONTEND_DRAG_IMAGE_SIZE);
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to decode drag image, falling back to generated image: {}",
+                    e
+                );
+            }
+        }
     }
 
-    drag::Image::Raw(png_bytes)
+    let image = match drag_image::render(paths, count, scale) {
+        Ok(png) => drag::Image::Raw(png),
+        Err(e) => {
+            error!("Failed to render drag image: {}", e);
+            drag::Image::Raw(vec![])
+        }
+    };
+    (image, drag_image::SIZE)
 }
 
+/// Drag a piece of text. Either `text` is given directly, or `item_id` names a text
+/// snippet on the shelf; snippets can also be dropped as a file, which is only written
+/// to the staging folder once a drop target asks for it.
 #[tauri::command]
 pub fn start_text_drag(
     app: AppHandle,
-    text: String,
+    file_list: State<'_, FileList>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    text: Option<String>,
+    item_id: Option<u64>,
     drag_image: Option<String>,
 ) -> Result<(), String> {
-    info!("Starting text drag");
+    let window = main_window_for_drag(&app)?;
+    let source = DragSource {
+        backend: &drag::NativeBackend,
+        window: &native_window(&window)?,
+        scale: window.scale_factor().unwrap_or(1.0),
+        app: &app,
+        file_list: &file_list,
+        config: &config,
+    };
+    text_drag(&source, text, item_id, drag_image)
+}
 
-    // Use the drag image from the frontend if provided, otherwise generate one
-    let image = if let Some(base64_data) = drag_image {
-        let base64_str = if let Some(comma_pos) = base64_data.find(',') {
-            &base64_data[comma_pos + 1..]
-        } else {
-            &base64_data
-        };
+/// Drag `text`, or the text snippet `item_id` on the shelf, out of the window.
+pub(crate) fn text_drag<R: Runtime, W: ?Sized>(
+    source: &DragSource<'_, R, W>,
+    text: Option<String>,
+    item_id: Option<u64>,
+    drag_image: Option<String>,
+) -> Result<(), String> {
+    info!("Starting text drag");
 
-        match base64::Engine::decode(&base64::engine::general_purpose::STANDARD, base64_str) {
-            Ok(bytes) => drag::Image::Raw(bytes),
-            Err(e) => {
-                warn!("Failed to decode drag image, falling back: {}", e);
-                generate_drag_image(1)
+    let text = match item_id {
+        Some(id) => {
+            let list = source
+                .file_list
+                .lock()
+                .map_err(|_| "Failed to acquire lock".to_string())?;
+            match list.iter().find(|item| item.id() == id) {
+                Some(ShelfItem::Text(item)) => item.text.clone(),
+                Some(_) => return Err(format!("Item {} is not a text snippet", id)),
+                None => return Err(format!("Item with ID {} not found", id)),
             }
         }
-    } else {
-        generate_drag_image(1)
+        None => text.ok_or("No text to drag")?,
     };
 
-    let text_clone = text.clone();
+    let (image, image_size) = choose_drag_image(drag_image, source.scale, &[], 1);
+    let link_format = link_format(source.config)?;
+    let item = text_drag_item(text, item_id, source.file_list.clone(), link_format)?;
+
+    match source.start(item, image, image_size, item_id.into_iter().collect()) {
+        Ok(()) => {
+            info!("Native text drag started successfully");
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to start native text drag: {}", e);
+            Err(format!("Failed to start text drag operation: {}", e))
+        }
+    }
+}
+
+// Text formats offered for a text drag, and the ones that ask for the snippet as a file
+const TEXT_TYPES: &[&str] = &["text/plain", "public.utf8-plain-text"];
+const FILE_TYPES: &[&str] = &["public.file-url", "text/uri-list"];
+
+#[cfg(not(target_os = "windows"))]
+fn text_drag_item(
+    text: String,
+    item_id: Option<u64>,
+    file_list: FileList,
+    link_format: LinkFormat,
+) -> Result<drag::DragItem, String> {
+    let mut types: Vec<String> = TEXT_TYPES.iter().map(|t| t.to_string()).collect();
+    if item_id.is_some() {
+        types.extend(FILE_TYPES.iter().map(|t| t.to_string()));
+    }
+
     let provider: drag::DataProvider = Box::new(move |format: &str| -> Option<Vec<u8>> {
-        if format == "text/plain" {
-            Some(text_clone.as_bytes().to_vec())
-        } else {
-            None
+        if TEXT_TYPES.contains(&format) {
+            return Some(text.as_bytes().to_vec());
+        }
+        if FILE_TYPES.contains(&format) {
+            let path = materialize_shelf_item(&file_list, item_id?, link_format)
+                .map_err(|e| error!("Failed to write text snippet for drop: {}", e))
+                .ok()?;
+            let url = reqwest::Url::from_file_path(&path).ok()?;
+            return Some(url.as_str().as_bytes().to_vec());
         }
+        None
     });
 
-    let item = drag::DragItem::Data {
+    Ok(drag::DragItem::Data { provider, types })
+}
+
+// The Windows backend can't serve data lazily yet, so shelf snippets are written out
+// up front and dragged as a file
+#[cfg(target_os = "windows")]
+fn text_drag_item(
+    text: String,
+    item_id: Option<u64>,
+    file_list: FileList,
+    link_format: LinkFormat,
+) -> Result<drag::DragItem, String> {
+    if let Some(id) = item_id {
+        let path = materialize_shelf_item(&file_list, id, link_format)?;
+        return Ok(drag::DragItem::Files(vec![path]));
+    }
+
+    let provider: drag::DataProvider = Box::new(move |format: &str| -> Option<Vec<u8>> {
+        TEXT_TYPES
+            .contains(&format)
+            .then(|| text.as_bytes().to_vec())
+    });
+    Ok(drag::DragItem::Data {
         provider,
-        types: vec!["text/plain".to_string()],
-    };
+        types: TEXT_TYPES.iter().map(|t| t.to_string()).collect(),
+    })
+}
 
-    let window = app
-        .get_webview_window("main")
-        .ok_or("Main window not found")?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use drag::{MockBackend, MockOutcome};
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use tauri::test::{mock_app, MockRuntime};
+    use tauri::{App, Listener};
+
+    struct Fixture {
+        app: App<MockRuntime>,
+        backend: MockBackend,
+        file_list: FileList,
+        config: Mutex<AppConfig>,
+        dir: tempfile::TempDir,
+    }
 
-    #[cfg(target_os = "macos")]
-    {
-        if let Err(e) = window.show() {
-            warn!("Failed to show main window before drag: {}", e);
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                app: mock_app(),
+                backend: MockBackend::new(),
+                file_list: Arc::new(Mutex::new(Vec::new())),
+                config: Mutex::new(AppConfig::default()),
+                dir: tempfile::tempdir().unwrap(),
+            }
         }
-        if let Err(e) = window.set_focus() {
-            warn!("Failed to focus main window before drag: {}", e);
+
+        // Write a file and put it on the shelf as item `id`
+        fn shelve(&self, name: &str, id: u64) -> PathBuf {
+            let path = self.dir.path().join(name);
+            std::fs::write(&path, b"data").unwrap();
+            let mut item = ShelfItem::from_path(&path).unwrap();
+            item.set_id(id);
+            self.file_list.lock().unwrap().push(item);
+            path
         }
-        std::thread::sleep(std::time::Duration::from_millis(50));
-    }
 
-    let app_clone = app.clone();
-    let on_drop_callback = move |result: drag::DragResult, _: drag::CursorPosition| {
-        if matches!(result, drag::DragResult::Cancel) {
-            return;
+        fn drag(&self, paths: &[&Path], drag_image: Option<String>) -> Result<(), String> {
+            let source = DragSource {
+                backend: &self.backend,
+                window: &(),
+                scale: 1.0,
+                app: self.app.handle(),
+                file_list: &self.file_list,
+                config: &self.config,
+            };
+            let paths = paths
+                .iter()
+                .map(|p| p.to_string_lossy().into_owned())
+                .collect();
+            multi_drag(&source, paths, drag_image)
         }
-        if app_clone.get_webview_window("popup").is_some() {
-            if let Err(e) = super::window_ops::close_popup_window(app_clone.clone()) {
-                error!("Failed to close popup window after drag: {}", e);
-            }
+
+        // The `drag_session` events emitted from now on
+        fn session_events(&self) -> mpsc::Receiver<serde_json::Value> {
+            let (tx, rx) = mpsc::channel();
+            self.app.listen_any("drag_session", move |event| {
+                let _ = tx.send(serde_json::from_str(event.payload()).unwrap());
+            });
+            rx
         }
-        if let Some(main_window) = app_clone.get_webview_window("main") {
-            if let Err(e) = main_window.hide() {
-                error!("Failed to hide main window after drag: {}", e);
-            }
+
+        fn shelf_ids(&self) -> Vec<u64> {
+            self.file_list
+                .lock()
+                .unwrap()
+                .iter()
+                .map(ShelfItem::id)
+                .collect()
         }
-    };
+    }
 
-    #[cfg(target_os = "macos")]
-    let mode = drag::DragMode::CopyOrMove;
-    #[cfg(not(target_os = "macos"))]
-    let mode = drag::DragMode::CopyOrMove;
-
-    match drag::start_drag(
-        &window,
-        item,
-        image,
-        on_drop_callback,
-        drag::Options {
-            skip_animatation_on_cancel_or_failure: true,
-            mode,
-        },
-    ) {
-        Ok(_) => {
-            info!("Native text drag started successfully");
-            Ok(())
+    // Wait for the `ended` event sent once the shelf has been updated
+    fn wait_for_end(events: &mpsc::Receiver<serde_json::Value>) -> serde_json::Value {
+        loop {
+            let event = events.recv_timeout(Duration::from_secs(5)).unwrap();
+            if event["type"] == "ended" {
+                return event;
+            }
         }
-        Err(e) => {
-            error!("Failed to start native text drag: {:?}", e);
-            Err(format!("Failed to start text drag operation: {:?}", e))
+    }
+
+    fn dragged_files(item: &drag::DragItem) -> Vec<PathBuf> {
+        match item {
+            drag::DragItem::Files(paths) => paths.clone(),
+            _ => panic!("expected a files drag"),
         }
     }
+
+    #[test]
+    fn drags_only_paths_that_exist() {
+        let fixture = Fixture::new();
+        let existing = fixture.shelve("a.txt", 1);
+        let missing = fixture.dir.path().join("missing.txt");
+
+        fixture.drag(&[&existing, &missing], None).unwrap();
+
+        let drags = fixture.backend.take_drags();
+        assert_eq!(drags.len(), 1);
+        assert_eq!(
+            dragged_files(&drags[0].item),
+            vec![std::fs::canonicalize(&existing).unwrap()]
+        );
+    }
+
+    #[test]
+    fn refuses_a_drag_without_valid_paths() {
+        let fixture = Fixture::new();
+        let missing = fixture.dir.path().join("missing.txt");
+
+        let result = fixture.drag(&[&missing], None);
+
+        assert_eq!(result, Err("No valid files to drag".to_string()));
+        assert!(fixture.backend.take_drags().is_empty());
+    }
+
+    #[test]
+    fn uses_the_frontend_drag_image_when_it_decodes() {
+        let fixture = Fixture::new();
+        let path = fixture.shelve("a.txt", 1);
+
+        fixture
+            .drag(&[&path], Some("data:image/png;base64,AQID".to_string()))
+            .unwrap();
+
+        let recorded = fixture.backend.take_drags().remove(0);
+        assert!(matches!(&recorded.image, drag::Image::Raw(bytes) if bytes == &[1, 2, 3]));
+        let size = FRONTEND_DRAG_IMAGE_SIZE;
+        assert_eq!(recorded.options.image_size, Some((size, size)));
+    }
+
+    #[test]
+    fn falls_back_to_a_generated_drag_image() {
+        let fixture = Fixture::new();
+        let path = fixture.shelve("a.txt", 1);
+
+        fixture
+            .drag(&[&path], Some("not base64!".to_string()))
+            .unwrap();
+
+        let recorded = fixture.backend.take_drags().remove(0);
+        let size = drag_image::SIZE;
+        assert_eq!(recorded.options.image_size, Some((size, size)));
+        assert_eq!(
+            recorded.options.image_hotspot,
+            Some((size / 2.0, size / 2.0))
+        );
+    }
+
+    #[test]
+    fn removes_dropped_items_when_configured() {
+        let fixture = Fixture::new();
+        let dragged = fixture.shelve("a.txt", 1);
+        fixture.shelve("b.txt", 2);
+        fixture.config.lock().unwrap().drag_out.remove_items = RemoveAfterDrop::Always;
+        fixture
+            .backend
+            .push_outcome(MockOutcome::Dropped(drag::DropEffect::Copy));
+        let events = fixture.session_events();
+
+        fixture.drag(&[&dragged], None).unwrap();
+
+        let ended = wait_for_end(&events);
+        assert_eq!(ended["effect"], "copy");
+        assert_eq!(ended["removed"], serde_json::json!([1]));
+        assert_eq!(fixture.shelf_ids(), vec![2]);
+    }
+
+    #[test]
+    fn keeps_copied_items_when_only_moves_remove() {
+        let fixture = Fixture::new();
+        let dragged = fixture.shelve("a.txt", 1);
+        fixture.config.lock().unwrap().drag_out.remove_items = RemoveAfterDrop::IfMoved;
+        fixture
+            .backend
+            .push_outcome(MockOutcome::Dropped(drag::DropEffect::Copy));
+        let events = fixture.session_events();
+
+        fixture.drag(&[&dragged], None).unwrap();
+
+        let ended = wait_for_end(&events);
+        assert_eq!(ended["removed"], serde_json::json!([]));
+        assert_eq!(fixture.shelf_ids(), vec![1]);
+    }
+
+    #[test]
+    fn keeps_items_when_the_drag_is_cancelled() {
+        let fixture = Fixture::new();
+        let dragged = fixture.shelve("a.txt", 1);
+        fixture.config.lock().unwrap().drag_out.remove_items = RemoveAfterDrop::Always;
+        fixture.backend.push_outcome(MockOutcome::Cancelled);
+        let events = fixture.session_events();
+
+        fixture.drag(&[&dragged], None).unwrap();
+
+        let ended = wait_for_end(&events);
+        assert_eq!(ended["effect"], serde_json::Value::Null);
+        assert_eq!(fixture.shelf_ids(), vec![1]);
+    }
+
+    #[test]
+    fn reports_a_backend_failure() {
+        let fixture = Fixture::new();
+        let dragged = fixture.shelve("a.txt", 1);
+        fixture.backend.push_outcome(MockOutcome::Failed);
+
+        let result = fixture.drag(&[&dragged], None);
+
+        assert!(result
+            .unwrap_err()
+            .starts_with("Failed to start multi-file drag operation"));
+    }
 }
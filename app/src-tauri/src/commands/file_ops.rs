@@ -1,9 +1,22 @@
 use crate::analytics;
-use crate::file::{get_dir_size, FileMetadata};
+use crate::config::{AppConfig, LinkFormat, PastedTextConfig};
+use crate::download::{self, DownloadManager, DownloadOptions, DownloadProgress};
+use crate::drop_rules;
+use crate::file::staging_dir;
+use crate::file_drop::add_paths_to_shelf;
+use crate::ingest::html as html_ingest;
+use crate::ingest::image as image_ingest;
+use crate::ingest::links;
+use crate::ingest::text::{self as text_ingest, TextFormat};
+use crate::ingest::url::{self as url_ingest, UrlSource};
+use crate::shelf::{next_id, ShelfItem, TextItem};
 use crate::thumbnail::get_thumbnail_base64;
 use crate::FileList;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, State};
+use tracing::{info, warn};
+use uuid::Uuid;
 
 #[tauri::command]
 pub fn add_files(
@@ -11,9 +24,15 @@ pub fn add_files(
     file_list: State<'_, FileList>,
     files: Vec<String>,
 ) -> Result<(), String> {
+    let paths: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+    if let Err(reason) = drop_rules::check_paths(&drop_rules::current_rules(&app_handle), &paths) {
+        drop_rules::reject(&app_handle, &reason);
+        return Err(reason);
+    }
+
     if !files.is_empty() {
-        // Immediately notify monitor that files were dropped to prevent auto-close!
-        // Do this FIRST before doing any heavy disk I/O like get_dir_size which might take >300ms
+        // Notify the monitor that files were dropped to prevent auto-close, before the
+        // disk I/O of adding them (get_dir_size may take >300ms)
         let _ = app_handle.emit("file_added", ());
     }
 
@@ -24,36 +43,11 @@ pub fn add_files(
     for path_str in files.iter() {
         let path = PathBuf::from(path_str);
         if path.exists() {
-            let metadata = path.metadata().map_err(|e| e.to_string())?;
-
-            // Calculate size correctly for directories
-            let size = if metadata.is_dir() {
-                get_dir_size(&path).unwrap_or(0)
-            } else {
-                metadata.len()
-            };
-
-            let file = FileMetadata {
-                id: list.len() as u64,
-                name: path
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or("Unknown")
-                    .to_string(),
-                path: path.clone(),
-                size,
-                file_type: if metadata.is_dir() {
-                    "folder".to_string()
-                } else {
-                    path.extension()
-                        .and_then(|ext| ext.to_str())
-                        .unwrap_or("unknown")
-                        .to_string()
-                },
-            };
+            let mut item = ShelfItem::from_path(&path).map_err(|e| e.to_string())?;
             // Avoid duplicates
-            if !list.iter().any(|f| f.path == file.path) {
-                list.push(file);
+            if !list.iter().any(|f| f.path() == item.path()) {
+                item.set_id(next_id(&list));
+                list.push(item);
             }
             app_handle
                 .emit("files_updated", ())
@@ -64,15 +58,36 @@ pub fn add_files(
     Ok(())
 }
 
+/// Save pasted text to the staging folder. The file extension comes from the content
+/// (see [`text_ingest::classify`]); `extension` is only a hint for text that isn't
+/// recognisable as anything but plain text.
 #[tauri::command]
-pub fn save_pasted_text(text: String, extension: String) -> Result<String, String> {
+pub fn save_pasted_text(
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    text: String,
+    extension: Option<String>,
+) -> Result<String, String> {
     use std::io::Write;
+    let settings = config
+        .lock()
+        .map_err(|_| "Failed to acquire config lock".to_string())?
+        .pasted_text
+        .clone();
     let timestamp = chrono::Local::now();
-    let folder_name = timestamp.format("%Y%m%d").to_string();
-    let drop_folder = std::env::temp_dir().join("holdem_drops").join(&folder_name);
-    if let Err(e) = std::fs::create_dir_all(&drop_folder) {
-        return Err(format!("Failed to create drop folder: {}", e));
-    }
+    let drop_folder = staging_dir()?;
+
+    let format = pasted_text_format(&text, &settings);
+    let extension = match format {
+        Some(format) if format != TextFormat::Plain => format.extension().to_string(),
+        _ => extension
+            .as_deref()
+            .and_then(sanitize_extension_hint)
+            .unwrap_or_else(|| "txt".to_string()),
+    };
+    let text = match format.filter(|_| settings.pretty_print) {
+        Some(format) => text_ingest::pretty_print(&text, format).unwrap_or(text),
+        None => text,
+    };
 
     let file_name = format!("pasted_{}.{}", timestamp.format("%H%M%S"), extension);
     let new_path = drop_folder.join(&file_name);
@@ -83,6 +98,19 @@ pub fn save_pasted_text(text: String, extension: String) -> Result<String, Strin
     Ok(new_path.to_string_lossy().to_string())
 }
 
+fn pasted_text_format(text: &str, settings: &PastedTextConfig) -> Option<TextFormat> {
+    settings.detect_format.then(|| text_ingest::classify(text))
+}
+
+// The frontend's guess is only used as a plain file extension, never as a path
+fn sanitize_extension_hint(extension: &str) -> Option<String> {
+    let extension = extension.trim().trim_start_matches('.').to_lowercase();
+    let valid = !extension.is_empty()
+        && extension.len() <= 10
+        && extension.chars().all(|c| c.is_ascii_alphanumeric());
+    valid.then_some(extension)
+}
+
 #[tauri::command]
 pub fn save_pasted_data_base64(data_base64: String, extension: String) -> Result<String, String> {
     use base64::{engine::general_purpose, Engine as _};
@@ -93,11 +121,7 @@ pub fn save_pasted_data_base64(data_base64: String, extension: String) -> Result
         .map_err(|e| e.to_string())?;
 
     let timestamp = chrono::Local::now();
-    let folder_name = timestamp.format("%Y%m%d").to_string();
-    let drop_folder = std::env::temp_dir().join("holdem_drops").join(&folder_name);
-    if let Err(e) = std::fs::create_dir_all(&drop_folder) {
-        return Err(format!("Failed to create drop folder: {}", e));
-    }
+    let drop_folder = staging_dir()?;
 
     let file_name = format!("pasted_{}.{}", timestamp.format("%H%M%S"), extension);
     let new_path = drop_folder.join(&file_name);
@@ -108,33 +132,277 @@ pub fn save_pasted_data_base64(data_base64: String, extension: String) -> Result
     Ok(new_path.to_string_lossy().to_string())
 }
 
+// Save image data dropped without a file (a browser image, a bitmap from an image editor)
+// and add it to the shelf, named after the HTML's alt text or the image URL if given
 #[tauri::command]
-pub async fn download_image_to_shelf(url: String) -> Result<String, String> {
-    use std::io::Write;
-    let timestamp = chrono::Local::now();
-    let folder_name = timestamp.format("%Y%m%d").to_string();
-    let drop_folder = std::env::temp_dir().join("holdem_drops").join(&folder_name);
-    if let Err(e) = std::fs::create_dir_all(&drop_folder) {
-        return Err(format!("Failed to create drop folder: {}", e));
+pub fn add_dropped_image(
+    app_handle: AppHandle,
+    file_list: State<'_, FileList>,
+    data_base64: String,
+    mime: String,
+    html: Option<String>,
+    url: Option<String>,
+) -> Result<String, String> {
+    use base64::{engine::general_purpose, Engine as _};
+
+    let bytes = general_purpose::STANDARD
+        .decode(data_base64)
+        .map_err(|e| e.to_string())?;
+    let name = image_ingest::image_name(html.as_deref(), url.as_deref());
+    let path = image_ingest::save_image(&bytes, &mime, name.as_deref(), &staging_dir()?)?;
+    info!("Saved dropped image as {}", path.display());
+
+    add_paths_to_shelf(&[path.clone()], &file_list, &app_handle)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn download_image_to_shelf(
+    app_handle: AppHandle,
+    downloads: State<'_, DownloadManager>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    url: String,
+    download_id: Option<String>,
+) -> Result<String, String> {
+    let path = download_to_staging(&app_handle, &downloads, &config, &url, download_id).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// Download `url` into the staging folder, reporting progress as `download_progress` events
+async fn download_to_staging(
+    app_handle: &AppHandle,
+    downloads: &DownloadManager,
+    config: &Arc<Mutex<AppConfig>>,
+    url: &str,
+    download_id: Option<String>,
+) -> Result<PathBuf, String> {
+    let options = {
+        let config = config
+            .lock()
+            .map_err(|e| format!("Failed to lock config: {}", e))?;
+        DownloadOptions::from(&config.downloads)
+    };
+    let client = download::build_client(&options)?;
+    let drop_folder = staging_dir()?;
+
+    let id = download_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let cancelled = downloads.register(&id);
+
+    let progress_handle = app_handle.clone();
+    let progress_id = id.clone();
+    let result = download::download_to_dir(
+        &client,
+        &id,
+        url,
+        &drop_folder,
+        &options,
+        &cancelled,
+        move |received, total| {
+            let _ = progress_handle.emit(
+                "download_progress",
+                DownloadProgress {
+                    id: progress_id.clone(),
+                    received,
+                    total,
+                },
+            );
+        },
+    )
+    .await;
+    downloads.finish(&id);
+
+    if let Err(e) = &result {
+        warn!("Download of {} failed: {}", url, e);
     }
+    result
+}
 
-    let url_path = std::path::Path::new(&url);
-    let ext = url_path
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("png");
+async fn resolve_url_to_file(
+    app_handle: &AppHandle,
+    downloads: &DownloadManager,
+    config: &Arc<Mutex<AppConfig>>,
+    url: &str,
+    download_id: Option<String>,
+) -> Result<PathBuf, String> {
+    match url_ingest::parse_source(url)? {
+        UrlSource::Data(data) => url_ingest::save_data_url(&data, &staging_dir()?),
+        UrlSource::File(path) => {
+            if !path.exists() {
+                return Err(format!("File not found: {}", path.display()));
+            }
+            Ok(path)
+        }
+        UrlSource::Http(url) => {
+            download_to_staging(app_handle, downloads, config, &url, download_id).await
+        }
+    }
+}
 
-    let file_name = format!("downloaded_{}.{}", timestamp.format("%H%M%S"), ext);
-    let new_path = drop_folder.join(&file_name);
+// Add whatever a URL points to as a file on the shelf: `data:` URLs are decoded,
+// `file://` URLs are added like a dropped file and http(s) URLs are downloaded first
+#[tauri::command]
+pub async fn add_from_url(
+    app_handle: AppHandle,
+    file_list: State<'_, FileList>,
+    downloads: State<'_, DownloadManager>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    url: String,
+    download_id: Option<String>,
+) -> Result<String, String> {
+    let path = resolve_url_to_file(&app_handle, &downloads, &config, &url, download_id).await?;
+    let added = add_paths_to_shelf(&[path.clone()], &file_list, &app_handle)?;
+    let path = added
+        .first()
+        .and_then(|item| item.path())
+        .map(|p| p.to_path_buf())
+        .unwrap_or(path);
+    Ok(path.to_string_lossy().to_string())
+}
+
+// Save dropped or pasted HTML (raw CF_HTML or plain markup) as .html and/or .md files,
+// optionally pulling the images and links it references onto the shelf as well
+#[tauri::command]
+pub async fn ingest_html(
+    app_handle: AppHandle,
+    file_list: State<'_, FileList>,
+    downloads: State<'_, DownloadManager>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    html: String,
+    source_url: Option<String>,
+) -> Result<Vec<String>, String> {
+    let settings = {
+        let config = config
+            .lock()
+            .map_err(|e| format!("Failed to lock config: {}", e))?;
+        config.html_ingest.clone()
+    };
+
+    let mut fragment = html_ingest::parse_cf_html(&html);
+    if fragment.source_url.is_none() {
+        fragment.source_url = source_url;
+    }
 
-    let response = reqwest::get(&url).await.map_err(|e| e.to_string())?;
-    let bytes = response.bytes().await.map_err(|e| e.to_string())?;
+    let mut paths = html_ingest::save_fragment(
+        &fragment,
+        &staging_dir()?,
+        settings.save_html,
+        settings.save_markdown,
+    )?;
+
+    if settings.extract_images || settings.extract_links {
+        let base = fragment
+            .source_url
+            .as_deref()
+            .and_then(|url| reqwest::Url::parse(url).ok());
+        let resources = html_ingest::extract_resources(&fragment.fragment, base.as_ref());
+
+        let mut targets = Vec::new();
+        if settings.extract_images {
+            targets.extend(resources.images);
+        }
+        if settings.extract_links {
+            targets.extend(resources.links);
+        }
+        if targets.len() > settings.max_resources {
+            warn!(
+                "Dropped HTML references {} resources, downloading the first {}",
+                targets.len(),
+                settings.max_resources
+            );
+            targets.truncate(settings.max_resources);
+        }
 
-    let mut file = std::fs::File::create(&new_path).map_err(|e| e.to_string())?;
-    file.write_all(&bytes).map_err(|e| e.to_string())?;
+        for target in targets {
+            match resolve_url_to_file(&app_handle, &downloads, &config, &target, None).await {
+                Ok(path) => paths.push(path),
+                Err(e) => warn!("Skipping resource {} from dropped HTML: {}", target, e),
+            }
+        }
+    }
 
-    Ok(new_path.to_string_lossy().to_string())
+    let added = add_paths_to_shelf(&paths, &file_list, &app_handle)?;
+    Ok(added
+        .iter()
+        .filter_map(|item| item.path())
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
 }
+
+// Turn text made of one or more URLs into link files (.url/.webloc/.desktop) on the shelf,
+// so dragging them out creates a clickable shortcut. Fails if the text isn't just URLs.
+// `title` is the link's title as given by a drag source, used for a single URL.
+#[tauri::command]
+pub async fn add_links_from_text(
+    app_handle: AppHandle,
+    file_list: State<'_, FileList>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    text: String,
+    title: Option<String>,
+) -> Result<Vec<String>, String> {
+    let urls = links::text_as_urls(&text).ok_or("Text does not contain only URLs")?;
+    let (settings, options) = {
+        let config = config
+            .lock()
+            .map_err(|e| format!("Failed to lock config: {}", e))?;
+        (
+            config.links.clone(),
+            DownloadOptions::from(&config.downloads),
+        )
+    };
+    let drop_folder = staging_dir()?;
+
+    let given_title = title.filter(|_| urls.len() == 1);
+    let titles = if given_title.is_some() || !settings.fetch_titles {
+        vec![given_title; urls.len()]
+    } else {
+        // Fetch every page at once rather than one after another
+        let client = download::build_client(&options)?;
+        let fetches: Vec<_> = urls
+            .iter()
+            .map(|url| {
+                let client = client.clone();
+                let url = url.clone();
+                tauri::async_runtime::spawn(async move { links::fetch_title(&client, &url).await })
+            })
+            .collect();
+        let mut titles = Vec::with_capacity(fetches.len());
+        for fetch in fetches {
+            titles.push(fetch.await.ok().flatten());
+        }
+        titles
+    };
+
+    let mut paths = Vec::new();
+    for (url, title) in urls.iter().zip(titles) {
+        paths.push(links::write_link_file(
+            url,
+            title.as_deref(),
+            settings.format,
+            &drop_folder,
+        )?);
+    }
+
+    let added = add_paths_to_shelf(&paths, &file_list, &app_handle)?;
+    Ok(added
+        .iter()
+        .filter_map(|item| item.path())
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+#[tauri::command]
+pub fn cancel_download(
+    downloads: State<'_, DownloadManager>,
+    download_id: String,
+) -> Result<(), String> {
+    if downloads.cancel(&download_id) {
+        info!("Cancelling download {}", download_id);
+        Ok(())
+    } else {
+        Err(format!("Download {} is not running", download_id))
+    }
+}
+
 #[tauri::command]
 pub fn remove_files(
     app_handle: AppHandle,
@@ -147,8 +415,8 @@ pub fn remove_files(
 
     let mut removed_files = Vec::new();
     for file_id in file_ids {
-        if let Some(pos) = list.iter().position(|f| f.id == file_id) {
-            let file_name = list[pos].name.clone();
+        if let Some(pos) = list.iter().position(|f| f.id() == file_id) {
+            let file_name = list[pos].name().to_string();
             list.remove(pos);
             removed_files.push(file_name);
             app_handle
@@ -171,7 +439,7 @@ pub fn remove_files(
 }
 
 #[tauri::command]
-pub fn get_files(file_list: State<'_, FileList>) -> Result<Vec<FileMetadata>, String> {
+pub fn get_files(file_list: State<'_, FileList>) -> Result<Vec<ShelfItem>, String> {
     let list = file_list
         .lock()
         .map_err(|_| "Failed to acquire lock".to_string())?;
@@ -188,9 +456,9 @@ pub fn rename_file(
     let mut list = file_list
         .lock()
         .map_err(|_| "Failed to acquire lock".to_string())?;
-    if let Some(file) = list.iter_mut().find(|f| f.id == file_id) {
-        let old_name = file.name.clone();
-        file.name = new_name.clone();
+    if let Some(file) = list.iter_mut().find(|f| f.id() == file_id) {
+        let old_name = file.name().to_string();
+        file.set_name(new_name.clone());
 
         // Send analytics event for file rename (fire and forget)
         let app_handle_clone = app_handle.clone();
@@ -214,6 +482,111 @@ pub fn rename_file(
     }
 }
 
+/// Put a text snippet on the shelf. It stays in memory until it needs to be a file.
+#[tauri::command]
+pub fn add_text_item(
+    app_handle: AppHandle,
+    file_list: State<'_, FileList>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    text: String,
+    name: Option<String>,
+) -> Result<u64, String> {
+    let settings = config
+        .lock()
+        .map_err(|_| "Failed to acquire config lock".to_string())?
+        .pasted_text
+        .clone();
+    let format = pasted_text_format(&text, &settings).unwrap_or(TextFormat::Plain);
+    let name = name
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| format!("snippet_{}", chrono::Local::now().format("%H%M%S")));
+
+    let mut list = file_list
+        .lock()
+        .map_err(|_| "Failed to acquire lock".to_string())?;
+    let mut item = ShelfItem::Text(TextItem::new(name, text, format));
+    let id = next_id(&list);
+    item.set_id(id);
+    list.push(item);
+
+    let _ = app_handle.emit("file_added", ());
+    app_handle
+        .emit("files_updated", ())
+        .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn update_text_item(
+    app_handle: AppHandle,
+    file_list: State<'_, FileList>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    item_id: u64,
+    text: String,
+) -> Result<(), String> {
+    let settings = config
+        .lock()
+        .map_err(|_| "Failed to acquire config lock".to_string())?
+        .pasted_text
+        .clone();
+    let format = pasted_text_format(&text, &settings);
+
+    let mut list = file_list
+        .lock()
+        .map_err(|_| "Failed to acquire lock".to_string())?;
+    match list.iter_mut().find(|item| item.id() == item_id) {
+        Some(ShelfItem::Text(item)) => {
+            if let Some(format) = format {
+                item.set_format(format);
+            }
+            item.set_text(text);
+        }
+        Some(_) => return Err(format!("Item {} is not a text snippet", item_id)),
+        None => return Err(format!("Item with ID {} not found", item_id)),
+    }
+
+    app_handle
+        .emit("files_updated", ())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Write an in-memory shelf item to the staging folder (if it isn't already) and
+/// return the path of the file.
+#[tauri::command]
+pub fn materialize_item(
+    file_list: State<'_, FileList>,
+    config: State<'_, Arc<Mutex<AppConfig>>>,
+    item_id: u64,
+) -> Result<String, String> {
+    let path = materialize_shelf_item(&file_list, item_id, link_format(&config)?)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Format URL items are written in when they need a file.
+pub(crate) fn link_format(config: &Mutex<AppConfig>) -> Result<LinkFormat, String> {
+    config
+        .lock()
+        .map(|config| config.links.format)
+        .map_err(|e| format!("Failed to lock config: {}", e))
+}
+
+pub(crate) fn materialize_shelf_item(
+    file_list: &FileList,
+    item_id: u64,
+    link_format: LinkFormat,
+) -> Result<PathBuf, String> {
+    let dir = staging_dir()?;
+    let mut list = file_list
+        .lock()
+        .map_err(|_| "Failed to acquire lock".to_string())?;
+    let item = list
+        .iter_mut()
+        .find(|item| item.id() == item_id)
+        .ok_or_else(|| format!("Item with ID {} not found", item_id))?;
+    item.materialize(&dir, link_format)
+}
+
 #[tauri::command]
 pub fn clear_files(app_handle: AppHandle, file_list: State<'_, FileList>) -> Result<(), String> {
     let mut list = file_list
@@ -249,7 +622,8 @@ pub fn refresh_file_list(
     let mut new_list = Vec::new();
 
     for file in list.iter() {
-        if file.path.exists() {
+        // Only files and folders can disappear from under us
+        if !file.is_file_backed() || file.path().is_some_and(|p| p.exists()) {
             new_list.push(file.clone());
         } else {
             needs_update = true;
@@ -1,3 +1,4 @@
+pub mod clipboard_ops;
 pub mod config_ops;
 pub mod drag_ops;
 pub mod file_ops;
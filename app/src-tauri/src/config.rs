@@ -13,20 +13,472 @@ pub struct AppConfig {
     pub hotkey: String,
     pub analytics_enabled: bool,
     pub analytics_uuid: String,
+    #[serde(default)]
+    pub downloads: DownloadConfig,
+    #[serde(default)]
+    pub html_ingest: HtmlIngestConfig,
+    #[serde(default)]
+    pub links: LinkConfig,
+    #[serde(default)]
+    pub pasted_text: PastedTextConfig,
+    #[serde(default)]
+    pub clipboard_watch: ClipboardWatchConfig,
+    #[serde(default)]
+    pub drag_out: DragOutConfig,
+    #[serde(default)]
+    pub drop_rules: DropRulesConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MouseMonitorConfig {
     pub required_shakes: u32,
     pub shake_time_limit: u64,
+    /// Shortest stroke between two reversals that counts, in pixels. This used to be
+    /// how far the cursor had to move between two polls; saved values are kept as they
+    /// are, since a stroke with such a move in it was at least that long.
     pub shake_threshold: i32,
     pub window_close_delay: u64,
-    #[serde(default = "default_whitelist")]
-    pub whitelist: Vec<String>,
+    /// Decide, in order, which apps a drag may come from; the first matching rule wins
+    #[serde(default = "default_app_rules")]
+    pub app_rules: Vec<AppRule>,
+    /// For drags from apps no rule matches
+    #[serde(default = "default_unmatched_action")]
+    pub unmatched_action: RuleAction,
+    /// Replaced by `app_rules`; only read to migrate older configs
+    #[serde(default, skip_serializing)]
+    pub whitelist: Option<Vec<String>>,
+    #[serde(default)]
+    pub shake_axis: ShakeAxis,
+    /// Slowest stroke that counts, in pixels per second
+    #[serde(default = "default_min_shake_speed")]
+    pub min_shake_speed: f64,
+    #[serde(default)]
+    pub edge_dwell: EdgeDwellConfig,
+    #[serde(default)]
+    pub drop_strip: DropStripConfig,
+    #[serde(default)]
+    pub key_trigger: KeyTriggerConfig,
 }
 
-fn default_whitelist() -> Vec<String> {
-    vec!["explorer.exe".to_string()]
+#[cfg(target_os = "windows")]
+fn default_app_rules() -> Vec<AppRule> {
+    vec![AppRule::allow("explorer.exe")]
+}
+
+#[cfg(target_os = "linux")]
+fn default_app_rules() -> Vec<AppRule> {
+    ["nautilus", "nemo", "caja", "dolphin", "thunar", "pcmanfm"]
+        .into_iter()
+        .map(AppRule::allow)
+        .collect()
+}
+
+// Drags on macOS are allowed from any app
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn default_app_rules() -> Vec<AppRule> {
+    Vec::new()
+}
+
+fn default_unmatched_action() -> RuleAction {
+    if cfg!(target_os = "macos") {
+        RuleAction::Allow
+    } else {
+        RuleAction::Deny
+    }
+}
+
+impl MouseMonitorConfig {
+    // The whitelist allowed apps whose name contained an entry, or every app when it
+    // was empty. macOS never applied it, and Linux configs only carry the Windows default.
+    fn migrate_whitelist(&mut self, whitelist: Vec<String>) {
+        let unused = cfg!(target_os = "macos")
+            || (cfg!(target_os = "linux") && whitelist == ["explorer.exe"]);
+        if unused {
+            return;
+        }
+        self.unmatched_action = if whitelist.is_empty() {
+            RuleAction::Allow
+        } else {
+            RuleAction::Deny
+        };
+        self.app_rules = whitelist.iter().map(AppRule::allow).collect();
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AppRule {
+    pub action: RuleAction,
+    pub pattern: String,
+    #[serde(default)]
+    pub match_kind: MatchKind,
+    #[serde(default)]
+    pub match_on: MatchTarget,
+    /// Shake settings for drags from this app, in place of the global ones
+    #[serde(default)]
+    pub required_shakes: Option<u32>,
+    #[serde(default)]
+    pub shake_threshold: Option<i32>,
+    #[serde(default)]
+    pub shake_time_limit: Option<u64>,
+    #[serde(default)]
+    pub min_shake_speed: Option<f64>,
+}
+
+impl AppRule {
+    fn allow(name: impl Into<String>) -> Self {
+        AppRule {
+            action: RuleAction::Allow,
+            pattern: name.into(),
+            match_kind: MatchKind::Exact,
+            match_on: MatchTarget::Name,
+            required_shakes: None,
+            shake_threshold: None,
+            shake_time_limit: None,
+            min_shake_speed: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    Deny,
+}
+
+// All matching ignores case
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchKind {
+    /// The whole name or path; `.exe` may be left off names
+    #[default]
+    Exact,
+    /// `*` and `?` wildcards over the whole name or path
+    Glob,
+    /// A regular expression found anywhere in the name or path
+    Regex,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MatchTarget {
+    /// The executable's file name, e.g. `explorer.exe`
+    #[default]
+    Name,
+    /// The executable's full path
+    Path,
+}
+
+fn default_min_shake_speed() -> f64 {
+    800.0
+}
+
+// Which cursor reversals count towards a shake
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ShakeAxis {
+    #[default]
+    Horizontal,
+    Vertical,
+    /// Reversals in any direction, circles included
+    Any,
+}
+
+// Opens the shelf when a drag rests at a screen edge or corner
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct EdgeDwellConfig {
+    pub enabled: bool,
+    pub edges: Vec<ScreenEdge>,
+    /// How long the cursor has to stay at the edge, in milliseconds
+    pub dwell_ms: u64,
+    /// Distance from a side of the screen that counts as being at it, in pixels
+    pub edge_size: u32,
+    /// Size of the square at each corner that counts as the corner, in pixels
+    pub corner_size: u32,
+}
+
+impl Default for EdgeDwellConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            edges: vec![ScreenEdge::TopRight],
+            dwell_ms: 600,
+            edge_size: 2,
+            corner_size: 40,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreenEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+// A thin always-on-top window on the primary screen that opens the shelf when a drag
+// enters it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct DropStripConfig {
+    pub enabled: bool,
+    pub side: ScreenSide,
+    /// In pixels
+    pub thickness: u32,
+    /// In pixels, centred on the side; 0 spans the whole side
+    pub length: u32,
+}
+
+impl Default for DropStripConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            side: ScreenSide::Top,
+            thickness: 4,
+            length: 240,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenSide {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+// Opens the shelf when keys are held, or tapped twice, during a drag
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct KeyTriggerConfig {
+    pub enabled: bool,
+    /// Written like the global hotkey, e.g. `Shift+Ctrl` or `Alt`
+    pub keys: String,
+    /// Whether the keys have to be pressed twice
+    pub double_tap: bool,
+    /// Longest time between the two presses of a double tap, in milliseconds
+    pub double_tap_ms: u64,
+}
+
+impl Default for KeyTriggerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keys: "Shift+Ctrl".to_string(),
+            double_tap: false,
+            double_tap_ms: 400,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DownloadConfig {
+    /// Downloads larger than this are aborted
+    pub max_size_mb: u64,
+    pub max_redirects: usize,
+    pub connect_timeout_secs: u64,
+    /// Maximum time to wait for the next chunk of a response body
+    pub read_timeout_secs: u64,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            max_size_mb: 1024,
+            max_redirects: 10,
+            connect_timeout_secs: 15,
+            read_timeout_secs: 30,
+        }
+    }
+}
+
+// What to keep when HTML is dropped or pasted on the shelf
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct HtmlIngestConfig {
+    pub save_html: bool,
+    pub save_markdown: bool,
+    /// Download `<img src>` targets as separate shelf items
+    pub extract_images: bool,
+    /// Download `<a href>` targets as separate shelf items
+    pub extract_links: bool,
+    /// Most images and links downloaded from one snippet, images first
+    pub max_resources: usize,
+}
+
+impl Default for HtmlIngestConfig {
+    fn default() -> Self {
+        Self {
+            save_html: true,
+            save_markdown: false,
+            extract_images: false,
+            extract_links: false,
+            max_resources: 20,
+        }
+    }
+}
+
+// File format used for dropped or pasted URLs
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkFormat {
+    /// The native format of the current platform
+    #[default]
+    Auto,
+    /// Windows Internet Shortcut
+    Url,
+    /// macOS Web Location
+    Webloc,
+    /// freedesktop.org `Type=Link` entry
+    Desktop,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LinkConfig {
+    pub format: LinkFormat,
+    /// Fetch the page to name the link file after its title
+    pub fetch_titles: bool,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self {
+            format: LinkFormat::Auto,
+            fetch_titles: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PastedTextConfig {
+    /// Pick the file extension from the content instead of always using `.txt`
+    pub detect_format: bool,
+    /// Re-indent JSON and XML before saving
+    pub pretty_print: bool,
+}
+
+impl Default for PastedTextConfig {
+    fn default() -> Self {
+        Self {
+            detect_format: true,
+            pretty_print: false,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ClipboardWatchConfig {
+    /// Collect everything copied to the clipboard into the clipboard shelf
+    pub enabled: bool,
+    /// Apps whose clipboard content is never collected, by whole process or app name
+    /// (case and a `.exe` suffix are ignored)
+    pub ignored_apps: Vec<String>,
+    pub poll_interval_ms: u64,
+    /// Oldest entries are dropped once the clipboard shelf holds this many
+    pub max_items: usize,
+}
+
+impl Default for ClipboardWatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ignored_apps: vec![
+                "1Password".to_string(),
+                "Bitwarden".to_string(),
+                "KeePassXC".to_string(),
+                "KeePass".to_string(),
+                "Dashlane".to_string(),
+                "LastPass".to_string(),
+                "Keychain Access".to_string(),
+            ],
+            poll_interval_ms: 500,
+            max_items: 200,
+        }
+    }
+}
+
+// Operation offered to drop targets when dragging items out of the shelf
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DragOutMode {
+    Copy,
+    Move,
+    Link,
+    /// Offer copy and move and let the target (and held modifier keys) decide
+    #[default]
+    Auto,
+}
+
+// Whether dragged items leave the shelf once they are dropped
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoveAfterDrop {
+    #[default]
+    Never,
+    Always,
+    /// Only when the target moved the files rather than copying or linking them
+    IfMoved,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DragOutConfig {
+    pub mode: DragOutMode,
+    /// Hide the main window (and close the popup) after a successful drop
+    pub hide_window: bool,
+    pub remove_items: RemoveAfterDrop,
+}
+
+impl Default for DragOutConfig {
+    fn default() -> Self {
+        Self {
+            mode: DragOutMode::Auto,
+            hide_window: true,
+            remove_items: RemoveAfterDrop::Never,
+        }
+    }
+}
+
+// Coarse file types the drop rules can allow or block, derived from the file extension
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileKind {
+    Image,
+    Video,
+    Audio,
+    Document,
+    Archive,
+    Text,
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct DropRulesConfig {
+    /// Largest total size of a drop, folders included; 0 for no limit
+    pub max_total_size_mb: u64,
+    /// Most items in a single drop; 0 for no limit
+    pub max_items: usize,
+    /// If not empty, only files with these extensions or kinds are accepted
+    pub allowed_extensions: Vec<String>,
+    pub allowed_kinds: Vec<FileKind>,
+    /// Files with these extensions or kinds are never accepted
+    pub blocked_extensions: Vec<String>,
+    pub blocked_kinds: Vec<FileKind>,
+    pub reject_folders: bool,
 }
 
 impl Default for AppConfig {
@@ -37,12 +489,26 @@ impl Default for AppConfig {
                 shake_time_limit: 1500,
                 shake_threshold: 100,
                 window_close_delay: 3000,
-                whitelist: default_whitelist(),
+                app_rules: default_app_rules(),
+                unmatched_action: default_unmatched_action(),
+                whitelist: None,
+                shake_axis: ShakeAxis::Horizontal,
+                min_shake_speed: default_min_shake_speed(),
+                edge_dwell: EdgeDwellConfig::default(),
+                drop_strip: DropStripConfig::default(),
+                key_trigger: KeyTriggerConfig::default(),
             },
             autostart: false,
             hotkey: "".to_string(),
             analytics_enabled: false,
             analytics_uuid: uuid::Uuid::new_v4().to_string(),
+            downloads: DownloadConfig::default(),
+            html_ingest: HtmlIngestConfig::default(),
+            links: LinkConfig::default(),
+            pasted_text: PastedTextConfig::default(),
+            clipboard_watch: ClipboardWatchConfig::default(),
+            drag_out: DragOutConfig::default(),
+            drop_rules: DropRulesConfig::default(),
         }
     }
 }
@@ -76,6 +542,10 @@ impl AppConfig {
                         config.analytics_uuid = Uuid::new_v4().to_string();
                         info!("Generated new analytics UUID while loading config");
                     }
+                    if let Some(whitelist) = config.mouse_monitor.whitelist.take() {
+                        config.mouse_monitor.migrate_whitelist(whitelist);
+                        info!("Migrated the app whitelist to app rules");
+                    }
                     return config;
                 }
                 Err(e) => {
@@ -123,3 +593,51 @@ impl AppConfig {
         Ok(config_path)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrated(whitelist: &[&str]) -> MouseMonitorConfig {
+        let mut config = AppConfig::default().mouse_monitor;
+        config.migrate_whitelist(whitelist.iter().map(|name| name.to_string()).collect());
+        config
+    }
+
+    #[test]
+    fn migrates_whitelist_to_allow_rules() {
+        let config = migrated(&["explorer.exe", "Code.exe"]);
+        if cfg!(target_os = "macos") {
+            assert_eq!(config.app_rules, default_app_rules());
+            assert_eq!(config.unmatched_action, RuleAction::Allow);
+        } else {
+            assert_eq!(
+                config.app_rules,
+                vec![AppRule::allow("explorer.exe"), AppRule::allow("Code.exe")]
+            );
+            assert_eq!(config.unmatched_action, RuleAction::Deny);
+        }
+    }
+
+    #[test]
+    fn empty_whitelist_allows_every_app() {
+        let config = migrated(&[]);
+        if cfg!(target_os = "macos") {
+            assert_eq!(config.app_rules, default_app_rules());
+        } else {
+            assert!(config.app_rules.is_empty());
+        }
+        assert_eq!(config.unmatched_action, RuleAction::Allow);
+    }
+
+    #[test]
+    fn default_whitelist_keeps_default_rules_off_windows() {
+        let config = migrated(&["explorer.exe"]);
+        if cfg!(target_os = "windows") {
+            assert_eq!(config.app_rules, vec![AppRule::allow("explorer.exe")]);
+        } else {
+            assert_eq!(config.app_rules, default_app_rules());
+        }
+        assert_eq!(config.unmatched_action, default_unmatched_action());
+    }
+}
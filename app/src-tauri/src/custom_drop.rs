@@ -1,9 +1,13 @@
 #![cfg(target_os = "windows")]
 
-use serde::Serialize;
+use crate::drop_rules;
+use crate::drop_target::DropPayload;
+use base64::{engine::general_purpose, Engine as _};
 use std::ffi::OsString;
 use std::os::windows::ffi::OsStringExt;
-use tauri::{AppHandle, Emitter};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager};
 use windows::core::{implement, Result as WindowsResult};
 use windows::Win32::Foundation::POINTL;
 use windows::Win32::System::Com::{
@@ -11,27 +15,32 @@ use windows::Win32::System::Com::{
 };
 use windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
 use windows::Win32::System::Ole::{
-    IDropTarget, IDropTarget_Impl, DROPEFFECT, DROPEFFECT_COPY,
+    IDropTarget, IDropTarget_Impl, ReleaseStgMedium, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_NONE,
 };
 use windows::Win32::System::SystemServices::MODIFIERKEYS_FLAGS;
 use windows::Win32::UI::Shell::{DragQueryFileW, HDROP};
 
-#[derive(Clone, Serialize)]
-#[serde(tag = "type", content = "data")]
-pub enum DropPayload {
-    Files(Vec<String>),
-    Text(String),
-    Html(String),
-}
-
 #[implement(IDropTarget)]
 pub struct CustomDropTarget {
     app_handle: AppHandle,
+    // Why the drag over the window breaks the drop rules, checked once when it enters
+    rejection: Mutex<Option<String>>,
 }
 
 impl CustomDropTarget {
     pub fn new(app_handle: AppHandle) -> Self {
-        Self { app_handle }
+        Self {
+            app_handle,
+            rejection: Mutex::new(None),
+        }
+    }
+
+    // The effect to report while the drag is over the window
+    fn drop_effect(&self) -> DROPEFFECT {
+        match self.rejection.lock().map(|rejection| rejection.is_some()) {
+            Ok(true) => DROPEFFECT_NONE,
+            _ => DROPEFFECT_COPY,
+        }
     }
 
     unsafe fn extract_files(&self, pdataobj: &IDataObject) -> Option<Vec<String>> {
@@ -161,6 +170,57 @@ impl CustomDropTarget {
         }
         None
     }
+
+    unsafe fn extract_bytes(&self, pdataobj: &IDataObject, format: u16) -> Option<Vec<u8>> {
+        let mut fmt = FORMATETC {
+            cfFormat: format,
+            ptd: std::ptr::null_mut(),
+            dwAspect: DVASPECT_CONTENT.0,
+            lindex: -1,
+            tymed: TYMED_HGLOBAL.0 as u32,
+        };
+
+        let mut stg = pdataobj.GetData(&mut fmt).ok()?;
+        let hglobal = stg.u.hGlobal;
+        let mut bytes = None;
+        if !hglobal.is_invalid() {
+            let ptr = GlobalLock(hglobal) as *const u8;
+            if !ptr.is_null() {
+                bytes = Some(std::slice::from_raw_parts(ptr, GlobalSize(hglobal)).to_vec());
+                let _ = GlobalUnlock(hglobal);
+            }
+        }
+        // The medium belongs to us now that GetData returned it
+        ReleaseStgMedium(&mut stg);
+        bytes
+    }
+
+    // Bitmap data without a file, e.g. from image editors. Browsers and Office put the
+    // original PNG next to the CF_DIB bitmap.
+    unsafe fn extract_image(&self, pdataobj: &IDataObject) -> Option<(String, Vec<u8>)> {
+        let png_fmt_id = windows::Win32::System::DataExchange::RegisterClipboardFormatW(windows::core::w!("PNG"));
+        if png_fmt_id != 0 {
+            if let Some(png) = self.extract_bytes(pdataobj, png_fmt_id as u16) {
+                return Some(("image/png".to_string(), png));
+            }
+        }
+        let dib = self.extract_bytes(pdataobj, 8)?; // CF_DIB
+        crate::clipboard::dib_to_bmp(&dib).map(|bmp| ("image/bmp".to_string(), bmp))
+    }
+
+    unsafe fn extract_url(&self, pdataobj: &IDataObject) -> Option<String> {
+        let url_fmt_id = windows::Win32::System::DataExchange::RegisterClipboardFormatW(windows::core::w!("UniformResourceLocatorW"));
+        if url_fmt_id == 0 {
+            return None;
+        }
+        let bytes = self.extract_bytes(pdataobj, url_fmt_id as u16)?;
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .take_while(|&unit| unit != 0)
+            .collect();
+        Some(String::from_utf16_lossy(&units)).filter(|url| !url.is_empty())
+    }
 }
 
 impl IDropTarget_Impl for CustomDropTarget_Impl {
@@ -182,8 +242,26 @@ impl IDropTarget_Impl for CustomDropTarget_Impl {
                 }
             }
 
+            let rejection = pdataobj
+                .as_ref()
+                .and_then(|obj| self.extract_files(obj))
+                .map(|files| files.iter().map(PathBuf::from).collect::<Vec<_>>())
+                .and_then(|paths| {
+                    drop_rules::check_hovered_paths(
+                        &drop_rules::current_rules(&self.app_handle),
+                        &paths,
+                    )
+                    .err()
+                });
+            if let Some(reason) = &rejection {
+                drop_rules::reject(&self.app_handle, reason);
+            }
+            if let Ok(mut current) = self.rejection.lock() {
+                *current = rejection;
+            }
+
             if !pdweffect.is_null() {
-                *pdweffect = DROPEFFECT_COPY;
+                *pdweffect = self.drop_effect();
             }
         }
         Ok(())
@@ -197,13 +275,16 @@ impl IDropTarget_Impl for CustomDropTarget_Impl {
     ) -> WindowsResult<()> {
         unsafe {
             if !pdweffect.is_null() {
-                *pdweffect = DROPEFFECT_COPY;
+                *pdweffect = self.drop_effect();
             }
         }
         Ok(())
     }
 
     fn DragLeave(&self) -> WindowsResult<()> {
+        if let Ok(mut rejection) = self.rejection.lock() {
+            *rejection = None;
+        }
         Ok(())
     }
 
@@ -215,11 +296,16 @@ impl IDropTarget_Impl for CustomDropTarget_Impl {
         pdweffect: *mut DROPEFFECT,
     ) -> WindowsResult<()> {
         unsafe {
+            let rejected = self.rejection.lock().ok().and_then(|mut rejection| rejection.take()).is_some();
             if !pdweffect.is_null() {
-                *pdweffect = DROPEFFECT_COPY;
+                *pdweffect = if rejected { DROPEFFECT_NONE } else { DROPEFFECT_COPY };
+            }
+            if rejected {
+                return Ok(());
             }
 
             if let Some(dataobj) = pdataobj.as_ref() {
+                self.app_handle.state::<std::sync::Arc<crate::DragState>>().record_drop();
                 let mut debug_log = String::new();
                 debug_log.push_str("Drop called! Attempting to extract files...\n");
                 
@@ -232,8 +318,29 @@ impl IDropTarget_Impl for CustomDropTarget_Impl {
                     }
                 }
                 
-                debug_log.push_str("extract_files failed, attempting extract_html...\n");
-                if let Some(html) = self.extract_html(dataobj) {
+                // Image data wins over HTML that embeds the image, and comes with that HTML,
+                // whose alt text names the image. Other HTML (e.g. a table) is kept as HTML.
+                let html = self.extract_html(dataobj).filter(|html| !html.is_empty());
+                let image = html
+                    .as_ref()
+                    .is_none_or(|html| html.contains("<img"))
+                    .then(|| self.extract_image(dataobj))
+                    .flatten();
+                debug_log.push_str("extract_files failed, attempting extract_image...\n");
+                if let Some((mime, data)) = image {
+                    debug_log.push_str(&format!("extract_image succeeded: {} ({} bytes)\n", mime, data.len()));
+                    let payload = DropPayload::Image {
+                        mime,
+                        data_base64: general_purpose::STANDARD.encode(data),
+                        html,
+                        url: self.extract_url(dataobj),
+                    };
+                    let _ = self.app_handle.emit("native_drop", payload);
+                    return Ok(());
+                }
+
+                debug_log.push_str("extract_image failed, attempting extract_html...\n");
+                if let Some(html) = html {
                     debug_log.push_str(&format!("extract_html succeeded, length: {}\nHTML HEAD: {}\n", html.len(), &html[..html.len().min(300)]));
                     if !html.is_empty() {
                         let _ = self.app_handle.emit("native_drop", DropPayload::Html(html));
@@ -0,0 +1,566 @@
+use crate::config::DownloadConfig;
+use crate::file::{sanitize_file_name, unique_path};
+use percent_encoding::percent_decode_str;
+use reqwest::header::{CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_TYPE, RANGE};
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// How often progress is reported while a body is streamed to disk
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    pub max_bytes: u64,
+    pub max_redirects: usize,
+    pub connect_timeout: Duration,
+    pub read_timeout: Duration,
+}
+
+impl From<&DownloadConfig> for DownloadOptions {
+    fn from(config: &DownloadConfig) -> Self {
+        Self {
+            max_bytes: config.max_size_mb.saturating_mul(1024 * 1024),
+            max_redirects: config.max_redirects,
+            connect_timeout: Duration::from_secs(config.connect_timeout_secs),
+            read_timeout: Duration::from_secs(config.read_timeout_secs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub id: String,
+    pub received: u64,
+    pub total: Option<u64>,
+}
+
+// Cancellation flags of the downloads that are currently running, by download id
+#[derive(Default)]
+pub struct DownloadManager {
+    active: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl DownloadManager {
+    pub fn register(&self, id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        if let Ok(mut active) = self.active.lock() {
+            active.insert(id.to_string(), flag.clone());
+        }
+        flag
+    }
+
+    pub fn cancel(&self, id: &str) -> bool {
+        match self.active.lock() {
+            Ok(active) => match active.get(id) {
+                Some(flag) => {
+                    flag.store(true, Ordering::Relaxed);
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+
+    pub fn finish(&self, id: &str) {
+        if let Ok(mut active) = self.active.lock() {
+            active.remove(id);
+        }
+    }
+}
+
+pub fn build_client(options: &DownloadOptions) -> Result<Client, String> {
+    Client::builder()
+        .redirect(reqwest::redirect::Policy::limited(options.max_redirects))
+        .connect_timeout(options.connect_timeout)
+        .read_timeout(options.read_timeout)
+        .user_agent(concat!("holdem/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+/// Download `url` into `dest_dir` and return the path of the finished file.
+///
+/// The body is streamed to a `.part` file next to the destination. If a previous attempt
+/// with the same download id and URL left one behind, the download resumes from it with a
+/// `Range` request. The partial file is kept when the download fails or is cancelled so it
+/// can be resumed.
+pub async fn download_to_dir<F: Fn(u64, Option<u64>)>(
+    client: &Client,
+    id: &str,
+    url: &str,
+    dest_dir: &Path,
+    options: &DownloadOptions,
+    cancelled: &AtomicBool,
+    on_progress: F,
+) -> Result<PathBuf, String> {
+    let part_path = dest_dir.join(partial_file_name(id, url));
+
+    let mut response = None;
+    let mut resume_from = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+    for _ in 0..2 {
+        let mut request = client.get(url);
+        if resume_from > 0 {
+            request = request.header(RANGE, format!("bytes={}-", resume_from));
+        }
+        let resp = request.send().await.map_err(|e| e.to_string())?;
+
+        if resp.status() == StatusCode::RANGE_NOT_SATISFIABLE && resume_from > 0 {
+            // The partial file doesn't match the remote file anymore, start over
+            warn!("Server rejected resume of {} at {} bytes", url, resume_from);
+            let _ = fs::remove_file(&part_path);
+            resume_from = 0;
+            continue;
+        }
+        response = Some(resp);
+        break;
+    }
+    let mut response = response.ok_or("Failed to download file")?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Download failed with HTTP status {}", status));
+    }
+
+    // Only append to the partial file if the server actually honoured our range
+    let resumed = status == StatusCode::PARTIAL_CONTENT
+        && response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(content_range_start)
+            == Some(resume_from);
+    if resume_from > 0 && !resumed {
+        info!("Server does not support resuming {}, restarting", url);
+        resume_from = 0;
+    }
+
+    let total = response.content_length().map(|len| len + resume_from);
+    if let Some(total) = total {
+        if total > options.max_bytes {
+            return Err(format!(
+                "File is too large ({} bytes, limit is {} bytes)",
+                total, options.max_bytes
+            ));
+        }
+    }
+
+    let file_name = response_file_name(&response);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part_path)
+        .map_err(|e| e.to_string())?;
+
+    let mut received = resume_from;
+    let mut last_progress = Instant::now();
+    on_progress(received, total);
+
+    while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err("Download cancelled".to_string());
+        }
+
+        received += chunk.len() as u64;
+        if received > options.max_bytes {
+            drop(file);
+            let _ = fs::remove_file(&part_path);
+            return Err(format!(
+                "File is too large (limit is {} bytes)",
+                options.max_bytes
+            ));
+        }
+        file.write_all(&chunk).map_err(|e| e.to_string())?;
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            on_progress(received, total);
+            last_progress = Instant::now();
+        }
+    }
+    file.flush().map_err(|e| e.to_string())?;
+    drop(file);
+    on_progress(received, total);
+
+    let final_path = unique_path(dest_dir, &file_name);
+    fs::rename(&part_path, &final_path).map_err(|e| e.to_string())?;
+    info!(
+        "Downloaded {} ({} bytes) to {:?}",
+        url, received, final_path
+    );
+
+    Ok(final_path)
+}
+
+// Name for the partial file of a download, so a retry with the same id can pick it up
+// again while other downloads of the URL get their own. FNV-1a gives the same name on
+// every build, which `DefaultHasher` doesn't promise across Rust releases.
+fn partial_file_name(id: &str, url: &str) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = [id.as_bytes(), b"\0", url.as_bytes()]
+        .concat()
+        .iter()
+        .fold(FNV_OFFSET_BASIS, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        });
+    format!(".download-{:016x}.part", hash)
+}
+
+// Parse the first byte position out of a `Content-Range: bytes 100-199/200` header
+fn content_range_start(value: &str) -> Option<u64> {
+    value
+        .strip_prefix("bytes ")?
+        .split('-')
+        .next()?
+        .trim()
+        .parse()
+        .ok()
+}
+
+// Work out the name of the downloaded file: `Content-Disposition` first, then the last
+// segment of the (post-redirect) URL, with an extension from `Content-Type` if it has none
+fn response_file_name(response: &reqwest::Response) -> String {
+    let headers = response.headers();
+    let mime = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or(v).trim().to_lowercase());
+
+    let name = headers
+        .get(CONTENT_DISPOSITION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(content_disposition_file_name)
+        .or_else(|| {
+            response
+                .url()
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .map(|segment| percent_decode_str(segment).decode_utf8_lossy().to_string())
+        })
+        .map(|name| sanitize_file_name(&name))
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("downloaded_{}", chrono::Local::now().format("%H%M%S")));
+
+    let has_extension = Path::new(&name).extension().is_some();
+    match mime.as_deref().and_then(extension_for_mime) {
+        Some(ext) if !has_extension => format!("{}.{}", name, ext),
+        _ => name,
+    }
+}
+
+// Extract the file name from a `Content-Disposition` header, preferring the RFC 5987
+// `filename*=UTF-8''...` form over the plain `filename="..."` one
+pub fn content_disposition_file_name(value: &str) -> Option<String> {
+    let mut plain = None;
+    for param in value.split(';').skip(1) {
+        let Some((key, val)) = param.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let val = val.trim();
+        match key.as_str() {
+            "filename*" => {
+                // charset'language'percent-encoded-value
+                let mut parts = val.splitn(3, '\'');
+                let charset = parts.next().unwrap_or_default();
+                let Some(encoded) = parts.nth(1) else {
+                    continue;
+                };
+                let decoded = percent_decode_str(encoded);
+                let name = if charset.eq_ignore_ascii_case("utf-8") {
+                    decoded.decode_utf8_lossy().to_string()
+                } else {
+                    // ISO-8859-1 maps byte for byte onto the first 256 code points
+                    decoded.map(char::from).collect()
+                };
+                if !name.is_empty() {
+                    return Some(name);
+                }
+            }
+            "filename" => {
+                let name = val.trim_matches('"').replace("\\\"", "\"");
+                if !name.is_empty() {
+                    plain = Some(name);
+                }
+            }
+            _ => {}
+        }
+    }
+    plain
+}
+
+pub fn extension_for_mime(mime: &str) -> Option<&'static str> {
+    let ext = match mime {
+        "image/png" => "png",
+        "image/jpeg" | "image/jpg" | "image/pjpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/webp" => "webp",
+        "image/svg+xml" => "svg",
+        "image/bmp" | "image/x-ms-bmp" => "bmp",
+        "image/tiff" => "tiff",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        "image/avif" => "avif",
+        "image/heic" => "heic",
+        "video/mp4" => "mp4",
+        "video/webm" => "webm",
+        "video/quicktime" => "mov",
+        "audio/mpeg" => "mp3",
+        "audio/ogg" => "ogg",
+        "audio/wav" | "audio/x-wav" => "wav",
+        "application/pdf" => "pdf",
+        "application/zip" => "zip",
+        "application/gzip" => "gz",
+        "application/json" => "json",
+        "application/xml" | "text/xml" => "xml",
+        "text/html" => "html",
+        "text/plain" => "txt",
+        "text/csv" => "csv",
+        "text/markdown" => "md",
+        _ => return None,
+    };
+    Some(ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // A local HTTP stand-in: every connection gets the response `respond` builds from
+    // the request head
+    async fn serve<F>(respond: F) -> String
+    where
+        F: Fn(&str) -> Vec<u8> + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let respond = Arc::new(respond);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let respond = respond.clone();
+                tokio::spawn(async move {
+                    let mut head = Vec::new();
+                    let mut buf = [0u8; 1024];
+                    while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match stream.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => head.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let response = respond(&String::from_utf8_lossy(&head).to_lowercase());
+                    let _ = stream.write_all(&response).await;
+                    let _ = stream.shutdown().await;
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn response(status: &str, headers: &[(&str, &str)], body: &[u8]) -> Vec<u8> {
+        let mut head = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+            status,
+            body.len()
+        );
+        for (name, value) in headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+        let mut response = head.into_bytes();
+        response.extend_from_slice(body);
+        response
+    }
+
+    fn options() -> DownloadOptions {
+        DownloadOptions {
+            max_bytes: 1024,
+            max_redirects: 3,
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(5),
+        }
+    }
+
+    async fn download(url: &str, dir: &Path, options: &DownloadOptions) -> Result<PathBuf, String> {
+        download_cancellable(url, dir, options, &AtomicBool::new(false)).await
+    }
+
+    async fn download_cancellable(
+        url: &str,
+        dir: &Path,
+        options: &DownloadOptions,
+        cancelled: &AtomicBool,
+    ) -> Result<PathBuf, String> {
+        let client = build_client(options).unwrap();
+        download_to_dir(&client, "test", url, dir, options, cancelled, |_, _| {}).await
+    }
+
+    #[tokio::test]
+    async fn names_file_from_content_disposition() {
+        let base = serve(|_| {
+            response(
+                "200 OK",
+                &[("Content-Disposition", "attachment; filename=\"report.pdf\"")],
+                b"%PDF",
+            )
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = download(&format!("{}/download?id=1", base), dir.path(), &options())
+            .await
+            .unwrap();
+        assert_eq!(path.file_name().unwrap(), "report.pdf");
+        assert_eq!(fs::read(&path).unwrap(), b"%PDF");
+    }
+
+    #[tokio::test]
+    async fn adds_extension_from_content_type() {
+        let base = serve(|_| response("200 OK", &[("Content-Type", "image/png")], b"png")).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let path = download(&format!("{}/files/photo", base), dir.path(), &options())
+            .await
+            .unwrap();
+        assert_eq!(path.file_name().unwrap(), "photo.png");
+    }
+
+    #[tokio::test]
+    async fn fails_on_error_status() {
+        let base = serve(|_| response("404 Not Found", &[], b"missing")).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let error = download(&format!("{}/file.txt", base), dir.path(), &options())
+            .await
+            .unwrap_err();
+        assert!(error.contains("404"), "{}", error);
+        assert!(!dir.path().join("file.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn aborts_files_over_the_size_limit() {
+        let base = serve(|_| response("200 OK", &[], &[0u8; 2048])).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        let error = download(&format!("{}/big.bin", base), dir.path(), &options())
+            .await
+            .unwrap_err();
+        assert!(error.contains("too large"), "{}", error);
+        assert!(!dir.path().join("big.bin").exists());
+    }
+
+    #[tokio::test]
+    async fn stops_after_too_many_redirects() {
+        let base = serve(|_| response("302 Found", &[("Location", "/again")], b"")).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        assert!(download(&format!("{}/start", base), dir.path(), &options())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn cancelled_download_keeps_partial_file() {
+        let base = serve(|_| response("200 OK", &[], b"content")).await;
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("{}/file.txt", base);
+
+        let cancelled = AtomicBool::new(true);
+        let error = download_cancellable(&url, dir.path(), &options(), &cancelled)
+            .await
+            .unwrap_err();
+        assert_eq!(error, "Download cancelled");
+        assert!(!dir.path().join("file.txt").exists());
+        assert!(dir.path().join(partial_file_name("test", &url)).exists());
+    }
+
+    #[tokio::test]
+    async fn resumes_from_partial_file() {
+        let base = serve(|request| {
+            if request.contains("range: bytes=5-") {
+                response(
+                    "206 Partial Content",
+                    &[("Content-Range", "bytes 5-10/11")],
+                    b" world",
+                )
+            } else {
+                response("200 OK", &[], b"hello world")
+            }
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("{}/greeting.txt", base);
+        fs::write(dir.path().join(partial_file_name("test", &url)), b"hello").unwrap();
+
+        let path = download(&url, dir.path(), &options()).await.unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"hello world");
+        assert!(!dir.path().join(partial_file_name("test", &url)).exists());
+    }
+
+    #[tokio::test]
+    async fn other_downloads_of_the_url_keep_their_partial_file() {
+        let base = serve(|request| {
+            if request.contains("range: bytes=5-") {
+                response(
+                    "206 Partial Content",
+                    &[("Content-Range", "bytes 5-10/11")],
+                    b" world",
+                )
+            } else {
+                response("200 OK", &[], b"hello world")
+            }
+        })
+        .await;
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("{}/greeting.txt", base);
+        fs::write(dir.path().join(partial_file_name("other", &url)), b"HELLO").unwrap();
+
+        let path = download(&url, dir.path(), &options()).await.unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"hello world");
+        assert!(dir.path().join(partial_file_name("other", &url)).exists());
+    }
+
+    #[test]
+    fn partial_file_names_are_stable() {
+        assert_eq!(
+            partial_file_name("test", "https://example.com/file.zip"),
+            ".download-f8e29c8c2ba02aa1.part"
+        );
+        assert_ne!(
+            partial_file_name("test", "https://example.com/file.zip"),
+            partial_file_name("other", "https://example.com/file.zip")
+        );
+    }
+
+    #[test]
+    fn content_disposition_prefers_extended_name() {
+        assert_eq!(
+            content_disposition_file_name(
+                "attachment; filename=\"plain.txt\"; filename*=UTF-8''na%C3%AFve.txt"
+            )
+            .as_deref(),
+            Some("naïve.txt")
+        );
+    }
+
+    #[test]
+    fn content_disposition_falls_back_on_malformed_extended_name() {
+        assert_eq!(
+            content_disposition_file_name("attachment; filename=\"plain.txt\"; filename*=broken")
+                .as_deref(),
+            Some("plain.txt")
+        );
+    }
+}
@@ -0,0 +1,335 @@
+// Drag images for drags out of the shelf: thumbnails of up to three of the dragged
+// items stacked on top of each other, and a badge with the item count.
+//
+// Sizes are in logical pixels and multiplied by the window's scale factor, so the
+// image is sharp on high-DPI screens. The PNG records the scale as its resolution,
+// which macOS uses to show it at its logical size.
+
+use crate::thumbnail::get_thumbnail_image;
+use image::imageops::{self, FilterType};
+use image::{Pixel, Rgba, RgbaImage};
+use std::path::PathBuf;
+
+/// Logical size of the (square) drag images.
+pub const SIZE: f64 = 96.0;
+const CARD: f64 = 72.0;
+// How far each card further back in the stack is shifted up and to the right
+const STACK_OFFSET: f64 = 7.0;
+const MAX_STACKED: usize = 3;
+const CARD_PADDING: f64 = 4.0;
+const BADGE_RADIUS: f64 = 12.0;
+
+const CARD_FILL: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const CARD_BORDER: Rgba<u8> = Rgba([190, 190, 190, 255]);
+const SHADOW: Rgba<u8> = Rgba([0, 0, 0, 50]);
+const PAGE_FOLD: Rgba<u8> = Rgba([215, 215, 215, 255]);
+const BADGE_FILL: Rgba<u8> = Rgba([59, 130, 246, 255]);
+const BADGE_TEXT: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Render the drag image for `count` items as PNG, using thumbnails of the first
+/// few `paths`. Without paths (e.g. text snippets) a plain page is shown.
+pub fn render(paths: &[PathBuf], count: usize, scale: f64) -> Result<Vec<u8>, String> {
+    let scale = if scale.is_finite() && scale > 0.0 {
+        scale
+    } else {
+        1.0
+    };
+    let px = |logical: f64| (logical * scale).round() as u32;
+    let card_size = px(CARD);
+
+    let mut cards: Vec<RgbaImage> = paths
+        .iter()
+        .take(MAX_STACKED)
+        .map(|path| {
+            get_thumbnail_image(&path.to_string_lossy())
+                .map(|thumb| framed_thumbnail(&thumb, card_size, px(CARD_PADDING)))
+                .unwrap_or_else(|_| page(card_size))
+        })
+        .collect();
+    if cards.is_empty() {
+        cards.push(page(card_size));
+    }
+
+    let size = px(SIZE);
+    let mut canvas = RgbaImage::new(size, size);
+    let front_y = size - card_size - px(4.0);
+    // Back to front, so the first item ends up on top
+    for (depth, card) in cards.iter().enumerate().rev() {
+        let x = px(4.0 + STACK_OFFSET * depth as f64);
+        let y = front_y.saturating_sub(px(STACK_OFFSET * depth as f64));
+        let shadow = px(2.0).max(1);
+        draw_shadow(&mut canvas, card, x + shadow / 2, y + shadow);
+        imageops::overlay(&mut canvas, card, x as i64, y as i64);
+    }
+
+    if count > 1 {
+        draw_badge(&mut canvas, &badge_label(count), scale);
+    }
+
+    encode_png(&canvas, scale)
+}
+
+// A thumbnail fitted onto a white card with a thin border
+fn framed_thumbnail(thumb: &RgbaImage, size: u32, padding: u32) -> RgbaImage {
+    let mut card = RgbaImage::from_pixel(size, size, CARD_FILL);
+    draw_border(&mut card, CARD_BORDER);
+
+    let inner = size.saturating_sub(padding * 2).max(1);
+    let (width, height) = thumb.dimensions();
+    let fit = (inner as f64 / width.max(1) as f64).min(inner as f64 / height.max(1) as f64);
+    let fitted_width = ((width as f64 * fit).round() as u32).max(1);
+    let fitted_height = ((height as f64 * fit).round() as u32).max(1);
+    let fitted = imageops::resize(thumb, fitted_width, fitted_height, FilterType::Triangle);
+    imageops::overlay(
+        &mut card,
+        &fitted,
+        ((size - fitted_width) / 2) as i64,
+        ((size - fitted_height) / 2) as i64,
+    );
+    card
+}
+
+// A blank page with a folded corner, for items without a thumbnail
+fn page(size: u32) -> RgbaImage {
+    let width = size * 4 / 5;
+    let fold = size / 4;
+    let left = (size - width) / 2;
+    let mut page = RgbaImage::new(size, size);
+    for y in 0..size {
+        for x in left..left + width {
+            let from_right = left + width - 1 - x;
+            // Cut the top right corner off along the diagonal of the fold
+            if y + from_right < fold {
+                continue;
+            }
+            let in_fold = y < fold && from_right < fold;
+            let on_edge =
+                x == left || from_right == 0 || y == 0 || y == size - 1 || y + from_right == fold;
+            let color = if on_edge {
+                CARD_BORDER
+            } else if in_fold {
+                PAGE_FOLD
+            } else {
+                CARD_FILL
+            };
+            page.put_pixel(x, y, color);
+        }
+    }
+    page
+}
+
+fn draw_border(image: &mut RgbaImage, color: Rgba<u8>) {
+    let (width, height) = image.dimensions();
+    for x in 0..width {
+        image.put_pixel(x, 0, color);
+        image.put_pixel(x, height - 1, color);
+    }
+    for y in 0..height {
+        image.put_pixel(0, y, color);
+        image.put_pixel(width - 1, y, color);
+    }
+}
+
+// The card's silhouette in a translucent black
+fn draw_shadow(canvas: &mut RgbaImage, card: &RgbaImage, x: u32, y: u32) {
+    for (cx, cy, pixel) in card.enumerate_pixels() {
+        let (px, py) = (x + cx, y + cy);
+        if pixel.0[3] > 0 && px < canvas.width() && py < canvas.height() {
+            canvas.get_pixel_mut(px, py).blend(&SHADOW);
+        }
+    }
+}
+
+fn fill_rect(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.get_pixel_mut(px, py).blend(&color);
+        }
+    }
+}
+
+fn badge_label(count: usize) -> String {
+    if count > 999 {
+        "999+".to_string()
+    } else {
+        count.to_string()
+    }
+}
+
+// A pill in the top right corner, wide enough for the label
+fn draw_badge(canvas: &mut RgbaImage, label: &str, scale: f64) {
+    let glyph_scale = (2.0 * scale).round().max(1.0) as u32;
+    let (text_width, text_height) = text_size(label, glyph_scale);
+
+    let radius = BADGE_RADIUS * scale;
+    let margin = 1.0 * scale;
+    let straight = (text_width as f64 + 8.0 * scale - 2.0 * radius).max(0.0);
+    let cy = margin + radius;
+    let right = canvas.width() as f64 - margin - radius;
+    let left = right - straight;
+
+    // Anti-aliased by the distance from each pixel centre to the pill's edge
+    for y in 0..(cy + radius + 1.0).ceil() as u32 {
+        for x in (left - radius - 1.0).max(0.0) as u32..canvas.width() {
+            let (fx, fy) = (x as f64 + 0.5, y as f64 + 0.5);
+            let dx = fx - fx.clamp(left, right);
+            let distance = (dx * dx + (fy - cy).powi(2)).sqrt() - radius;
+            let coverage = (0.5 - distance).clamp(0.0, 1.0);
+            if coverage > 0.0 && y < canvas.height() {
+                let mut color = BADGE_FILL;
+                color.0[3] = (color.0[3] as f64 * coverage).round() as u8;
+                canvas.get_pixel_mut(x, y).blend(&color);
+            }
+        }
+    }
+
+    let text_x = ((left + right) / 2.0 - text_width as f64 / 2.0)
+        .round()
+        .max(0.0) as u32;
+    let text_y = (cy - text_height as f64 / 2.0).round().max(0.0) as u32;
+    draw_text(canvas, label, text_x, text_y, glyph_scale, BADGE_TEXT);
+}
+
+// 5x7 bitmap glyphs, one byte per row with the leftmost pixel in bit 4
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+fn glyph(c: char) -> [u8; 7] {
+    match c {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        _ => [0; 7],
+    }
+}
+
+fn text_size(text: &str, glyph_scale: u32) -> (u32, u32) {
+    let chars = text.chars().count() as u32;
+    let width = chars * GLYPH_WIDTH * glyph_scale + chars.saturating_sub(1) * glyph_scale;
+    (width, GLYPH_HEIGHT * glyph_scale)
+}
+
+fn draw_text(image: &mut RgbaImage, text: &str, x: u32, y: u32, glyph_scale: u32, color: Rgba<u8>) {
+    let advance = (GLYPH_WIDTH + 1) * glyph_scale;
+    for (i, c) in text.chars().enumerate() {
+        let glyph_x = x + i as u32 * advance;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                    continue;
+                }
+                fill_rect(
+                    image,
+                    glyph_x + column * glyph_scale,
+                    y + row as u32 * glyph_scale,
+                    glyph_scale,
+                    glyph_scale,
+                    color,
+                );
+            }
+        }
+    }
+}
+
+fn encode_png(image: &RgbaImage, scale: f64) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| format!("Failed to encode drag image: {}", e))?;
+    Ok(with_resolution(png, scale))
+}
+
+// Insert a pHYs chunk right after IHDR saying the image is `scale` times 72 DPI
+fn with_resolution(png: Vec<u8>, scale: f64) -> Vec<u8> {
+    // Signature (8 bytes) and IHDR (4 length + 4 type + 13 data + 4 CRC)
+    const IHDR_END: usize = 33;
+    if png.len() < IHDR_END {
+        return png;
+    }
+    let pixels_per_meter = (72.0 * scale / 0.0254).round() as u32;
+    let mut chunk = b"pHYs".to_vec();
+    chunk.extend_from_slice(&pixels_per_meter.to_be_bytes());
+    chunk.extend_from_slice(&pixels_per_meter.to_be_bytes());
+    chunk.push(1); // unit: meter
+
+    let mut out = Vec::with_capacity(png.len() + 21);
+    out.extend_from_slice(&png[..IHDR_END]);
+    out.extend_from_slice(&9u32.to_be_bytes());
+    out.extend_from_slice(&chunk);
+    out.extend_from_slice(&crc32(&chunk).to_be_bytes());
+    out.extend_from_slice(&png[IHDR_END..]);
+    out
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_a_page_at_the_scaled_size() {
+        let png = render(&[], 5, 2.0).unwrap();
+        let image = image::load_from_memory_with_format(&png, image::ImageFormat::Png)
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(image.dimensions(), (192, 192));
+        // The badge sits in the top right corner, the page below it on the left
+        assert_eq!(image.get_pixel(186, 25).0[..3], BADGE_FILL.0[..3]);
+        assert!(image.get_pixel(60, 150).0[3] > 0);
+        assert_eq!(image.get_pixel(0, 0).0[3], 0);
+    }
+
+    #[test]
+    fn invalid_scales_fall_back_to_one() {
+        for scale in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let png = render(&[], 1, scale).unwrap();
+            let image = image::load_from_memory(&png).unwrap();
+            assert_eq!((image.width(), image.height()), (96, 96));
+        }
+    }
+
+    #[test]
+    fn png_records_the_scale_as_its_resolution() {
+        let png = render(&[], 1, 2.0).unwrap();
+        let phys = &png[33..33 + 21];
+        assert_eq!(&phys[..8], b"\0\0\0\x09pHYs");
+        // 144 DPI is 5669 pixels per meter, in both directions
+        assert_eq!(&phys[8..17], &[0, 0, 0x16, 0x25, 0, 0, 0x16, 0x25, 1]);
+        assert_eq!(&phys[17..], &0x4952_24f0u32.to_be_bytes());
+    }
+
+    #[test]
+    fn crc_matches_the_png_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn badge_label_caps_at_three_digits() {
+        assert_eq!(badge_label(2), "2");
+        assert_eq!(badge_label(999), "999");
+        assert_eq!(badge_label(1000), "999+");
+    }
+}
@@ -0,0 +1,235 @@
+// Rules deciding which file drops the shelf takes: total size, item count, file types
+// and folders. Drop targets check them while a drag is over the window, so the cursor
+// can show that the drop is not allowed, and adding files checks them again. Walking
+// folders for their size is too slow for a drag in progress, so while hovering the
+// total size only counts files, and folders are added on the drop.
+
+use crate::config::{AppConfig, DropRulesConfig, FileKind};
+use crate::file::get_dir_size;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{error, info};
+
+/// Sent to the frontend as `drop_rejected`.
+#[derive(Clone, Serialize)]
+pub struct DropRejection {
+    pub reason: String,
+}
+
+/// The drop rules from the current configuration.
+pub fn current_rules(app: &AppHandle) -> DropRulesConfig {
+    app.try_state::<Arc<Mutex<AppConfig>>>()
+        .and_then(|config| config.lock().ok().map(|config| config.drop_rules.clone()))
+        .unwrap_or_default()
+}
+
+/// Check dropped files against the rules. The error is the reason, worded for the user.
+pub fn check_paths(rules: &DropRulesConfig, paths: &[PathBuf]) -> Result<(), String> {
+    check(rules, paths, true)
+}
+
+/// Check files dragged over the window against the rules, leaving the size of folders
+/// out of the total.
+pub fn check_hovered_paths(rules: &DropRulesConfig, paths: &[PathBuf]) -> Result<(), String> {
+    check(rules, paths, false)
+}
+
+fn check(rules: &DropRulesConfig, paths: &[PathBuf], walk_folders: bool) -> Result<(), String> {
+    let check_size = rules.max_total_size_mb > 0;
+    if rules.max_items > 0 && paths.len() > rules.max_items {
+        let noun = if rules.max_items == 1 {
+            "item"
+        } else {
+            "items"
+        };
+        return Err(format!(
+            "At most {} {} can be dropped at once",
+            rules.max_items, noun
+        ));
+    }
+
+    let mut total_size = 0;
+    for path in paths {
+        let is_dir = path.is_dir();
+        if is_dir && rules.reject_folders {
+            return Err(format!("Folders are not accepted ({})", display_name(path)));
+        }
+        if !is_dir {
+            check_type(rules, path)?;
+        }
+        if check_size && !is_dir {
+            total_size += path.metadata().map(|metadata| metadata.len()).unwrap_or(0);
+        } else if check_size && walk_folders {
+            total_size += get_dir_size(path).unwrap_or(0);
+        }
+    }
+
+    if check_size && total_size > rules.max_total_size_mb * 1024 * 1024 {
+        return Err(format!(
+            "The dropped items are larger than {} MB",
+            rules.max_total_size_mb
+        ));
+    }
+    Ok(())
+}
+
+/// Tell the frontend why a drop is not accepted.
+pub fn reject(app: &AppHandle, reason: &str) {
+    info!("Rejecting drop: {}", reason);
+    let rejection = DropRejection {
+        reason: reason.to_string(),
+    };
+    if let Err(e) = app.emit("drop_rejected", rejection) {
+        error!("Failed to emit drop_rejected event: {}", e);
+    }
+}
+
+fn check_type(rules: &DropRulesConfig, path: &Path) -> Result<(), String> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let kind = file_kind(&extension);
+    let listed = |extensions: &[String]| {
+        extensions.iter().any(|listed| {
+            listed
+                .trim_start_matches('.')
+                .eq_ignore_ascii_case(&extension)
+        })
+    };
+
+    let blocked = listed(&rules.blocked_extensions) || rules.blocked_kinds.contains(&kind);
+    let allowed = (rules.allowed_extensions.is_empty() && rules.allowed_kinds.is_empty())
+        || listed(&rules.allowed_extensions)
+        || rules.allowed_kinds.contains(&kind);
+    if blocked || !allowed {
+        return Err(format!(
+            "{} is not an accepted file type",
+            display_name(path)
+        ));
+    }
+    Ok(())
+}
+
+fn file_kind(extension: &str) -> FileKind {
+    match extension {
+        "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "bmp" | "tif" | "tiff" | "ico"
+        | "avif" | "heic" | "heif" | "raw" | "psd" => FileKind::Image,
+        "mp4" | "mov" | "m4v" | "mkv" | "webm" | "avi" | "wmv" | "flv" => FileKind::Video,
+        "mp3" | "wav" | "ogg" | "flac" | "m4a" | "aac" | "aiff" | "opus" => FileKind::Audio,
+        "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "odp"
+        | "rtf" | "pages" | "numbers" | "key" | "epub" => FileKind::Document,
+        "zip" | "gz" | "tgz" | "tar" | "rar" | "7z" | "bz2" | "xz" | "dmg" | "iso" => {
+            FileKind::Archive
+        }
+        "txt" | "md" | "csv" | "json" | "xml" | "html" | "htm" | "yaml" | "yml" | "toml"
+        | "log" => FileKind::Text,
+        _ => FileKind::Other,
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> DropRulesConfig {
+        DropRulesConfig {
+            max_total_size_mb: 1,
+            ..Default::default()
+        }
+    }
+
+    // A folder holding `size` bytes in a nested file
+    fn folder(dir: &Path, size: usize) -> PathBuf {
+        let folder = dir.join("folder");
+        std::fs::create_dir_all(folder.join("nested")).unwrap();
+        std::fs::write(folder.join("nested").join("big.bin"), vec![0u8; size]).unwrap();
+        folder
+    }
+
+    #[test]
+    fn checks_the_size_of_folders_only_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = vec![folder(dir.path(), 2 * 1024 * 1024)];
+
+        assert_eq!(check_hovered_paths(&rules(), &paths), Ok(()));
+        assert_eq!(
+            check_paths(&rules(), &paths),
+            Err("The dropped items are larger than 1 MB".to_string())
+        );
+    }
+
+    #[test]
+    fn checks_the_size_of_files_while_hovering() {
+        let dir = tempfile::tempdir().unwrap();
+        let small = dir.path().join("small.bin");
+        let large = dir.path().join("large.bin");
+        std::fs::write(&small, vec![0u8; 512 * 1024]).unwrap();
+        std::fs::write(&large, vec![0u8; 768 * 1024]).unwrap();
+
+        let paths = vec![small, large];
+
+        assert_eq!(check_hovered_paths(&rules(), &paths[..1]), Ok(()));
+        assert_eq!(
+            check_hovered_paths(&rules(), &paths),
+            Err("The dropped items are larger than 1 MB".to_string())
+        );
+    }
+
+    #[test]
+    fn accepts_drops_within_the_size_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = vec![folder(dir.path(), 1024)];
+
+        assert_eq!(check_paths(&rules(), &paths), Ok(()));
+    }
+
+    #[test]
+    fn counts_items_in_the_plural() {
+        let rules = DropRulesConfig {
+            max_items: 2,
+            ..rules()
+        };
+        let paths = vec![PathBuf::from("a"), PathBuf::from("b"), PathBuf::from("c")];
+
+        assert_eq!(
+            check_hovered_paths(&rules, &paths),
+            Err("At most 2 items can be dropped at once".to_string())
+        );
+    }
+
+    #[test]
+    fn checks_the_other_rules_while_hovering() {
+        let dir = tempfile::tempdir().unwrap();
+        let folder = folder(dir.path(), 1);
+        let exe = dir.path().join("setup.exe");
+        std::fs::write(&exe, b"MZ").unwrap();
+        let rules = DropRulesConfig {
+            max_items: 1,
+            blocked_extensions: vec![".EXE".to_string()],
+            reject_folders: true,
+            ..rules()
+        };
+
+        assert_eq!(
+            check_hovered_paths(&rules, &[folder.clone(), exe.clone()]),
+            Err("At most 1 item can be dropped at once".to_string())
+        );
+        assert_eq!(
+            check_hovered_paths(&rules, &[folder]),
+            Err("Folders are not accepted (folder)".to_string())
+        );
+        assert_eq!(
+            check_hovered_paths(&rules, &[exe]),
+            Err("setup.exe is not an accepted file type".to_string())
+        );
+    }
+}
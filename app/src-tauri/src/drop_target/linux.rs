@@ -0,0 +1,387 @@
+// GTK drop target on the main window's webview. It takes over drops from other apps
+// before WebKit sees them and picks the richest target the drag offers; drags started
+// by this app are left to WebKit.
+
+use super::DropPayload;
+use crate::clipboard::{decode_html, file_uris_to_paths};
+use crate::drop_rules;
+use crate::DragState;
+use base64::{engine::general_purpose, Engine as _};
+use gtk::prelude::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, WebviewWindow};
+use tracing::{debug, error, info, warn};
+
+const URI_LIST: &str = "text/uri-list";
+const HTML: &str = "text/html";
+const NETSCAPE_URL: &str = "_NETSCAPE_URL";
+// Preferred first; only one of them is requested
+const TEXT_TARGETS: [&str; 3] = ["text/plain;charset=utf-8", "UTF8_STRING", "text/plain"];
+
+// The targets of a drop still to be fetched, and what was received so far
+struct PendingDrop {
+    wanted: VecDeque<gdk::Atom>,
+    uris: Vec<String>,
+    image: Option<(String, Vec<u8>)>,
+    html: Option<String>,
+    netscape_url: Option<String>,
+    text: Option<String>,
+    time: u32,
+}
+
+#[derive(Default)]
+struct TargetState {
+    // The drag last checked against the drop rules, and why it breaks them. A drag
+    // with files is accepted until its URI list arrives.
+    checked: Option<(gdk::DragContext, Option<String>)>,
+    pending: Option<PendingDrop>,
+}
+
+impl TargetState {
+    fn rejection(&self, context: &gdk::DragContext) -> Option<&String> {
+        match &self.checked {
+            Some((checked, rejection)) if checked == context => rejection.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Make the main window's webview take drops of files, links, HTML, text and images.
+pub fn register(window: &WebviewWindow) -> Result<(), String> {
+    let gtk_window = window.gtk_window().map_err(|e| e.to_string())?;
+    let webview =
+        find_webview(gtk_window.upcast_ref()).ok_or("The main window has no WebKitWebView")?;
+    let app = window.app_handle().clone();
+    let state: Rc<RefCell<TargetState>> = Rc::default();
+
+    let motion_state = state.clone();
+    webview.connect_drag_motion(move |widget, context, _, _, time| {
+        if is_own_drag(context) {
+            return false;
+        }
+        let mut state = motion_state.borrow_mut();
+        let is_new = !matches!(&state.checked, Some((checked, _)) if checked == context);
+        let action = match state.rejection(context) {
+            Some(_) => gdk::DragAction::empty(),
+            None => gdk::DragAction::COPY,
+        };
+        context.drag_status(action, time);
+
+        // Fetch the URI list of a new drag to check it against the drop rules
+        let uri_list = gdk::Atom::intern(URI_LIST);
+        if is_new {
+            state.checked = Some((context.clone(), None));
+            if context.list_targets().contains(&uri_list) {
+                drop(state);
+                widget.drag_get_data(context, &uri_list, time);
+            }
+        }
+        true
+    });
+
+    let drop_state = state.clone();
+    webview.connect_drag_drop(move |widget, context, _, _, time| {
+        if is_own_drag(context) {
+            return false;
+        }
+        if drop_state.borrow().rejection(context).is_some() {
+            context.drag_finish(false, false, time);
+            return true;
+        }
+        let mut wanted = wanted_targets(&context.list_targets());
+        let Some(first) = wanted.pop_front() else {
+            debug!("Ignoring a drop without supported targets");
+            context.drag_finish(false, false, time);
+            return true;
+        };
+        drop_state.borrow_mut().pending = Some(PendingDrop {
+            wanted,
+            uris: Vec::new(),
+            image: None,
+            html: None,
+            netscape_url: None,
+            text: None,
+            time,
+        });
+        widget.drag_get_data(context, &first, time);
+        true
+    });
+
+    webview.connect_drag_data_received(move |widget, context, _, _, data, _, time| {
+        if is_own_drag(context) {
+            return;
+        }
+        widget.stop_signal_emission_by_name("drag-data-received");
+        let mut state = state.borrow_mut();
+        let Some(current) = state.pending.as_mut() else {
+            // The URI list asked for while the drag is over the window
+            let paths = file_uris_to_paths(data.uris().iter().map(|uri| uri.as_str()));
+            let rejection =
+                drop_rules::check_hovered_paths(&drop_rules::current_rules(&app), &paths).err();
+            if let Some(reason) = &rejection {
+                drop_rules::reject(&app, reason);
+                context.drag_status(gdk::DragAction::empty(), time);
+            }
+            state.checked = Some((context.clone(), rejection));
+            return;
+        };
+        receive(current, data);
+
+        if let Some(next) = current.wanted.pop_front() {
+            let time = current.time;
+            // Release the state first, the data may arrive in a nested emission
+            drop(state);
+            widget.drag_get_data(context, &next, time);
+            return;
+        }
+        let Some(received) = state.pending.take() else {
+            return;
+        };
+        state.checked = None;
+        drop(state);
+        let payload = payload(
+            received.uris,
+            received.image,
+            received.html,
+            received.netscape_url,
+            received.text,
+        );
+        context.drag_finish(payload.is_some(), false, received.time);
+        match payload {
+            Some(payload) => {
+                info!("Received a drop in the app window");
+                emit_drop(&app, payload);
+            }
+            None => warn!("Dropped data could not be read"),
+        }
+    });
+
+    info!("Registered GTK drop target");
+    Ok(())
+}
+
+// Report a drop on the main window to the frontend
+fn emit_drop(app: &AppHandle, payload: DropPayload) {
+    app.state::<Arc<DragState>>().record_drop();
+    if let Err(e) = app.emit("native_drop", payload) {
+        error!("Failed to emit native_drop event: {}", e);
+    }
+}
+
+fn find_webview(widget: &gtk::Widget) -> Option<gtk::Widget> {
+    if widget.type_().name() == "WebKitWebView" {
+        return Some(widget.clone());
+    }
+    widget
+        .downcast_ref::<gtk::Container>()?
+        .children()
+        .iter()
+        .find_map(find_webview)
+}
+
+// Drags out of the shelf have a source widget in this process
+fn is_own_drag(context: &gdk::DragContext) -> bool {
+    context.drag_get_source_widget().is_some()
+}
+
+// The targets to fetch, in the order they are requested
+fn wanted_targets(offered: &[gdk::Atom]) -> VecDeque<gdk::Atom> {
+    let names: Vec<String> = offered.iter().map(|atom| atom.name().to_string()).collect();
+    let has = |target: &str| names.iter().any(|name| name == target);
+
+    let mut wanted = VecDeque::new();
+    if has(URI_LIST) {
+        wanted.push_back(URI_LIST.to_string());
+    }
+    if let Some(image) = names.iter().find(|name| name.starts_with("image/")) {
+        wanted.push_back(image.clone());
+    }
+    for target in [HTML, NETSCAPE_URL] {
+        if has(target) {
+            wanted.push_back(target.to_string());
+        }
+    }
+    if let Some(text) = TEXT_TARGETS.iter().find(|target| has(target)) {
+        wanted.push_back(text.to_string());
+    }
+    wanted
+        .iter()
+        .map(|target| gdk::Atom::intern(target))
+        .collect()
+}
+
+fn receive(pending: &mut PendingDrop, data: &gtk::SelectionData) {
+    if data.length() <= 0 {
+        return;
+    }
+    let target = data.target().name().to_string();
+    match target.as_str() {
+        URI_LIST => {
+            pending.uris = data.uris().iter().map(|uri| uri.to_string()).collect();
+        }
+        HTML => pending.html = Some(decode_html(&data.data())),
+        NETSCAPE_URL => pending.netscape_url = Some(String::from_utf8_lossy(&data.data()).into()),
+        _ if target.starts_with("image/") => pending.image = Some((target, data.data())),
+        _ => pending.text = data.text().map(|text| text.to_string()),
+    }
+}
+
+// Files come first, then image data (with the HTML and link it came with, which name
+// the image), HTML embedding an image without data (the frontend fetches the image),
+// links, other HTML and finally plain text
+fn payload(
+    uris: Vec<String>,
+    image: Option<(String, Vec<u8>)>,
+    html: Option<String>,
+    netscape_url: Option<String>,
+    text: Option<String>,
+) -> Option<DropPayload> {
+    if !uris.is_empty() && uris.iter().all(|uri| uri.starts_with("file://")) {
+        let paths = file_uris_to_paths(uris.iter().map(String::as_str));
+        if !paths.is_empty() {
+            return Some(DropPayload::Files(
+                paths
+                    .iter()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .collect(),
+            ));
+        }
+    }
+    // _NETSCAPE_URL is the URL, optionally followed by the link's title on the next line
+    let link = netscape_url
+        .as_deref()
+        .and_then(|value| {
+            let mut lines = value.lines().map(str::trim);
+            let url = lines.next().filter(|url| !url.is_empty())?;
+            let title = lines.next().filter(|title| !title.is_empty());
+            Some((url.to_string(), title.map(str::to_string)))
+        })
+        .or_else(|| {
+            uris.iter()
+                .find(|uri| uri.starts_with("http://") || uri.starts_with("https://"))
+                .map(|uri| (uri.trim().to_string(), None))
+        });
+    if let Some((mime, data)) = image {
+        return Some(DropPayload::Image {
+            mime,
+            data_base64: general_purpose::STANDARD.encode(data),
+            html,
+            url: link.map(|(url, _)| url),
+        });
+    }
+    if let Some(html) = html.as_ref().filter(|html| html.contains("<img")) {
+        return Some(DropPayload::Html(html.clone()));
+    }
+    if let Some((url, title)) = link {
+        return Some(DropPayload::Url { url, title });
+    }
+    if let Some(html) = html {
+        return Some(DropPayload::Html(html));
+    }
+    text.filter(|text| !text.trim().is_empty())
+        .map(DropPayload::Text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMG_HTML: &str = r#"<img src="https://example.com/cat.png" alt="A cat">"#;
+
+    fn uris(uris: &[&str]) -> Vec<String> {
+        uris.iter().map(|uri| uri.to_string()).collect()
+    }
+
+    #[test]
+    fn files_win_over_everything_else() {
+        let dropped = payload(
+            uris(&["file:///tmp/a.txt", "file:///tmp/b%20c.txt"]),
+            Some(("image/png".to_string(), vec![1, 2, 3])),
+            Some(IMG_HTML.to_string()),
+            None,
+            Some("text".to_string()),
+        );
+        assert!(matches!(
+            dropped,
+            Some(DropPayload::Files(files)) if files == ["/tmp/a.txt", "/tmp/b c.txt"]
+        ));
+    }
+
+    #[test]
+    fn image_data_keeps_the_html_and_link_it_came_with() {
+        let dropped = payload(
+            uris(&["https://example.com/cat.png"]),
+            Some(("image/png".to_string(), vec![1, 2, 3])),
+            Some(IMG_HTML.to_string()),
+            Some("https://example.com/page\nCats".to_string()),
+            None,
+        );
+        let Some(DropPayload::Image {
+            mime,
+            data_base64,
+            html,
+            url,
+        }) = dropped
+        else {
+            panic!("expected an image");
+        };
+        assert_eq!(mime, "image/png");
+        assert_eq!(data_base64, "AQID");
+        assert_eq!(html.as_deref(), Some(IMG_HTML));
+        assert_eq!(url.as_deref(), Some("https://example.com/page"));
+    }
+
+    #[test]
+    fn html_with_an_image_but_no_data_is_html() {
+        let dropped = payload(
+            uris(&["https://example.com/cat.png"]),
+            None,
+            Some(IMG_HTML.to_string()),
+            None,
+            None,
+        );
+        assert!(matches!(dropped, Some(DropPayload::Html(html)) if html == IMG_HTML));
+    }
+
+    #[test]
+    fn links_take_their_title_from_netscape_url() {
+        let dropped = payload(
+            Vec::new(),
+            None,
+            Some("<a href=\"https://example.com/\">Example</a>".to_string()),
+            Some("https://example.com/\r\nExample".to_string()),
+            Some("https://example.com/".to_string()),
+        );
+        assert!(matches!(
+            dropped,
+            Some(DropPayload::Url { url, title })
+                if url == "https://example.com/" && title.as_deref() == Some("Example")
+        ));
+
+        let dropped = payload(uris(&["https://example.com/"]), None, None, None, None);
+        assert!(matches!(
+            dropped,
+            Some(DropPayload::Url { url, title: None }) if url == "https://example.com/"
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_html_then_text() {
+        let dropped = payload(
+            Vec::new(),
+            None,
+            Some("<b>bold</b>".to_string()),
+            None,
+            Some("bold".to_string()),
+        );
+        assert!(matches!(dropped, Some(DropPayload::Html(html)) if html == "<b>bold</b>"));
+
+        let dropped = payload(Vec::new(), None, None, None, Some("hello".to_string()));
+        assert!(matches!(dropped, Some(DropPayload::Text(text)) if text == "hello"));
+
+        assert!(payload(Vec::new(), None, None, None, Some("  ".to_string())).is_none());
+    }
+}
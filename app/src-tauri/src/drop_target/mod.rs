@@ -0,0 +1,35 @@
+// Native drop targets on the main window that take more than files: text, HTML, links
+// and image data, reported to the frontend as `native_drop` events. Windows uses
+// `custom_drop::CustomDropTarget`, Linux the GTK target here; macOS still gets Tauri's
+// file-only drop events.
+
+#[cfg(target_os = "linux")]
+mod linux;
+
+#[cfg(target_os = "linux")]
+pub use linux::register;
+
+use serde::Serialize;
+
+/// What was dropped on the main window, the richest form the drag offered.
+#[derive(Clone, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum DropPayload {
+    Files(Vec<String>),
+    Text(String),
+    Html(String),
+    /// Image data without a file (PNG, JPEG, BMP, ...), with the HTML and URL that came
+    /// with it, if any, to name the saved image after
+    Image {
+        mime: String,
+        data_base64: String,
+        html: Option<String>,
+        url: Option<String>,
+    },
+    /// A link, with its title if the source gave one
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    Url {
+        url: String,
+        title: Option<String>,
+    },
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileMetadata {
@@ -12,6 +12,39 @@ pub struct FileMetadata {
     pub file_type: String,
 }
 
+impl FileMetadata {
+    // Build the shelf entry for a path on disk. The id is assigned when it is added to the list.
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        let metadata = path.metadata()?;
+
+        // Calculate size correctly for directories
+        let size = if metadata.is_dir() {
+            get_dir_size(&path.to_path_buf()).unwrap_or(0)
+        } else {
+            metadata.len()
+        };
+
+        Ok(FileMetadata {
+            id: 0,
+            name: path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("Unknown")
+                .to_string(),
+            path: path.to_path_buf(),
+            size,
+            file_type: if metadata.is_dir() {
+                "folder".to_string()
+            } else {
+                path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .unwrap_or("unknown")
+                    .to_string()
+            },
+        })
+    }
+}
+
 // Calculate the size of a directory by recursively summing all file sizes
 pub fn get_dir_size(path: &PathBuf) -> io::Result<u64> {
     let mut total_size = 0;
@@ -33,3 +66,56 @@ pub fn get_dir_size(path: &PathBuf) -> io::Result<u64> {
 
     Ok(total_size)
 }
+
+// Folder for content that only exists once we write it (pastes, downloads, ...).
+// A new folder is used every day so `cleanup_old_files` can drop older ones.
+pub fn staging_dir() -> Result<PathBuf, String> {
+    let folder_name = chrono::Local::now().format("%Y%m%d").to_string();
+    let drop_folder = std::env::temp_dir().join("holdem_drops").join(folder_name);
+    fs::create_dir_all(&drop_folder).map_err(|e| format!("Failed to create drop folder: {}", e))?;
+    Ok(drop_folder)
+}
+
+// Pick a path for `file_name` inside `dir` that doesn't overwrite an existing file,
+// appending " (1)", " (2)", ... to the stem when needed
+pub fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let name = Path::new(file_name);
+    let stem = name
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(file_name);
+    let ext = name.extension().and_then(|e| e.to_str());
+
+    (1..)
+        .map(|i| match ext {
+            Some(ext) => dir.join(format!("{} ({}).{}", stem, i, ext)),
+            None => dir.join(format!("{} ({})", stem, i)),
+        })
+        .find(|p| !p.exists())
+        .unwrap_or(candidate)
+}
+
+// Make a name coming from outside (HTTP headers, URLs, HTML) safe to use as a file name
+pub fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('.').trim();
+
+    // Keep names well below the usual 255 byte limit, without splitting a character
+    let mut end = cleaned.len().min(200);
+    while !cleaned.is_char_boundary(end) {
+        end -= 1;
+    }
+    cleaned[..end].to_string()
+}
@@ -1,6 +1,8 @@
-use crate::file::{get_dir_size, FileMetadata};
+use crate::drop_rules;
+use crate::file::staging_dir;
+use crate::shelf::{next_id, ShelfItem};
 use crate::FileList;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Emitter};
 use tracing::{error, info};
 
@@ -10,71 +12,39 @@ pub fn handle_file_drop_from_paths(
     app_handle: AppHandle,
 ) {
     tauri::async_runtime::spawn(async move {
+        if let Err(reason) =
+            drop_rules::check_paths(&drop_rules::current_rules(&app_handle), &paths)
+        {
+            drop_rules::reject(&app_handle, &reason);
+            return;
+        }
+
         // Calculate all files metadata first
         let mut new_files = Vec::new();
 
         for path in paths.iter() {
             if path.exists() {
-                if let Ok(metadata) = path.metadata() {
-                    // If file is in temp directory, copy it to a permanent location
-                    let final_path = if path.starts_with(std::env::temp_dir()) {
-                        let timestamp = chrono::Local::now();
-                        let folder_name = timestamp.format("%Y%m%d").to_string();
-                        let drop_folder =
-                            std::env::temp_dir().join("holdem_drops").join(&folder_name);
-                        std::fs::create_dir_all(&drop_folder).ok();
-
-                        let file_name = path.file_name().unwrap_or_default();
-                        let new_path = drop_folder.join(file_name);
-                        if std::fs::copy(path, &new_path).is_ok() {
-                            new_path
-                        } else {
-                            path.clone()
-                        }
-                    } else {
-                        path.clone()
-                    };
-
-                    // Calculate size correctly for directories
-                    let size = if metadata.is_dir() {
-                        get_dir_size(path).unwrap_or(0)
-                    } else {
-                        metadata.len()
-                    };
+                // If file is in temp directory, copy it to a permanent location
+                let final_path = if path.starts_with(std::env::temp_dir()) {
+                    stage_temp_file(path)
+                } else {
+                    path.clone()
+                };
 
-                    let file = FileMetadata {
-                        id: 0, // Will be updated later
-                        name: final_path
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("Unknown")
-                            .to_string(),
-                        path: final_path,
-                        size,
-                        file_type: if metadata.is_dir() {
-                            "folder".to_string()
-                        } else {
-                            path.extension()
-                                .and_then(|ext| ext.to_str())
-                                .unwrap_or("unknown")
-                                .to_string()
-                        },
-                    };
-                    new_files.push(file);
+                if let Ok(item) = ShelfItem::from_path(&final_path) {
+                    new_files.push(item);
                 }
             }
         }
 
         // Now lock and add to list
         let mut list = file_list.lock().unwrap();
-        let starting_id = list.len() as u64;
-
-        for (i, mut file) in new_files.into_iter().enumerate() {
-            file.id = starting_id + i as u64;
+        for mut item in new_files.into_iter() {
             // Avoid duplicates
-            if !list.iter().any(|f| f.path == file.path) {
-                info!("Added dropped file: {:?}", file.path);
-                list.push(file);
+            if !list.iter().any(|f| f.path() == item.path()) {
+                info!("Added dropped file: {:?}", item.path());
+                item.set_id(next_id(&list));
+                list.push(item);
             }
         }
         drop(list);
@@ -88,6 +58,78 @@ pub fn handle_file_drop_from_paths(
     });
 }
 
+// Copy a file that lives in the system temp dir (and might be deleted by its owner soon)
+// into our staging folder. Files already staged are left where they are.
+fn stage_temp_file(path: &Path) -> PathBuf {
+    let staging_root = std::env::temp_dir().join("holdem_drops");
+    if path.starts_with(&staging_root) {
+        return path.to_path_buf();
+    }
+
+    let Ok(drop_folder) = staging_dir() else {
+        return path.to_path_buf();
+    };
+    let file_name = path.file_name().unwrap_or_default();
+    let new_path = drop_folder.join(file_name);
+    if std::fs::copy(path, &new_path).is_ok() {
+        new_path
+    } else {
+        path.to_path_buf()
+    }
+}
+
+// Add files that already exist on disk to the shelf right away, returning the new entries.
+// Used by commands that produce a file themselves (downloads, decoded data) before adding it.
+pub fn add_paths_to_shelf(
+    paths: &[PathBuf],
+    file_list: &FileList,
+    app_handle: &AppHandle,
+) -> Result<Vec<ShelfItem>, String> {
+    if !paths.is_empty() {
+        let _ = app_handle.emit("file_added", ());
+    }
+
+    let mut files = Vec::new();
+    for path in paths {
+        let final_path = if path.starts_with(std::env::temp_dir()) {
+            stage_temp_file(path)
+        } else {
+            path.clone()
+        };
+        files.push(ShelfItem::from_path(&final_path).map_err(|e| e.to_string())?);
+    }
+
+    add_items_to_shelf(files, file_list, app_handle)
+}
+
+// Add items (file-backed or in-memory) to the shelf, skipping files that are already on it.
+pub fn add_items_to_shelf(
+    items: Vec<ShelfItem>,
+    file_list: &FileList,
+    app_handle: &AppHandle,
+) -> Result<Vec<ShelfItem>, String> {
+    let mut list = file_list
+        .lock()
+        .map_err(|_| "Failed to acquire lock".to_string())?;
+    let mut added = Vec::new();
+    for mut item in items.into_iter() {
+        // Avoid duplicates
+        let duplicate = item.path().is_some() && list.iter().any(|f| f.path() == item.path());
+        if !duplicate {
+            info!("Added {} to shelf", item.name());
+            item.set_id(next_id(&list));
+            list.push(item.clone());
+            added.push(item);
+        }
+    }
+    drop(list);
+
+    app_handle
+        .emit("files_updated", ())
+        .map_err(|e| e.to_string())?;
+    Ok(added)
+}
+
 fn cleanup_old_files() {
     let temp_dir = std::env::temp_dir().join("holdem_drops");
     if let Ok(entries) = std::fs::read_dir(temp_dir) {
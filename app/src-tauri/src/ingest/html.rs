@@ -0,0 +1,375 @@
+use crate::file::{sanitize_file_name, unique_path};
+use reqwest::Url;
+use scraper::{Html, Selector};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// An HTML snippet as received from a drop or the clipboard.
+#[derive(Debug, Clone)]
+pub struct HtmlFragment {
+    /// The markup the user actually selected, without the surrounding document
+    pub fragment: String,
+    /// Page the fragment was copied from, used to resolve relative links
+    pub source_url: Option<String>,
+}
+
+/// Parse the Windows "HTML Format" (CF_HTML) clipboard payload.
+///
+/// The payload starts with a `Key:Value` header whose `StartFragment`/`EndFragment`
+/// byte offsets point at the selected markup, e.g.
+///
+/// ```text
+/// Version:0.9
+/// StartHTML:0000000105
+/// EndHTML:0000000199
+/// StartFragment:0000000141
+/// EndFragment:0000000163
+/// SourceURL:https://example.com/
+/// <html><body><!--StartFragment--><b>Hello</b><!--EndFragment--></body></html>
+/// ```
+///
+/// Input without a header (e.g. `text/html` on Linux and macOS) is taken as the fragment.
+pub fn parse_cf_html(raw: &str) -> HtmlFragment {
+    let mut source_url = None;
+    let mut start_fragment = None;
+    let mut end_fragment = None;
+    let mut start_html = None;
+    let mut end_html = None;
+
+    for line in raw.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            break;
+        };
+        // The header ends where the markup starts
+        if key.starts_with('<') || key.contains(char::is_whitespace) {
+            break;
+        }
+        let value = value.trim();
+        match key {
+            "StartFragment" => start_fragment = value.parse::<i64>().ok(),
+            "EndFragment" => end_fragment = value.parse::<i64>().ok(),
+            "StartHTML" => start_html = value.parse::<i64>().ok(),
+            "EndHTML" => end_html = value.parse::<i64>().ok(),
+            "SourceURL" => source_url = Some(value.to_string()),
+            "Version" | "StartSelection" | "EndSelection" => {}
+            _ => break,
+        }
+    }
+
+    let slice = |start: Option<i64>, end: Option<i64>| -> Option<&str> {
+        // Offsets are -1 when a section is absent
+        let start = usize::try_from(start?).ok()?;
+        let end = usize::try_from(end?).ok()?;
+        raw.get(start..end.min(raw.len()))
+    };
+
+    let fragment = slice(start_fragment, end_fragment)
+        .or_else(|| slice(start_html, end_html))
+        .map(str::to_string)
+        .or_else(|| fragment_between_markers(raw))
+        .unwrap_or_else(|| raw.to_string());
+
+    HtmlFragment {
+        fragment,
+        source_url: source_url.filter(|url| !url.is_empty()),
+    }
+}
+
+// Fallback for payloads whose offsets are wrong (some apps count UTF-16 units)
+fn fragment_between_markers(raw: &str) -> Option<String> {
+    let start = raw.find("<!--StartFragment-->")? + "<!--StartFragment-->".len();
+    let end = raw[start..].find("<!--EndFragment-->")? + start;
+    Some(raw[start..end].to_string())
+}
+
+/// Remove scripts, event handlers and other active content, and make relative
+/// URLs absolute against `base` so the saved file still works on its own.
+pub fn sanitize(fragment: &str, base: Option<&Url>) -> String {
+    let mut builder = ammonia::Builder::default();
+    builder.add_url_schemes(["data"]);
+    if let Some(base) = base {
+        builder.url_relative(ammonia::UrlRelative::RewriteWithBase(base.clone()));
+    } else {
+        builder.url_relative(ammonia::UrlRelative::PassThrough);
+    }
+    builder.clean(fragment).to_string()
+}
+
+/// Wrap a sanitised fragment into a standalone document.
+pub fn to_document(clean_fragment: &str, title: &str, source_url: Option<&str>) -> String {
+    let source = source_url
+        .map(|url| {
+            format!(
+                "<meta name=\"source-url\" content=\"{}\">\n",
+                html_escape(url)
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n{}<title>{}</title>\n</head>\n<body>\n{}\n</body>\n</html>\n",
+        source,
+        html_escape(title),
+        clean_fragment
+    )
+}
+
+pub fn to_markdown(clean_fragment: &str) -> String {
+    html2md::parse_html(clean_fragment).trim().to_string() + "\n"
+}
+
+/// Targets of `<img src>` and `<a href>` found in a fragment.
+#[derive(Debug, Default)]
+pub struct Resources {
+    pub images: Vec<String>,
+    pub links: Vec<String>,
+}
+
+/// Collect image and link targets, resolved against `base`. Only `http(s)` and `data:`
+/// targets are kept since those are the only ones we can fetch; duplicates are dropped.
+pub fn extract_resources(fragment: &str, base: Option<&Url>) -> Resources {
+    let document = Html::parse_fragment(fragment);
+    let collect = |selector: &str, attribute: &str| -> Vec<String> {
+        let Ok(selector) = Selector::parse(selector) else {
+            return Vec::new();
+        };
+        let mut seen = HashSet::new();
+        document
+            .select(&selector)
+            .filter_map(|element| element.value().attr(attribute))
+            .filter_map(|target| resolve_url(target.trim(), base))
+            .filter(|target| seen.insert(target.clone()))
+            .collect()
+    };
+
+    Resources {
+        images: collect("img[src]", "src"),
+        links: collect("a[href]", "href"),
+    }
+}
+
+fn resolve_url(target: &str, base: Option<&Url>) -> Option<String> {
+    let is_data = target
+        .get(..5)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("data:"));
+    if is_data && target.len() > 5 {
+        return Some(target.to_string());
+    }
+    let url = match Url::parse(target) {
+        Ok(url) => url,
+        Err(_) => base?.join(target).ok()?,
+    };
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+/// Title for the saved files: the first heading or the source page's host.
+pub fn title_for(fragment: &str, source_url: Option<&Url>) -> String {
+    let document = Html::parse_fragment(fragment);
+    let heading = Selector::parse("h1, h2, h3, title")
+        .ok()
+        .and_then(|selector| {
+            document
+                .select(&selector)
+                .map(|element| element.text().collect::<String>())
+                .map(|text| text.split_whitespace().collect::<Vec<_>>().join(" "))
+                .find(|text| !text.is_empty())
+        });
+
+    heading
+        .or_else(|| {
+            source_url
+                .and_then(|url| url.host_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| format!("clipping_{}", chrono::Local::now().format("%H%M%S")))
+}
+
+/// Save the fragment as `.html` and/or `.md` in `dir`, returning the written paths.
+pub fn save_fragment(
+    fragment: &HtmlFragment,
+    dir: &Path,
+    save_html: bool,
+    save_markdown: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let base = fragment
+        .source_url
+        .as_deref()
+        .and_then(|url| Url::parse(url).ok());
+    let clean = sanitize(&fragment.fragment, base.as_ref());
+    let title = title_for(&clean, base.as_ref());
+    let stem = sanitize_file_name(&title);
+    let stem = if stem.is_empty() {
+        "clipping".to_string()
+    } else {
+        stem
+    };
+
+    let mut paths = Vec::new();
+    if save_html {
+        let path = unique_path(dir, &format!("{}.html", stem));
+        let document = to_document(&clean, &title, fragment.source_url.as_deref());
+        std::fs::write(&path, document).map_err(|e| e.to_string())?;
+        paths.push(path);
+    }
+    if save_markdown {
+        let path = unique_path(dir, &format!("{}.md", stem));
+        std::fs::write(&path, to_markdown(&clean)).map_err(|e| e.to_string())?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEFORE: &str = "<html><body><!--StartFragment-->";
+    const AFTER: &str = "<!--EndFragment--></body></html>";
+
+    // CF_HTML header with `[StartHTML, EndHTML, StartFragment, EndFragment]` offsets
+    fn cf_header(offsets: [i64; 4], source_url: &str) -> String {
+        format!(
+            "Version:0.9\r\nStartHTML:{:010}\r\nEndHTML:{:010}\r\nStartFragment:{:010}\r\nEndFragment:{:010}\r\nSourceURL:{}\r\n",
+            offsets[0], offsets[1], offsets[2], offsets[3], source_url
+        )
+    }
+
+    // CF_HTML payload around `fragment` with correct byte offsets
+    fn cf_html(fragment: &str, source_url: &str) -> String {
+        let start_html = cf_header([0; 4], source_url).len() as i64;
+        let start_fragment = start_html + BEFORE.len() as i64;
+        let end_fragment = start_fragment + fragment.len() as i64;
+        let end_html = end_fragment + AFTER.len() as i64;
+        let header = cf_header(
+            [start_html, end_html, start_fragment, end_fragment],
+            source_url,
+        );
+        format!("{}{}{}{}", header, BEFORE, fragment, AFTER)
+    }
+
+    fn base() -> Url {
+        Url::parse("https://example.com/blog/post.html").unwrap()
+    }
+
+    #[test]
+    fn cuts_the_fragment_at_the_cf_html_offsets() {
+        let raw = cf_html("<b>Grüße</b> from 日本", "https://example.com/");
+        let parsed = parse_cf_html(&raw);
+        assert_eq!(parsed.fragment, "<b>Grüße</b> from 日本");
+        assert_eq!(parsed.source_url.as_deref(), Some("https://example.com/"));
+    }
+
+    #[test]
+    fn falls_back_to_the_markers_when_offsets_are_wrong() {
+        // Offsets counted in UTF-16 units can land inside a character
+        let body = format!("{}<i>Grüße</i>{}", BEFORE, AFTER);
+        let header_len = cf_header([0; 4], "").len();
+        let inside_u = (header_len + body.find('ü').unwrap() + 1) as i64;
+        let raw = cf_header([-1, -1, inside_u, (header_len + body.len()) as i64], "") + &body;
+
+        let parsed = parse_cf_html(&raw);
+        assert_eq!(parsed.fragment, "<i>Grüße</i>");
+        assert_eq!(parsed.source_url, None);
+    }
+
+    #[test]
+    fn takes_plain_markup_as_the_fragment() {
+        let parsed = parse_cf_html("<p>Hi: there</p>");
+        assert_eq!(parsed.fragment, "<p>Hi: there</p>");
+        assert_eq!(parsed.source_url, None);
+    }
+
+    #[test]
+    fn strips_scripts_and_handlers() {
+        let clean = sanitize(
+            "<p onclick=\"steal()\">Hi<script>alert(1)</script></p>\
+             <a href=\"javascript:alert(1)\">x</a><iframe src=\"https://evil.test\"></iframe>",
+            None,
+        );
+        assert!(clean.contains("<p>Hi</p>"), "{}", clean);
+        for active in ["script", "alert", "onclick", "javascript", "iframe"] {
+            assert!(!clean.contains(active), "{} in {}", active, clean);
+        }
+    }
+
+    #[test]
+    fn makes_relative_urls_absolute() {
+        let clean = sanitize(
+            "<a href=\"../about.html\">About</a><img src=\"/logo.png\"><img src=\"data:image/png;base64,aGk=\">",
+            Some(&base()),
+        );
+        assert!(
+            clean.contains("href=\"https://example.com/about.html\""),
+            "{}",
+            clean
+        );
+        assert!(
+            clean.contains("src=\"https://example.com/logo.png\""),
+            "{}",
+            clean
+        );
+        assert!(
+            clean.contains("src=\"data:image/png;base64,aGk=\""),
+            "{}",
+            clean
+        );
+
+        let clean = sanitize("<a href=\"about.html\">About</a>", None);
+        assert!(clean.contains("href=\"about.html\""), "{}", clean);
+    }
+
+    #[test]
+    fn extracts_fetchable_resources() {
+        let resources = extract_resources(
+            "<img src=\"a.png\"><img src=\" a.png \"><img src=\"data:image/gif;base64,R0lG\">\
+             <a href=\"https://other.test/x\">x</a><a href=\"mailto:me@example.com\">m</a>\
+             <a href=\"#top\">top</a><a href=\"日本語.html\">jp</a><a href=\"data:\">empty</a>",
+            Some(&base()),
+        );
+        assert_eq!(
+            resources.images,
+            [
+                "https://example.com/blog/a.png",
+                "data:image/gif;base64,R0lG"
+            ]
+        );
+        assert_eq!(
+            resources.links,
+            [
+                "https://other.test/x",
+                "https://example.com/blog/post.html#top",
+                "https://example.com/blog/%E6%97%A5%E6%9C%AC%E8%AA%9E.html"
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_relative_resources_without_a_base() {
+        let resources =
+            extract_resources("<img src=\"a.png\"><a href=\"日本語.html\">jp</a>", None);
+        assert!(resources.images.is_empty());
+        assert!(resources.links.is_empty());
+    }
+
+    #[test]
+    fn titles_come_from_the_first_heading_or_the_host() {
+        assert_eq!(
+            title_for(
+                "<p>intro</p><h2>  Release\n notes </h2><h1>Later</h1>",
+                None
+            ),
+            "Release notes"
+        );
+        assert_eq!(
+            title_for("<h1> </h1><p>text</p>", Some(&base())),
+            "example.com"
+        );
+        assert!(title_for("<p>text</p>", None).starts_with("clipping_"));
+    }
+}
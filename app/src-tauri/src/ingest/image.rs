@@ -0,0 +1,193 @@
+use crate::file::{sanitize_file_name, unique_path};
+use image::ImageFormat;
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use scraper::{Html, Selector};
+use std::path::{Path, PathBuf};
+
+// Formats written as they were received; anything else is converted to PNG
+const KEPT_FORMATS: [ImageFormat; 4] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+];
+
+/// File name stem for a dropped image: the `alt` text of the first image in the HTML
+/// that came with it, or else the last segment of the image's URL.
+pub fn image_name(html: Option<&str>, url: Option<&str>) -> Option<String> {
+    let (alt, src) = html.map(first_image).unwrap_or_default();
+    alt.or_else(|| url.or(src.as_deref()).and_then(url_stem))
+        .map(|name| sanitize_file_name(&name))
+        .filter(|name| !name.is_empty())
+}
+
+// The alt text and source of the first `<img>` in `html`
+fn first_image(html: &str) -> (Option<String>, Option<String>) {
+    let document = Html::parse_fragment(html);
+    let Ok(selector) = Selector::parse("img") else {
+        return (None, None);
+    };
+    let Some(image) = document.select(&selector).next() else {
+        return (None, None);
+    };
+    let alt = image
+        .value()
+        .attr("alt")
+        .map(|alt| alt.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|alt| !alt.is_empty());
+    let src = image.value().attr("src").map(|src| src.trim().to_string());
+    (alt, src)
+}
+
+fn url_stem(url: &str) -> Option<String> {
+    let url = Url::parse(url.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https" | "file") {
+        return None;
+    }
+    let segment = url
+        .path_segments()?
+        .rev()
+        .find(|segment| !segment.is_empty())?;
+    let segment = percent_decode_str(segment).decode_utf8_lossy();
+    Path::new(segment.as_ref())
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+}
+
+/// Write dropped image data into `dir` as `<name>.<ext>`. PNG, JPEG, GIF and WebP data
+/// is kept as it is; other formats the `image` crate decodes (BMP, TIFF, ...) become PNG.
+pub fn save_image(
+    bytes: &[u8],
+    mime: &str,
+    name: Option<&str>,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    let format = image::guess_format(bytes)
+        .ok()
+        .or_else(|| ImageFormat::from_mime_type(mime))
+        .ok_or_else(|| format!("Unsupported image data ({})", mime))?;
+
+    let (data, ext) = if KEPT_FORMATS.contains(&format) {
+        (bytes.to_vec(), format.extensions_str()[0])
+    } else {
+        let image = image::load_from_memory_with_format(bytes, format)
+            .map_err(|e| format!("Failed to decode dropped image: {}", e))?;
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode dropped image: {}", e))?;
+        (png, "png")
+    };
+
+    let stem = name
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("dropped_{}", chrono::Local::now().format("%H%M%S")));
+    let path = unique_path(dir, &format!("{}.{}", stem, ext));
+    std::fs::write(&path, data).map_err(|e| format!("Failed to save dropped image: {}", e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 2x2 image encoded as `format`
+    fn encoded(format: ImageFormat) -> Vec<u8> {
+        let image = image::RgbImage::from_pixel(2, 2, image::Rgb([200, 30, 30]));
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn names_images_after_their_alt_text() {
+        let html = r#"<p><img src="https://example.com/a/photo.jpg" alt=" A   red
+            barn "></p>"#;
+        assert_eq!(
+            image_name(Some(html), Some("https://example.com/other.png")),
+            Some("A red barn".to_string())
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_url_stem() {
+        let html = r#"<img src="https://example.com/from-html.png">"#;
+        assert_eq!(
+            image_name(
+                Some(html),
+                Some("https://example.com/a/b/red%20barn.jpg?w=200")
+            ),
+            Some("red barn".to_string())
+        );
+        assert_eq!(image_name(Some(html), None), Some("from-html".to_string()));
+        assert_eq!(
+            image_name(
+                Some(r#"<img src="x.png" alt="">"#),
+                Some("https://example.com/dir/")
+            ),
+            Some("dir".to_string())
+        );
+    }
+
+    #[test]
+    fn has_no_name_without_alt_text_or_a_usable_url() {
+        assert_eq!(image_name(None, None), None);
+        assert_eq!(image_name(Some("<p>no image</p>"), None), None);
+        assert_eq!(image_name(None, Some("data:image/png;base64,AAAA")), None);
+        assert_eq!(image_name(None, Some("https://example.com/")), None);
+    }
+
+    #[test]
+    fn keeps_png_jpeg_and_gif_data_as_is() {
+        let dir = tempfile::tempdir().unwrap();
+        for (format, ext) in [
+            (ImageFormat::Png, "png"),
+            (ImageFormat::Jpeg, "jpg"),
+            (ImageFormat::Gif, "gif"),
+        ] {
+            let bytes = encoded(format);
+            let path =
+                save_image(&bytes, "application/octet-stream", Some("pic"), dir.path()).unwrap();
+            assert_eq!(path.extension().unwrap(), ext);
+            assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn converts_bmp_to_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = save_image(
+            &encoded(ImageFormat::Bmp),
+            "image/bmp",
+            Some("pic"),
+            dir.path(),
+        )
+        .unwrap();
+
+        assert_eq!(path, dir.path().join("pic.png"));
+        let saved = std::fs::read(&path).unwrap();
+        assert_eq!(image::guess_format(&saved).unwrap(), ImageFormat::Png);
+        assert_eq!(image::load_from_memory(&saved).unwrap().width(), 2);
+    }
+
+    #[test]
+    fn does_not_overwrite_an_image_with_the_same_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let bytes = encoded(ImageFormat::Png);
+
+        let first = save_image(&bytes, "image/png", Some("pic"), dir.path()).unwrap();
+        let second = save_image(&bytes, "image/png", Some("pic"), dir.path()).unwrap();
+
+        assert_eq!(first, dir.path().join("pic.png"));
+        assert_eq!(second, dir.path().join("pic (1).png"));
+    }
+
+    #[test]
+    fn rejects_data_that_is_not_an_image() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(save_image(b"not an image", "text/plain", None, dir.path()).is_err());
+    }
+}
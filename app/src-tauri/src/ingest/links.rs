@@ -0,0 +1,294 @@
+use crate::config::LinkFormat;
+use crate::file::{sanitize_file_name, unique_path};
+use reqwest::{Client, Url};
+use scraper::{Html, Selector};
+use std::path::{Path, PathBuf};
+
+// Only the start of a page is read when looking for its title
+const TITLE_FETCH_LIMIT: usize = 256 * 1024;
+
+/// Return the URLs in `text` if it consists of nothing but http(s) URLs (one or more,
+/// separated by whitespace), which is what browsers put in a dragged or copied link.
+/// Text that merely mentions a URL is left alone.
+pub fn text_as_urls(text: &str) -> Option<Vec<Url>> {
+    let urls = text
+        .split_whitespace()
+        .map(|token| token.trim_matches(|c| c == '<' || c == '>'))
+        .map(|token| {
+            Url::parse(token)
+                .ok()
+                .filter(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    (!urls.is_empty()).then_some(urls)
+}
+
+/// Resolve `LinkFormat::Auto` to the native shortcut format of this platform.
+pub fn resolve_format(format: LinkFormat) -> LinkFormat {
+    match format {
+        LinkFormat::Auto if cfg!(target_os = "windows") => LinkFormat::Url,
+        LinkFormat::Auto if cfg!(target_os = "macos") => LinkFormat::Webloc,
+        LinkFormat::Auto => LinkFormat::Desktop,
+        format => format,
+    }
+}
+
+/// Fetch the `<title>` (or `og:title`) of a page with `client`, which carries the
+/// download settings, giving up quietly on any error.
+pub async fn fetch_title(client: &Client, url: &Url) -> Option<String> {
+    let mut response = client.get(url.clone()).send().await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    let is_html = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("html"));
+    if !is_html {
+        return None;
+    }
+
+    let mut body = Vec::new();
+    while let Ok(Some(chunk)) = response.chunk().await {
+        body.extend_from_slice(&chunk);
+        if body.len() >= TITLE_FETCH_LIMIT {
+            break;
+        }
+    }
+    page_title(&String::from_utf8_lossy(&body))
+}
+
+pub fn page_title(html: &str) -> Option<String> {
+    let document = Html::parse_document(html);
+    let from_selector = |selector: &str, attribute: Option<&str>| -> Option<String> {
+        let selector = Selector::parse(selector).ok()?;
+        let element = document.select(&selector).next()?;
+        let text = match attribute {
+            Some(attribute) => element.value().attr(attribute)?.to_string(),
+            None => element.text().collect(),
+        };
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some(text)
+    };
+
+    from_selector("title", None)
+        .or_else(|| from_selector("meta[property=\"og:title\"]", Some("content")))
+}
+
+/// Write a shortcut to `url` in `dir` in the given format, named after `title`
+/// (or the URL when no title is known).
+pub fn write_link_file(
+    url: &Url,
+    title: Option<&str>,
+    format: LinkFormat,
+    dir: &Path,
+) -> Result<PathBuf, String> {
+    let name = title
+        .map(sanitize_file_name)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| fallback_name(url));
+
+    let (extension, contents) = match resolve_format(format) {
+        LinkFormat::Url => (
+            "url",
+            format!("[InternetShortcut]\r\nURL={}\r\n", url),
+        ),
+        LinkFormat::Webloc => (
+            "webloc",
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+                 <plist version=\"1.0\">\n<dict>\n\t<key>URL</key>\n\t<string>{}</string>\n</dict>\n</plist>\n",
+                xml_escape(url.as_str())
+            ),
+        ),
+        LinkFormat::Desktop | LinkFormat::Auto => (
+            "desktop",
+            format!(
+                "[Desktop Entry]\nVersion=1.0\nType=Link\nName={}\nURL={}\nIcon=text-html\n",
+                title.unwrap_or(&name).replace('\n', " "),
+                url
+            ),
+        ),
+    };
+
+    let path = unique_path(dir, &format!("{}.{}", name, extension));
+    std::fs::write(&path, contents).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+// "example.com - some-page" for https://example.com/some-page
+fn fallback_name(url: &Url) -> String {
+    let host = url.host_str().unwrap_or("link").trim_start_matches("www.");
+    let last_segment = url
+        .path_segments()
+        .and_then(|segments| segments.rev().find(|s| !s.is_empty()))
+        .map(|segment| {
+            percent_encoding::percent_decode_str(segment)
+                .decode_utf8_lossy()
+                .to_string()
+        });
+    let name = match last_segment {
+        Some(segment) => format!("{} - {}", host, segment),
+        None => host.to_string(),
+    };
+    sanitize_file_name(&name)
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    fn read(path: &Path) -> String {
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn text_of_only_urls_is_a_link_drop() {
+        assert_eq!(
+            text_as_urls("https://example.com/page"),
+            Some(vec![url("https://example.com/page")])
+        );
+        assert_eq!(
+            text_as_urls("  <http://a.example/>\n\thttps://b.example/x?y=1 \r\n"),
+            Some(vec![
+                url("http://a.example/"),
+                url("https://b.example/x?y=1")
+            ])
+        );
+    }
+
+    #[test]
+    fn other_text_is_not_a_link_drop() {
+        assert_eq!(text_as_urls(""), None);
+        assert_eq!(text_as_urls(" \n "), None);
+        assert_eq!(text_as_urls("see https://example.com"), None);
+        assert_eq!(text_as_urls("https://example.com and more"), None);
+        assert_eq!(text_as_urls("ftp://example.com/file"), None);
+        assert_eq!(text_as_urls("mailto:someone@example.com"), None);
+        assert_eq!(text_as_urls("file:///tmp/file.txt"), None);
+    }
+
+    #[test]
+    fn auto_format_resolves_to_the_platform_shortcut() {
+        let expected = if cfg!(target_os = "windows") {
+            LinkFormat::Url
+        } else if cfg!(target_os = "macos") {
+            LinkFormat::Webloc
+        } else {
+            LinkFormat::Desktop
+        };
+        assert_eq!(resolve_format(LinkFormat::Auto), expected);
+        assert_eq!(resolve_format(LinkFormat::Webloc), LinkFormat::Webloc);
+    }
+
+    #[test]
+    fn page_title_prefers_title_over_og_title() {
+        assert_eq!(
+            page_title("<html><head><title>\n  A   page\n</title></head></html>"),
+            Some("A page".to_string())
+        );
+        assert_eq!(
+            page_title(
+                r#"<head><title>Title</title><meta property="og:title" content="OG"></head>"#
+            ),
+            Some("Title".to_string())
+        );
+        assert_eq!(
+            page_title(r#"<head><title> </title><meta property="og:title" content="OG"></head>"#),
+            Some("OG".to_string())
+        );
+        assert_eq!(page_title("<p>No title</p>"), None);
+    }
+
+    #[test]
+    fn url_file_is_an_internet_shortcut() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_link_file(
+            &url("https://example.com/a?b=1&c=2"),
+            Some("Example: a page"),
+            LinkFormat::Url,
+            dir.path(),
+        )
+        .unwrap();
+
+        assert_eq!(path.file_name().unwrap(), "Example_ a page.url");
+        assert_eq!(
+            read(&path),
+            "[InternetShortcut]\r\nURL=https://example.com/a?b=1&c=2\r\n"
+        );
+    }
+
+    #[test]
+    fn webloc_file_is_an_escaped_plist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_link_file(
+            &url("https://example.com/a?b=1&c=2"),
+            Some("Example"),
+            LinkFormat::Webloc,
+            dir.path(),
+        )
+        .unwrap();
+
+        assert_eq!(path.file_name().unwrap(), "Example.webloc");
+        let contents = read(&path);
+        assert!(contents.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+        assert!(contents.contains("<plist version=\"1.0\">"));
+        assert!(contents
+            .contains("\t<key>URL</key>\n\t<string>https://example.com/a?b=1&amp;c=2</string>\n"));
+    }
+
+    #[test]
+    fn desktop_file_is_a_link_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_link_file(
+            &url("https://example.com/"),
+            Some("Two\nlines"),
+            LinkFormat::Desktop,
+            dir.path(),
+        )
+        .unwrap();
+
+        assert_eq!(path.file_name().unwrap(), "Two_lines.desktop");
+        assert_eq!(
+            read(&path),
+            "[Desktop Entry]\nVersion=1.0\nType=Link\nName=Two lines\nURL=https://example.com/\nIcon=text-html\n"
+        );
+    }
+
+    #[test]
+    fn untitled_links_are_named_after_the_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let page = url("https://www.example.com/docs/Some%20Page/");
+
+        let first = write_link_file(&page, None, LinkFormat::Url, dir.path()).unwrap();
+        let second = write_link_file(&page, Some("  "), LinkFormat::Url, dir.path()).unwrap();
+        let root = write_link_file(
+            &url("https://example.com"),
+            None,
+            LinkFormat::Desktop,
+            dir.path(),
+        )
+        .unwrap();
+
+        assert_eq!(first.file_name().unwrap(), "example.com - Some Page.url");
+        assert_eq!(
+            second.file_name().unwrap(),
+            "example.com - Some Page (1).url"
+        );
+        assert_eq!(root.file_name().unwrap(), "example.com.desktop");
+        assert!(read(&root).contains("\nName=example.com\n"));
+    }
+}
@@ -0,0 +1,6 @@
+// Turning content that isn't a file yet (URLs, HTML, text, image data) into shelf items
+pub mod html;
+pub mod image;
+pub mod links;
+pub mod text;
+pub mod url;
@@ -0,0 +1,820 @@
+/// What a piece of pasted text most likely is, judged from its content alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFormat {
+    Json,
+    Xml,
+    Html,
+    Markdown,
+    Csv,
+    Tsv,
+    Yaml,
+    Shell,
+    Python,
+    Rust,
+    JavaScript,
+    TypeScript,
+    Go,
+    C,
+    Cpp,
+    Java,
+    CSharp,
+    Sql,
+    Css,
+    Plain,
+}
+
+impl TextFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TextFormat::Json => "json",
+            TextFormat::Xml => "xml",
+            TextFormat::Html => "html",
+            TextFormat::Markdown => "md",
+            TextFormat::Csv => "csv",
+            TextFormat::Tsv => "tsv",
+            TextFormat::Yaml => "yaml",
+            TextFormat::Shell => "sh",
+            TextFormat::Python => "py",
+            TextFormat::Rust => "rs",
+            TextFormat::JavaScript => "js",
+            TextFormat::TypeScript => "ts",
+            TextFormat::Go => "go",
+            TextFormat::C => "c",
+            TextFormat::Cpp => "cpp",
+            TextFormat::Java => "java",
+            TextFormat::CSharp => "cs",
+            TextFormat::Sql => "sql",
+            TextFormat::Css => "css",
+            TextFormat::Plain => "txt",
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            TextFormat::Json => "application/json",
+            TextFormat::Xml => "application/xml",
+            TextFormat::Html => "text/html",
+            TextFormat::Markdown => "text/markdown",
+            TextFormat::Csv => "text/csv",
+            TextFormat::Tsv => "text/tab-separated-values",
+            TextFormat::Yaml => "application/yaml",
+            TextFormat::Shell => "application/x-sh",
+            TextFormat::Python => "text/x-python",
+            TextFormat::Rust => "text/x-rust",
+            TextFormat::JavaScript => "text/javascript",
+            TextFormat::TypeScript => "application/typescript",
+            TextFormat::Go => "text/x-go",
+            TextFormat::C => "text/x-c",
+            TextFormat::Cpp => "text/x-c++",
+            TextFormat::Java => "text/x-java",
+            TextFormat::CSharp => "text/x-csharp",
+            TextFormat::Sql => "application/sql",
+            TextFormat::Css => "text/css",
+            TextFormat::Plain => "text/plain",
+        }
+    }
+}
+
+// Scores at or above this are trusted before the tabular and YAML checks get a say
+const STRONG_SCORE: u32 = 3;
+const WEAK_SCORE: u32 = 2;
+
+/// Work out what `text` is. Formats that can be validated (JSON, markup, delimited
+/// tables, YAML) are checked structurally; source code and Markdown are recognised by
+/// scoring typical line patterns. Anything unconvincing is plain text.
+pub fn classify(text: &str) -> TextFormat {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return TextFormat::Plain;
+    }
+
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde::de::IgnoredAny>(trimmed).is_ok()
+    {
+        return TextFormat::Json;
+    }
+
+    if let Some(format) = classify_markup(trimmed) {
+        return format;
+    }
+
+    if let Some(format) = classify_shebang(trimmed) {
+        return format;
+    }
+
+    let (best, score) = best_scoring(trimmed);
+    if score >= STRONG_SCORE {
+        return best;
+    }
+
+    if let Some(format) = classify_table(trimmed) {
+        return format;
+    }
+
+    if looks_like_yaml(trimmed) {
+        return TextFormat::Yaml;
+    }
+
+    if score >= WEAK_SCORE {
+        return best;
+    }
+
+    TextFormat::Plain
+}
+
+fn classify_markup(text: &str) -> Option<TextFormat> {
+    if !text.starts_with('<') || !text.ends_with('>') {
+        return None;
+    }
+    let lower = text.to_lowercase();
+    if lower.starts_with("<!doctype html") || lower.starts_with("<html") {
+        return Some(TextFormat::Html);
+    }
+    if lower.starts_with("<?xml") {
+        return Some(TextFormat::Xml);
+    }
+
+    const HTML_TAGS: &[&str] = &[
+        "div", "span", "p", "a", "body", "head", "table", "ul", "ol", "li", "img", "br", "h1",
+        "h2", "h3", "section", "article", "nav", "header", "footer", "form", "input", "button",
+        "script", "style", "meta", "link",
+    ];
+    let first_tag: String = lower[1..]
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | ':' | '_'))
+        .collect();
+    if first_tag.is_empty() {
+        return None;
+    }
+    if HTML_TAGS.contains(&first_tag.as_str()) {
+        return Some(TextFormat::Html);
+    }
+
+    // An unknown root element is XML if the document closes it
+    lower
+        .trim_end()
+        .ends_with(&format!("</{}>", first_tag))
+        .then_some(TextFormat::Xml)
+        .or_else(|| lower.ends_with("/>").then_some(TextFormat::Xml))
+}
+
+fn classify_shebang(text: &str) -> Option<TextFormat> {
+    let first_line = text.lines().next()?.strip_prefix("#!")?;
+    let format = if first_line.contains("python") {
+        TextFormat::Python
+    } else if first_line.contains("node") || first_line.contains("deno") {
+        TextFormat::JavaScript
+    } else {
+        TextFormat::Shell
+    };
+    Some(format)
+}
+
+// Per-line hints, each adding its weight to a format's score when a line matches
+struct Hint {
+    format: TextFormat,
+    weight: u32,
+    matches: fn(&str) -> bool,
+}
+
+const fn hint(format: TextFormat, weight: u32, matches: fn(&str) -> bool) -> Hint {
+    Hint {
+        format,
+        weight,
+        matches,
+    }
+}
+
+const HINTS: &[Hint] = &[
+    // Rust
+    hint(TextFormat::Rust, 2, |l| {
+        l.starts_with("fn ") || l.starts_with("pub fn ") || l.starts_with("pub(crate) fn ")
+    }),
+    hint(TextFormat::Rust, 2, |l| {
+        l.starts_with("use ") && l.contains("::") && l.ends_with(';')
+    }),
+    hint(TextFormat::Rust, 2, |l| {
+        l.starts_with("#[") || l.starts_with("impl ") || l.starts_with("impl<")
+    }),
+    hint(TextFormat::Rust, 1, |l| {
+        l.starts_with("let mut ")
+            || l.contains("println!(")
+            || l.contains("&mut ")
+            || l.contains("Option<")
+            || l.contains(".unwrap()")
+    }),
+    // Python
+    hint(TextFormat::Python, 2, |l| {
+        (l.starts_with("def ") || l.starts_with("async def ") || l.starts_with("class "))
+            && l.ends_with(':')
+    }),
+    hint(TextFormat::Python, 2, |l| {
+        l.starts_with("from ") && l.contains(" import ")
+    }),
+    hint(TextFormat::Python, 1, |l| {
+        l.starts_with("import ") && !l.contains(" from ") && !l.ends_with(';')
+    }),
+    hint(TextFormat::Python, 1, |l| {
+        [
+            "if ", "elif ", "else", "for ", "while ", "with ", "try", "except",
+        ]
+        .iter()
+        .any(|k| l.starts_with(k))
+            && l.ends_with(':')
+    }),
+    hint(TextFormat::Python, 1, |l| {
+        l.contains("self.") || l.contains("__name__") || l.starts_with("print(")
+    }),
+    // JavaScript (TypeScript hints add on top, see `best_scoring`)
+    hint(TextFormat::JavaScript, 2, |l| {
+        l.starts_with("function ")
+            || l.starts_with("export function ")
+            || l.starts_with("async function ")
+    }),
+    hint(TextFormat::JavaScript, 1, |l| {
+        l.starts_with("const ")
+            || l.starts_with("let ")
+            || l.starts_with("var ")
+            || l.starts_with("export ")
+    }),
+    hint(TextFormat::JavaScript, 1, |l| {
+        l.contains("=> ")
+            || l.contains("console.log")
+            || l.contains("require(")
+            || l.contains("document.")
+    }),
+    hint(TextFormat::JavaScript, 2, |l| {
+        l.starts_with("import ") && l.contains(" from ")
+    }),
+    hint(TextFormat::TypeScript, 2, |l| {
+        l.starts_with("interface ")
+            || l.starts_with("export interface ")
+            || l.starts_with("type ")
+            || l.starts_with("export type ")
+    }),
+    hint(TextFormat::TypeScript, 1, |l| {
+        [
+            ": string",
+            ": number",
+            ": boolean",
+            ": void",
+            " as const",
+            "readonly ",
+        ]
+        .iter()
+        .any(|k| l.contains(k))
+    }),
+    // Go
+    hint(TextFormat::Go, 3, |l| {
+        l.starts_with("package ") && !l.ends_with(';')
+    }),
+    hint(TextFormat::Go, 2, |l| l.starts_with("func ")),
+    hint(TextFormat::Go, 1, |l| {
+        l.contains(" := ") || l.contains("fmt.") || l == "import ("
+    }),
+    // C and C++
+    hint(TextFormat::C, 2, |l| {
+        l.starts_with("#include") || l.starts_with("#define ")
+    }),
+    hint(TextFormat::C, 1, |l| {
+        l.contains("printf(")
+            || l.contains("malloc(")
+            || l.starts_with("int main(")
+            || l.starts_with("struct ")
+    }),
+    hint(TextFormat::Cpp, 3, |l| {
+        l.contains("std::")
+            || l.contains("cout <<")
+            || l.starts_with("template<")
+            || l.starts_with("template <")
+    }),
+    hint(TextFormat::Cpp, 1, |l| {
+        l.contains("nullptr") || l.starts_with("namespace ") || l.contains("#include <iostream>")
+    }),
+    // Java and C#
+    hint(TextFormat::Java, 3, |l| {
+        l.contains("System.out.")
+            || l.starts_with("import java.")
+            || l.contains("public static void main")
+    }),
+    hint(TextFormat::Java, 1, |l| {
+        l.starts_with("@Override") || (l.starts_with("package ") && l.ends_with(';'))
+    }),
+    hint(TextFormat::Java, 1, |l| {
+        l.starts_with("public class ") || l.starts_with("private final ")
+    }),
+    hint(TextFormat::CSharp, 3, |l| {
+        l.starts_with("using System") || l.contains("Console.WriteLine")
+    }),
+    hint(TextFormat::CSharp, 2, |l| {
+        l.contains("{ get; set; }") || l.contains("async Task")
+    }),
+    hint(TextFormat::CSharp, 1, |l| {
+        l.starts_with("namespace ") && !l.contains('=')
+    }),
+    // SQL
+    hint(TextFormat::Sql, 3, |l| {
+        let u = l.to_uppercase();
+        [
+            "SELECT ",
+            "INSERT INTO ",
+            "DELETE FROM ",
+            "CREATE TABLE ",
+            "ALTER TABLE ",
+            "DROP TABLE ",
+            "WITH ",
+        ]
+        .iter()
+        .any(|k| u.starts_with(k))
+    }),
+    hint(TextFormat::Sql, 1, |l| {
+        let u = l.to_uppercase();
+        [
+            "FROM ",
+            "WHERE ",
+            "GROUP BY ",
+            "ORDER BY ",
+            "JOIN ",
+            "UPDATE ",
+            "VALUES",
+        ]
+        .iter()
+        .any(|k| u.starts_with(k))
+    }),
+    // CSS
+    hint(TextFormat::Css, 2, |l| {
+        l.ends_with('{')
+            && (l.starts_with('.')
+                || l.starts_with('#')
+                || l.starts_with('@')
+                || l.starts_with(':'))
+    }),
+    hint(TextFormat::Css, 1, |l| {
+        l.ends_with(';')
+            && l.split_once(':').is_some_and(|(prop, _)| {
+                !prop.is_empty() && prop.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+            })
+    }),
+    // Shell
+    hint(TextFormat::Shell, 2, |l| {
+        l.starts_with("$ ") || l.starts_with("sudo ") || l.starts_with("export ") && l.contains('=')
+    }),
+    hint(TextFormat::Shell, 2, |l| {
+        l.starts_with("if [") || l == "fi" || l == "done" || l == "then" || l == "esac"
+    }),
+    hint(TextFormat::Shell, 1, |l| {
+        [
+            "cd ", "echo ", "ls ", "mkdir ", "chmod ", "git ", "npm ", "cargo ", "apt ",
+            "apt-get ", "brew ", "curl ", "wget ", "pip ", "docker ",
+        ]
+        .iter()
+        .any(|k| l.starts_with(k))
+    }),
+    hint(TextFormat::Shell, 1, |l| {
+        l.contains(" | grep ") || l.contains(" && ") || l.contains("$(")
+    }),
+    // Markdown
+    hint(TextFormat::Markdown, 2, |l| {
+        let hashes = l.chars().take_while(|&c| c == '#').count();
+        (1..=6).contains(&hashes) && l[hashes..].starts_with(' ') && !l[hashes..].trim().is_empty()
+    }),
+    hint(TextFormat::Markdown, 2, |l| {
+        l.starts_with("```") || l.starts_with("- [ ]") || l.starts_with("- [x]")
+    }),
+    hint(TextFormat::Markdown, 1, |l| {
+        l.starts_with("> ") || l.starts_with("* ") || l.starts_with("- ") || l.starts_with("1. ")
+    }),
+    hint(TextFormat::Markdown, 1, |l| {
+        l.contains("](") && l.contains('[')
+            || l.contains("**")
+            || l.starts_with("| ") && l.ends_with(" |")
+    }),
+];
+
+fn best_scoring(text: &str) -> (TextFormat, u32) {
+    let mut scores: Vec<(TextFormat, u32)> = Vec::new();
+    for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        for hint in HINTS.iter().filter(|hint| (hint.matches)(line)) {
+            match scores.iter_mut().find(|(format, _)| *format == hint.format) {
+                Some((_, score)) => *score += hint.weight,
+                None => scores.push((hint.format, hint.weight)),
+            }
+        }
+    }
+    // TypeScript is JavaScript plus type annotations, and C++ is mostly C
+    absorb(&mut scores, TextFormat::TypeScript, TextFormat::JavaScript);
+    absorb(&mut scores, TextFormat::Cpp, TextFormat::C);
+
+    // Ties go to the format listed first in `HINTS`
+    scores
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, score)| *score)
+        .unwrap_or((TextFormat::Plain, 0))
+}
+
+// Fold the score of `base` into `superset` when there is any evidence for the latter
+fn absorb(scores: &mut Vec<(TextFormat, u32)>, superset: TextFormat, base: TextFormat) {
+    let Some(base_pos) = scores.iter().position(|(f, _)| *f == base) else {
+        return;
+    };
+    if let Some(pos) = scores.iter().position(|(f, _)| *f == superset) {
+        scores[pos].1 += scores[base_pos].1;
+        scores.remove(base_pos);
+    }
+}
+
+// At least two rows with the same number (> 0) of tabs or commas outside quotes
+fn classify_table(text: &str) -> Option<TextFormat> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.len() < 2 {
+        return None;
+    }
+    for (delimiter, format) in [('\t', TextFormat::Tsv), (',', TextFormat::Csv)] {
+        let first = count_delimiters(lines[0], delimiter);
+        if first > 0
+            && lines
+                .iter()
+                .all(|l| count_delimiters(l, delimiter) == first)
+        {
+            return Some(format);
+        }
+    }
+    None
+}
+
+fn count_delimiters(line: &str, delimiter: char) -> usize {
+    let mut in_quotes = false;
+    let mut count = 0;
+    for c in line.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => count += 1,
+            _ => {}
+        }
+    }
+    count
+}
+
+// Every line is a `key: value` pair, a `- item`, a comment or a document marker,
+// and there are at least two lines, one of them a mapping key
+fn looks_like_yaml(text: &str) -> bool {
+    if text.lines().filter(|l| !l.trim().is_empty()).count() < 2 {
+        return false;
+    }
+    let mut has_key = false;
+    for line in text
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.trim().is_empty())
+    {
+        let content = line.trim_start();
+        if content.starts_with('#') || content == "---" || content == "..." {
+            continue;
+        }
+        let content = content.strip_prefix("- ").unwrap_or(content);
+        if let Some((key, rest)) = content.split_once(':') {
+            let key = key.trim_matches(|c| c == '"' || c == '\'');
+            let is_key = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ' '))
+                && (rest.is_empty() || rest.starts_with(' '));
+            if is_key {
+                has_key = true;
+                continue;
+            }
+        }
+        if line.trim_start().starts_with("- ") || line.starts_with(' ') {
+            continue;
+        }
+        return false;
+    }
+    has_key
+}
+
+/// Reformat structured text with consistent indentation. Returns `None` when the format
+/// has no pretty form or the text can't be parsed; key order and values are preserved.
+pub fn pretty_print(text: &str, format: TextFormat) -> Option<String> {
+    match format {
+        TextFormat::Json => {
+            serde_json::from_str::<serde::de::IgnoredAny>(text).ok()?;
+            Some(indent_json(text.trim()))
+        }
+        TextFormat::Xml => indent_xml(text.trim()),
+        _ => None,
+    }
+}
+
+// Re-indent valid JSON token by token (rather than through `serde_json::Value`, which
+// would sort object keys)
+fn indent_json(json: &str) -> String {
+    const INDENT: &str = "  ";
+    let mut out = String::with_capacity(json.len() * 2);
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = json.chars().peekable();
+
+    let newline = |out: &mut String, depth: usize| {
+        out.push('\n');
+        out.push_str(&INDENT.repeat(depth));
+    };
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                out.push(c);
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                // Keep empty objects and arrays on one line
+                if matches!(chars.peek(), Some('}') | Some(']')) {
+                    out.push(chars.next().unwrap_or_default());
+                } else {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            '}' | ']' => {
+                depth = depth.saturating_sub(1);
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(": "),
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+    out.push('\n');
+    out
+}
+
+// Put every element on its own line, indented by nesting depth. Elements that only
+// contain text stay on one line. Returns `None` if the tags don't balance.
+fn indent_xml(xml: &str) -> Option<String> {
+    let mut tokens = Vec::new();
+    let mut rest = xml;
+    while !rest.is_empty() {
+        if rest.starts_with('<') {
+            let end = if rest.starts_with("<!--") {
+                rest.find("-->")? + 3
+            } else if rest.starts_with("<![CDATA[") {
+                rest.find("]]>")? + 3
+            } else {
+                rest.find('>')? + 1
+            };
+            tokens.push(&rest[..end]);
+            rest = &rest[end..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            if !rest[..end].trim().is_empty() {
+                tokens.push(rest[..end].trim());
+            }
+            rest = &rest[end..];
+        }
+    }
+
+    let is_open = |t: &str| {
+        t.starts_with('<')
+            && !t.starts_with("</")
+            && !t.starts_with("<?")
+            && !t.starts_with("<!")
+            && !t.ends_with("/>")
+    };
+    let is_text = |t: &str| !t.starts_with('<') || t.starts_with("<![CDATA[");
+
+    let mut out = String::with_capacity(xml.len() * 2);
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        let indent = "  ".repeat(depth);
+        if is_open(token) {
+            // <name>text</name> on a single line
+            if i + 2 < tokens.len() && is_text(tokens[i + 1]) && tokens[i + 2].starts_with("</") {
+                out.push_str(&format!(
+                    "{}{}{}{}\n",
+                    indent,
+                    token,
+                    tokens[i + 1],
+                    tokens[i + 2]
+                ));
+                i += 3;
+                continue;
+            }
+            out.push_str(&format!("{}{}\n", indent, token));
+            depth += 1;
+        } else if token.starts_with("</") {
+            depth = depth.checked_sub(1)?;
+            out.push_str(&format!("{}{}\n", "  ".repeat(depth), token));
+        } else {
+            out.push_str(&format!("{}{}\n", indent, token));
+        }
+        i += 1;
+    }
+
+    (depth == 0).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_json() {
+        assert_eq!(
+            classify(r#"{"name": "shelf", "items": [1, 2]}"#),
+            TextFormat::Json
+        );
+        assert_eq!(classify("[true, null]"), TextFormat::Json);
+    }
+
+    #[test]
+    fn invalid_json_is_not_json() {
+        assert_ne!(
+            classify(r#"{"name": "shelf", "items": [1, 2]"#),
+            TextFormat::Json
+        );
+        assert_ne!(classify("{ not json }"), TextFormat::Json);
+    }
+
+    #[test]
+    fn tells_xml_from_html() {
+        assert_eq!(
+            classify(r#"<?xml version="1.0"?><note><to>Ann</to></note>"#),
+            TextFormat::Xml
+        );
+        assert_eq!(classify("<note><to>Ann</to></note>"), TextFormat::Xml);
+        assert_eq!(
+            classify("<!DOCTYPE html><html><body></body></html>"),
+            TextFormat::Html
+        );
+        assert_eq!(classify("<div><p>Hello</p></div>"), TextFormat::Html);
+    }
+
+    #[test]
+    fn tells_csv_from_tsv() {
+        assert_eq!(classify("name,age\nAnn,31\nBob,42"), TextFormat::Csv);
+        assert_eq!(classify("name\tage\nAnn\t31\nBob\t42"), TextFormat::Tsv);
+        // Commas inside quotes don't count
+        assert_eq!(
+            classify("name,city\n\"Smith, Ann\",Oslo\nBob,Rome"),
+            TextFormat::Csv
+        );
+    }
+
+    #[test]
+    fn classifies_yaml() {
+        assert_eq!(
+            classify("name: shelf\nversion: 3\ntags:\n  - files\n  - drag"),
+            TextFormat::Yaml
+        );
+    }
+
+    #[test]
+    fn classifies_yaml_with_quoted_and_non_ascii_keys() {
+        assert_eq!(classify("\"name\": shelf\n'version': 3"), TextFormat::Yaml);
+        assert_eq!(classify("\"aé\": 1\nb: 2"), TextFormat::Yaml);
+        assert_eq!(classify("größe: 1\nbreite: 2"), TextFormat::Yaml);
+        // A colon without a space after it is not a mapping key
+        assert_eq!(classify("time:12\ndate:today"), TextFormat::Plain);
+    }
+
+    #[test]
+    fn classifies_shell() {
+        assert_eq!(classify("#!/bin/bash\necho hello"), TextFormat::Shell);
+        assert_eq!(
+            classify("cd app\nnpm install && npm run build\ngit status"),
+            TextFormat::Shell
+        );
+    }
+
+    #[test]
+    fn classifies_programming_languages() {
+        let cases = [
+            (
+                "use std::io::Read;\n\nfn main() {\n    let mut s = String::new();\n}",
+                TextFormat::Rust,
+            ),
+            (
+                "import os\n\ndef main():\n    print(os.getcwd())",
+                TextFormat::Python,
+            ),
+            (
+                "const add = (a, b) => a + b;\nconsole.log(add(1, 2));",
+                TextFormat::JavaScript,
+            ),
+            (
+                "interface User {\n  name: string;\n  age: number;\n}",
+                TextFormat::TypeScript,
+            ),
+            (
+                "package main\n\nimport \"fmt\"\n\nfunc main() {\n\tfmt.Println(\"hi\")\n}",
+                TextFormat::Go,
+            ),
+            (
+                "#include <stdio.h>\n\nint main(void) {\n    printf(\"hi\\n\");\n}",
+                TextFormat::C,
+            ),
+            (
+                "#include <iostream>\n\nint main() {\n    std::cout << \"hi\";\n}",
+                TextFormat::Cpp,
+            ),
+            (
+                "public class Main {\n    public static void main(String[] args) {\n        System.out.println(\"hi\");\n    }\n}",
+                TextFormat::Java,
+            ),
+            (
+                "using System;\n\nclass Program {\n    static void Main() {\n        Console.WriteLine(\"hi\");\n    }\n}",
+                TextFormat::CSharp,
+            ),
+            (
+                "SELECT name, age\nFROM users\nWHERE age > 30\nORDER BY name;",
+                TextFormat::Sql,
+            ),
+            (
+                ".button {\n  color: red;\n  margin-top: 4px;\n}",
+                TextFormat::Css,
+            ),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(classify(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn classifies_markdown() {
+        assert_eq!(
+            classify(
+                "# Title\n\nSome **bold** text and a [link](https://example.com).\n\n- one\n- two"
+            ),
+            TextFormat::Markdown
+        );
+        assert_eq!(
+            classify("## Todo\n- [ ] write tests\n- [x] fix yaml"),
+            TextFormat::Markdown
+        );
+        assert_eq!(
+            classify("```rust\nfn main() {}\n```\n\n> quoted"),
+            TextFormat::Markdown
+        );
+    }
+
+    #[test]
+    fn prose_is_plain() {
+        assert_eq!(
+            classify("Meet at the station at nine.\nBring the tickets."),
+            TextFormat::Plain
+        );
+    }
+
+    #[test]
+    fn pretty_json_round_trips() {
+        let json = r#"{"b":1,"a":[1,{"c":"x, y"}],"e":{},"s":"q\"{"}"#;
+        let pretty = pretty_print(json, TextFormat::Json).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap(),
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+        // Keys keep their order
+        assert!(pretty.find("\"b\"").unwrap() < pretty.find("\"a\"").unwrap());
+        assert_eq!(pretty_print(&pretty, TextFormat::Json).unwrap(), pretty);
+    }
+
+    #[test]
+    fn pretty_xml_round_trips() {
+        let xml = "<note><to>Ann</to><body><p>Hi</p><br/></body></note>";
+        let pretty = pretty_print(xml, TextFormat::Xml).unwrap();
+        assert_eq!(
+            pretty,
+            "<note>\n  <to>Ann</to>\n  <body>\n    <p>Hi</p>\n    <br/>\n  </body>\n</note>\n"
+        );
+        assert_eq!(pretty_print(&pretty, TextFormat::Xml).unwrap(), pretty);
+        assert_eq!(pretty.split_whitespace().collect::<String>(), xml);
+    }
+
+    #[test]
+    fn unbalanced_xml_is_left_alone() {
+        assert_eq!(pretty_print("<a><b></a>", TextFormat::Xml), None);
+        assert_eq!(pretty_print("{\"a\": ", TextFormat::Json), None);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::download::extension_for_mime;
+use crate::file::{sanitize_file_name, unique_path};
+use base64::alphabet;
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine as _;
+use percent_encoding::percent_decode_str;
+use reqwest::Url;
+use std::path::{Path, PathBuf};
+
+// Base64 engine that accepts payloads with or without trailing `=` padding,
+// since browsers and editors are not consistent about it in data URLs
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+const LENIENT_BASE64_URL_SAFE: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Where the content behind a URL dropped or pasted on the shelf comes from.
+#[derive(Debug)]
+pub enum UrlSource {
+    /// Inline `data:` URL, already decoded
+    Data(DataUrl),
+    /// Local file referenced by a `file://` URL
+    File(PathBuf),
+    /// Remote content that needs to be downloaded
+    Http(String),
+}
+
+#[derive(Debug)]
+pub struct DataUrl {
+    pub mime: String,
+    /// Some apps put the original file name in a `name=` parameter
+    pub name: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+pub fn parse_source(url: &str) -> Result<UrlSource, String> {
+    let url = url.trim();
+    let scheme = url
+        .split_once(':')
+        .map(|(scheme, _)| scheme.to_lowercase())
+        .ok_or_else(|| format!("Not a URL: {}", url))?;
+
+    match scheme.as_str() {
+        "data" => decode_data_url(url).map(UrlSource::Data),
+        "file" => {
+            let parsed = Url::parse(url).map_err(|e| format!("Invalid file URL: {}", e))?;
+            let path = parsed
+                .to_file_path()
+                .map_err(|_| format!("Not a local file URL: {}", url))?;
+            Ok(UrlSource::File(path))
+        }
+        "http" | "https" => {
+            Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+            Ok(UrlSource::Http(url.to_string()))
+        }
+        _ => Err(format!("Unsupported URL scheme: {}", scheme)),
+    }
+}
+
+/// Decode a `data:[<mediatype>][;base64],<data>` URL (RFC 2397).
+pub fn decode_data_url(url: &str) -> Result<DataUrl, String> {
+    let rest = url
+        .get(..5)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("data:"))
+        .map(|_| &url[5..])
+        .ok_or("Not a data URL")?;
+    let (header, payload) = rest
+        .split_once(',')
+        .ok_or("Malformed data URL: missing ','")?;
+
+    let mut params = header.split(';').map(str::trim);
+    let mut mime = params.next().unwrap_or_default().to_lowercase();
+    if mime.is_empty() {
+        mime = "text/plain".to_string();
+    }
+
+    let mut is_base64 = false;
+    let mut name = None;
+    for param in params {
+        if param.eq_ignore_ascii_case("base64") {
+            is_base64 = true;
+        } else if let Some((key, value)) = param.split_once('=') {
+            if key.trim().eq_ignore_ascii_case("name")
+                || key.trim().eq_ignore_ascii_case("filename")
+            {
+                let value = percent_decode_str(value.trim_matches('"'))
+                    .decode_utf8_lossy()
+                    .to_string();
+                name = Some(value);
+            }
+        }
+    }
+
+    // The payload may be percent-encoded even when it is base64 (e.g. `%2B` for `+`)
+    let decoded: Vec<u8> = percent_decode_str(payload).collect();
+    let bytes = if is_base64 {
+        let compact: Vec<u8> = decoded
+            .into_iter()
+            .filter(|b| !b.is_ascii_whitespace())
+            .collect();
+        LENIENT_BASE64
+            .decode(&compact)
+            .or_else(|_| LENIENT_BASE64_URL_SAFE.decode(&compact))
+            .map_err(|e| format!("Invalid base64 in data URL: {}", e))?
+    } else {
+        decoded
+    };
+
+    Ok(DataUrl { mime, name, bytes })
+}
+
+/// Write a decoded data URL into `dir` with a name and extension matching its content.
+pub fn save_data_url(data: &DataUrl, dir: &Path) -> Result<PathBuf, String> {
+    let ext = extension_for_mime(&data.mime).unwrap_or("bin");
+    let file_name = data
+        .name
+        .as_deref()
+        .map(sanitize_file_name)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            if Path::new(&name).extension().is_some() {
+                name
+            } else {
+                format!("{}.{}", name, ext)
+            }
+        })
+        .unwrap_or_else(|| format!("pasted_{}.{}", chrono::Local::now().format("%H%M%S"), ext));
+
+    let path = unique_path(dir, &file_name);
+    std::fs::write(&path, &data.bytes).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(url: &str) -> DataUrl {
+        decode_data_url(url).unwrap()
+    }
+
+    #[test]
+    fn decodes_base64_payloads() {
+        let decoded = data("data:image/png;base64,aGVsbG8=");
+        assert_eq!(decoded.mime, "image/png");
+        assert_eq!(decoded.bytes, b"hello");
+        assert_eq!(decoded.name, None);
+    }
+
+    #[test]
+    fn decodes_base64_without_padding() {
+        assert_eq!(data("data:text/plain;base64,aGVsbG8").bytes, b"hello");
+        assert_eq!(data("data:text/plain;base64,aGk").bytes, b"hi");
+    }
+
+    #[test]
+    fn decodes_percent_encoded_and_url_safe_base64() {
+        assert_eq!(data("data:;base64,%2B%2F8%3D").bytes, [0xfb, 0xff]);
+        assert_eq!(data("data:;base64,-_8").bytes, [0xfb, 0xff]);
+        assert_eq!(data("data:;base64,aGVs\n bG8=").bytes, b"hello");
+    }
+
+    #[test]
+    fn decodes_percent_encoded_payloads() {
+        let decoded = data("data:text/html,%3Cb%3Ehello%20world%3C%2Fb%3E");
+        assert_eq!(decoded.mime, "text/html");
+        assert_eq!(decoded.bytes, b"<b>hello world</b>");
+    }
+
+    #[test]
+    fn empty_mediatype_defaults_to_text_plain() {
+        assert_eq!(data("data:,hi").mime, "text/plain");
+        assert_eq!(data("data:;base64,aGk=").mime, "text/plain");
+        assert_eq!(data("DATA:Image/PNG;BASE64,aGk=").mime, "image/png");
+    }
+
+    #[test]
+    fn reads_name_parameter() {
+        let decoded = data("data:image/png;name=my%20photo.png;base64,aGk=");
+        assert_eq!(decoded.name.as_deref(), Some("my photo.png"));
+        let decoded = data("data:application/pdf;filename=\"report\";base64,aGk=");
+        assert_eq!(decoded.name.as_deref(), Some("report"));
+    }
+
+    #[test]
+    fn rejects_malformed_data_urls() {
+        assert!(decode_data_url("data:text/plain").is_err());
+        assert!(decode_data_url("data:;base64,!!!").is_err());
+        assert!(decode_data_url("http://example.com").is_err());
+        assert!(decode_data_url("dat").is_err());
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn parses_file_urls_to_paths() {
+        match parse_source("file:///tmp/my%20file.txt").unwrap() {
+            UrlSource::File(path) => assert_eq!(path, Path::new("/tmp/my file.txt")),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn parses_file_urls_to_paths() {
+        match parse_source("file:///C:/Users/me/my%20file.txt").unwrap() {
+            UrlSource::File(path) => {
+                assert_eq!(path, Path::new(r"C:\Users\me\my file.txt"))
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_other_sources() {
+        assert!(matches!(
+            parse_source(" data:,hi ").unwrap(),
+            UrlSource::Data(DataUrl { ref bytes, .. }) if bytes == b"hi"
+        ));
+        assert!(matches!(
+            parse_source("HTTPS://example.com/a.png").unwrap(),
+            UrlSource::Http(url) if url == "HTTPS://example.com/a.png"
+        ));
+        assert!(parse_source("ftp://example.com/a.txt").is_err());
+        assert!(parse_source("just text").is_err());
+    }
+
+    #[test]
+    fn saves_data_urls_with_an_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path =
+            save_data_url(&data("data:image/png;name=photo;base64,aGk="), dir.path()).unwrap();
+        assert_eq!(path.file_name().unwrap(), "photo.png");
+        assert_eq!(std::fs::read(&path).unwrap(), b"hi");
+
+        let path = save_data_url(&data("data:image/png;name=notes.txt,hi"), dir.path()).unwrap();
+        assert_eq!(path.file_name().unwrap(), "notes.txt");
+    }
+}
@@ -1,15 +1,31 @@
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
+use std::time::Instant;
 use tauri::Manager;
 #[cfg(target_os = "windows")]
 use tauri::PhysicalPosition;
 use tracing::{error, info, warn};
 
-#[derive(Clone)]
+/// Drops on the main window, which the mouse monitors check to tell whether a drag
+/// they opened the window for ended there.
+#[derive(Default)]
 struct DragState {
-    drag_started: Arc<AtomicBool>,
-    successful_drop: Arc<AtomicBool>,
+    last_drop: Mutex<Option<Instant>>,
+}
+
+impl DragState {
+    fn record_drop(&self) {
+        if let Ok(mut last_drop) = self.last_drop.lock() {
+            *last_drop = Some(Instant::now());
+        }
+    }
+
+    fn dropped_since(&self, since: Instant) -> bool {
+        self.last_drop
+            .lock()
+            .map(|last_drop| last_drop.is_some_and(|dropped| dropped >= since))
+            .unwrap_or(false)
+    }
 }
 use tauri::WebviewUrl;
 use tauri::WebviewWindowBuilder;
@@ -22,13 +38,21 @@ use windows::Win32::System::Threading::{CreateMutexW, ReleaseMutex, WaitForSingl
 #[cfg(target_os = "windows")]
 use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
 mod analytics;
+mod clipboard;
 mod commands;
 mod config;
+mod download;
+mod drag_image;
+mod drop_rules;
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+mod drop_target;
 mod file;
 mod file_drop;
+mod ingest;
 mod logging;
-#[cfg(any(target_os = "windows", target_os = "macos"))]
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
 mod mouse_monitor;
+mod shelf;
 mod thumbnail;
 #[cfg(desktop)]
 mod tray;
@@ -38,13 +62,13 @@ mod utils;
 mod custom_drop;
 
 use analytics::AnalyticsService;
-use commands::{config_ops::*, drag_ops::*, file_ops::*, window_ops::*};
+use commands::{clipboard_ops::*, config_ops::*, drag_ops::*, file_ops::*, window_ops::*};
 use config::AppConfig;
-use file::FileMetadata;
-#[cfg(any(target_os = "windows", target_os = "macos"))]
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
 use mouse_monitor::start_mouse_monitor;
+use shelf::ShelfItem;
 
-type FileList = Arc<Mutex<Vec<FileMetadata>>>;
+type FileList = Arc<Mutex<Vec<ShelfItem>>>;
 
 fn build_app() -> tauri::Builder<tauri::Wry> {
     let mut builder = tauri::Builder::default()
@@ -115,9 +139,23 @@ fn build_app() -> tauri::Builder<tauri::Wry> {
             save_pasted_text,
             save_pasted_data_base64,
             download_image_to_shelf,
+            cancel_download,
+            add_from_url,
+            ingest_html,
+            add_dropped_image,
+            add_links_from_text,
             remove_files,
             get_files,
             rename_file,
+            add_text_item,
+            update_text_item,
+            materialize_item,
+            copy_items_to_clipboard,
+            paste_from_clipboard,
+            get_clipboard_shelf,
+            clear_clipboard_shelf,
+            move_clipboard_items_to_shelf,
+            set_clipboard_watch,
             get_file_icon_base64,
             clear_files,
             refresh_file_list,
@@ -187,11 +225,16 @@ fn build_app() -> tauri::Builder<tauri::Wry> {
             let file_list: FileList = Arc::new(Mutex::new(Vec::new()));
             app.manage(file_list.clone());
 
+            app.manage(download::DownloadManager::default());
+
+            // Clipboard shelf, filled by the clipboard watcher when it is enabled
+            app.manage(clipboard::watcher::ClipboardShelf::default());
+            let clipboard_watcher = clipboard::watcher::ClipboardWatcher::default();
+            clipboard_watcher.apply(app.handle(), config.clipboard_watch.enabled);
+            app.manage(clipboard_watcher);
+
             // Create drag state
-            let drag_state = Arc::new(DragState {
-                drag_started: Arc::new(AtomicBool::new(false)),
-                successful_drop: Arc::new(AtomicBool::new(false)),
-            });
+            let drag_state = Arc::new(DragState::default());
             app.manage(drag_state.clone());
 
             // Check for updates on startup
@@ -243,8 +286,8 @@ fn build_app() -> tauri::Builder<tauri::Wry> {
                 tray::create_tray(handle)?;
             }
 
-            // Start the mouse monitor with configuration (Windows and macOS)
-            #[cfg(any(target_os = "windows", target_os = "macos"))]
+            // Start the mouse monitor with configuration (Windows, macOS and X11)
+            #[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
             {
                 let app_handle = app.handle().clone();
                 let config_state = app.state::<Arc<Mutex<AppConfig>>>();
@@ -264,6 +307,11 @@ fn build_app() -> tauri::Builder<tauri::Wry> {
                     let _ = window.hide();
                 }
 
+                #[cfg(target_os = "linux")]
+                if let Err(e) = drop_target::register(&window) {
+                    warn!("Failed to register drop target: {}", e);
+                }
+
                 #[cfg(target_os = "windows")]
                 {
                     use windows::Win32::Foundation::HWND;
@@ -289,32 +337,32 @@ fn build_app() -> tauri::Builder<tauri::Wry> {
 
             Ok(())
         })
-        .on_window_event(|window, event| {
-            if let WindowEvent::DragDrop(drop_event) = event {
-                let drag_state = window.app_handle().state::<Arc<DragState>>().inner();
-                match drop_event {
-                    DragDropEvent::Enter { .. } => {
-                        drag_state.drag_started.store(true, Ordering::Relaxed);
-                    }
-                    DragDropEvent::Drop { paths, .. } => {
-                        info!("Received {} dropped file(s) in the app window", paths.len());
-                        drag_state.successful_drop.store(true, Ordering::Relaxed);
-                        drag_state.drag_started.store(false, Ordering::Relaxed);
-
-                        // Handle the file drop
-                        let app_handle = window.app_handle();
-                        let file_list_state = app_handle.state::<FileList>();
-                        file_drop::handle_file_drop_from_paths(
-                            paths.clone(),
-                            file_list_state.inner().clone(),
-                            app_handle.clone(),
-                        );
-
-                        // Do not hide the window after processing - let user interact with the files
-                    }
-                    _ => {}
+        .on_window_event(|window, event| match event {
+            // Tauri can't refuse the drop, but the frontend can say why it won't be taken
+            WindowEvent::DragDrop(DragDropEvent::Enter { paths, .. }) => {
+                let app_handle = window.app_handle();
+                if let Err(reason) =
+                    drop_rules::check_hovered_paths(&drop_rules::current_rules(app_handle), paths)
+                {
+                    drop_rules::reject(app_handle, &reason);
                 }
             }
+            WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) => {
+                info!("Received {} dropped file(s) in the app window", paths.len());
+                window.app_handle().state::<Arc<DragState>>().record_drop();
+
+                // Handle the file drop
+                let app_handle = window.app_handle();
+                let file_list_state = app_handle.state::<FileList>();
+                file_drop::handle_file_drop_from_paths(
+                    paths.clone(),
+                    file_list_state.inner().clone(),
+                    app_handle.clone(),
+                );
+
+                // Do not hide the window after processing - let user interact with the files
+            }
+            _ => {}
         })
 }
 
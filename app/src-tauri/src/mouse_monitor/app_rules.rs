@@ -0,0 +1,294 @@
+// Which apps a drag may open the shelf from. Every backend finds the app a drag comes
+// from when it starts and resolves the rules here once, so they behave the same
+// everywhere.
+
+use crate::config::{AppRule, MatchKind, MatchTarget, MouseMonitorConfig, RuleAction};
+use crate::utils::same_app_name;
+use regex::RegexBuilder;
+use tracing::{info, warn};
+
+/// The app a drag comes from.
+#[derive(Debug, Clone, Default)]
+pub struct SourceApp {
+    /// File name of the executable
+    pub name: String,
+    pub path: Option<String>,
+}
+
+impl SourceApp {
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    pub fn from_process(process_id: u32) -> Self {
+        SourceApp {
+            name: crate::utils::process_name(process_id).unwrap_or_default(),
+            path: crate::utils::process_path(process_id)
+                .map(|path| path.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+/// The mouse monitor settings for a drag from `app`: `None` if the rules keep it from
+/// opening the shelf, otherwise `config` with the matching rule's shake settings.
+pub fn resolve(config: &MouseMonitorConfig, app: &SourceApp) -> Option<MouseMonitorConfig> {
+    let rule = config.app_rules.iter().find(|rule| matches(rule, app));
+    let action = rule.map_or(config.unmatched_action, |rule| rule.action);
+    if action == RuleAction::Deny {
+        return None;
+    }
+
+    let mut resolved = config.clone();
+    if let Some(rule) = rule {
+        resolved.required_shakes = rule.required_shakes.unwrap_or(config.required_shakes);
+        resolved.shake_threshold = rule.shake_threshold.unwrap_or(config.shake_threshold);
+        resolved.shake_time_limit = rule.shake_time_limit.unwrap_or(config.shake_time_limit);
+        resolved.min_shake_speed = rule.min_shake_speed.unwrap_or(config.min_shake_speed);
+    }
+    Some(resolved)
+}
+
+/// Resolve the rules for a drag from `app` when it starts, as [`resolve`] does, and log
+/// the decision. The backends keep the result for the rest of the drag.
+pub fn decide(config: &MouseMonitorConfig, app: &SourceApp) -> Option<MouseMonitorConfig> {
+    let name = if app.name.is_empty() {
+        "an unknown app"
+    } else {
+        app.name.as_str()
+    };
+    let resolved = resolve(config, app);
+    if resolved.is_some() {
+        info!("Drag from {} may open the shelf", name);
+    } else {
+        info!("App rules ignore drags from {}", name);
+    }
+    resolved
+}
+
+fn matches(rule: &AppRule, app: &SourceApp) -> bool {
+    let subject = match rule.match_on {
+        MatchTarget::Name => app.name.as_str(),
+        MatchTarget::Path => match app.path.as_deref() {
+            Some(path) => path,
+            None => return false,
+        },
+    };
+    let pattern = rule.pattern.trim();
+
+    match rule.match_kind {
+        MatchKind::Exact => match rule.match_on {
+            MatchTarget::Name => same_app_name(subject, pattern),
+            MatchTarget::Path => subject.eq_ignore_ascii_case(pattern),
+        },
+        MatchKind::Glob => is_match(&glob_to_regex(pattern), subject),
+        MatchKind::Regex => is_match(pattern, subject),
+    }
+}
+
+fn is_match(pattern: &str, subject: &str) -> bool {
+    match RegexBuilder::new(pattern).case_insensitive(true).build() {
+        Ok(regex) => regex.is_match(subject),
+        Err(e) => {
+            warn!(
+                "Ignoring app rule with invalid pattern {:?}: {}",
+                pattern, e
+            );
+            false
+        }
+    }
+}
+
+// `*` stands for any run of characters and `?` for one, across the whole subject
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    fn rule(action: RuleAction, pattern: &str, kind: MatchKind, on: MatchTarget) -> AppRule {
+        AppRule {
+            action,
+            pattern: pattern.to_string(),
+            match_kind: kind,
+            match_on: on,
+            required_shakes: None,
+            shake_threshold: None,
+            shake_time_limit: None,
+            min_shake_speed: None,
+        }
+    }
+
+    fn name_rule(kind: MatchKind, pattern: &str) -> AppRule {
+        rule(RuleAction::Allow, pattern, kind, MatchTarget::Name)
+    }
+
+    fn path_rule(kind: MatchKind, pattern: &str) -> AppRule {
+        rule(RuleAction::Allow, pattern, kind, MatchTarget::Path)
+    }
+
+    fn app(name: &str, path: Option<&str>) -> SourceApp {
+        SourceApp {
+            name: name.to_string(),
+            path: path.map(str::to_string),
+        }
+    }
+
+    fn with_rules(rules: Vec<AppRule>, unmatched_action: RuleAction) -> MouseMonitorConfig {
+        MouseMonitorConfig {
+            app_rules: rules,
+            unmatched_action,
+            ..AppConfig::default().mouse_monitor
+        }
+    }
+
+    #[test]
+    fn exact_names_ignore_case_and_exe() {
+        let explorer = app("explorer.exe", None);
+        assert!(matches(
+            &name_rule(MatchKind::Exact, "explorer.exe"),
+            &explorer
+        ));
+        assert!(matches(&name_rule(MatchKind::Exact, "Explorer"), &explorer));
+        assert!(matches(
+            &name_rule(MatchKind::Exact, " explorer "),
+            &explorer
+        ));
+        assert!(matches(
+            &name_rule(MatchKind::Exact, "explorer.exe"),
+            &app("explorer", None)
+        ));
+        assert!(!matches(&name_rule(MatchKind::Exact, "explore"), &explorer));
+    }
+
+    #[test]
+    fn exact_paths_match_the_whole_path() {
+        let nautilus = app("nautilus", Some("/usr/bin/nautilus"));
+        assert!(matches(
+            &path_rule(MatchKind::Exact, "/USR/bin/nautilus"),
+            &nautilus
+        ));
+        assert!(!matches(
+            &path_rule(MatchKind::Exact, "/usr/bin"),
+            &nautilus
+        ));
+        assert!(!matches(
+            &path_rule(MatchKind::Exact, "nautilus"),
+            &nautilus
+        ));
+    }
+
+    #[test]
+    fn path_rules_skip_apps_without_a_path() {
+        let unknown = app("nautilus", None);
+        assert!(!matches(&path_rule(MatchKind::Glob, "*"), &unknown));
+        assert!(!matches(&path_rule(MatchKind::Regex, ""), &unknown));
+    }
+
+    #[test]
+    fn globs_match_the_whole_subject() {
+        let code = app(
+            "Code.exe",
+            Some(r"C:\Program Files\Microsoft VS Code\Code.exe"),
+        );
+        assert!(matches(&name_rule(MatchKind::Glob, "code*"), &code));
+        assert!(matches(&name_rule(MatchKind::Glob, "C?de.exe"), &code));
+        assert!(!matches(&name_rule(MatchKind::Glob, "ode*"), &code));
+        assert!(matches(
+            &path_rule(MatchKind::Glob, r"C:\Program Files\*"),
+            &code
+        ));
+        assert!(!matches(&path_rule(MatchKind::Glob, r"D:\*"), &code));
+    }
+
+    #[test]
+    fn regexes_match_anywhere() {
+        let dolphin = app("dolphin", Some("/usr/bin/dolphin"));
+        assert!(matches(&name_rule(MatchKind::Regex, "olph"), &dolphin));
+        assert!(matches(
+            &name_rule(MatchKind::Regex, "^(dolphin|nautilus)$"),
+            &dolphin
+        ));
+        assert!(matches(&path_rule(MatchKind::Regex, "^/USR/"), &dolphin));
+        assert!(!matches(&name_rule(MatchKind::Regex, "^olph"), &dolphin));
+    }
+
+    #[test]
+    fn invalid_regexes_match_nothing() {
+        assert!(!matches(
+            &name_rule(MatchKind::Regex, "(dolphin"),
+            &app("(dolphin", None)
+        ));
+    }
+
+    #[test]
+    fn globs_escape_regex_syntax() {
+        assert_eq!(glob_to_regex("*.exe"), r"^.*\.exe$");
+        assert_eq!(glob_to_regex("a?c"), "^a.c$");
+        assert_eq!(glob_to_regex("(a|b)+[c]"), r"^\(a\|b\)\+\[c\]$");
+        assert!(matches(
+            &name_rule(MatchKind::Glob, "a+b.exe"),
+            &app("a+b.exe", None)
+        ));
+        assert!(!matches(
+            &name_rule(MatchKind::Glob, "a+b.exe"),
+            &app("aab.exe", None)
+        ));
+        assert!(!matches(
+            &name_rule(MatchKind::Glob, "a.exe"),
+            &app("abexe", None)
+        ));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let deny_code = rule(
+            RuleAction::Deny,
+            "code",
+            MatchKind::Exact,
+            MatchTarget::Name,
+        );
+        let allow_all = name_rule(MatchKind::Glob, "*");
+
+        let config = with_rules(vec![deny_code.clone(), allow_all.clone()], RuleAction::Deny);
+        assert!(resolve(&config, &app("Code.exe", None)).is_none());
+        assert!(resolve(&config, &app("explorer.exe", None)).is_some());
+
+        let config = with_rules(vec![allow_all, deny_code], RuleAction::Deny);
+        assert!(resolve(&config, &app("Code.exe", None)).is_some());
+    }
+
+    #[test]
+    fn unmatched_apps_follow_the_unmatched_action() {
+        let rules = vec![name_rule(MatchKind::Exact, "explorer.exe")];
+        let firefox = app("firefox", None);
+        assert!(resolve(&with_rules(rules.clone(), RuleAction::Deny), &firefox).is_none());
+        assert!(resolve(&with_rules(rules, RuleAction::Allow), &firefox).is_some());
+    }
+
+    #[test]
+    fn matching_rule_overrides_shake_settings() {
+        let mut slow = name_rule(MatchKind::Exact, "slow");
+        slow.required_shakes = Some(2);
+        slow.min_shake_speed = Some(100.0);
+        let config = with_rules(vec![slow], RuleAction::Allow);
+
+        let resolved = resolve(&config, &app("slow", None)).unwrap();
+        assert_eq!(resolved.required_shakes, 2);
+        assert_eq!(resolved.min_shake_speed, 100.0);
+        assert_eq!(resolved.shake_threshold, config.shake_threshold);
+        assert_eq!(resolved.shake_time_limit, config.shake_time_limit);
+
+        let resolved = resolve(&config, &app("other", None)).unwrap();
+        assert_eq!(resolved.required_shakes, config.required_shakes);
+        assert_eq!(resolved.min_shake_speed, config.min_shake_speed);
+    }
+}
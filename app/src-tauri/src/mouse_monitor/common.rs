@@ -1 +1,369 @@
+// Shake recognition shared by the platform backends. The detector only sees timestamped
+// cursor positions, so it works the same whatever the polling rate.
+//
+// The cursor path is split into strokes at each reversal: a stroke ends once the cursor
+// has come back a quarter of the minimum amplitude from the point furthest from the
+// stroke's start. A stroke counts if it is long and fast enough, and a shake is enough
+// counted strokes in a row, each within the time limit of the one before.
+
+use crate::config::{MouseMonitorConfig, ShakeAxis};
+use std::time::{Duration, Instant};
+
 pub const DRAG_PASTEBOARD_NAME: &str = "Apple CFPasteboard drag";
+
+// How far the cursor must come back from a stroke's furthest point, as a fraction of
+// the minimum amplitude, before that is taken as a reversal
+const REVERSAL_FRACTION: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    x: f64,
+    y: f64,
+    at: Instant,
+}
+
+impl Sample {
+    fn distance(&self, other: &Sample) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// Recognises shake gestures in a stream of cursor positions.
+#[derive(Debug, Clone)]
+pub struct ShakeDetector {
+    axis: ShakeAxis,
+    /// Shortest stroke that counts, in pixels
+    min_amplitude: f64,
+    /// Slowest stroke that counts, in pixels per second
+    min_speed: f64,
+    /// Longest time between two counted strokes
+    time_limit: Duration,
+    required_reversals: u32,
+
+    last: Option<Sample>,
+    // Start of the current stroke, and its point furthest from the start
+    origin: Option<Sample>,
+    extreme: Option<Sample>,
+    reversals: u32,
+    last_reversal: Option<Instant>,
+}
+
+impl ShakeDetector {
+    pub fn new(
+        axis: ShakeAxis,
+        min_amplitude: f64,
+        min_speed: f64,
+        time_limit: Duration,
+        required_reversals: u32,
+    ) -> Self {
+        ShakeDetector {
+            axis,
+            min_amplitude: min_amplitude.max(1.0),
+            min_speed: min_speed.max(0.0),
+            time_limit,
+            required_reversals: required_reversals.max(1),
+            last: None,
+            origin: None,
+            extreme: None,
+            reversals: 0,
+            last_reversal: None,
+        }
+    }
+
+    pub fn from_config(config: &MouseMonitorConfig) -> Self {
+        Self::new(
+            config.shake_axis,
+            config.shake_threshold as f64,
+            config.min_shake_speed,
+            Duration::from_millis(config.shake_time_limit),
+            config.required_shakes,
+        )
+    }
+
+    /// Take the thresholds from `config`, keeping the gesture in progress.
+    pub fn configure(&mut self, config: &MouseMonitorConfig) {
+        let fresh = Self::from_config(config);
+        self.axis = fresh.axis;
+        self.min_amplitude = fresh.min_amplitude;
+        self.min_speed = fresh.min_speed;
+        self.time_limit = fresh.time_limit;
+        self.required_reversals = fresh.required_reversals;
+    }
+
+    /// Forget the gesture in progress, e.g. when the mouse button is released.
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.origin = None;
+        self.extreme = None;
+        self.reversals = 0;
+        self.last_reversal = None;
+    }
+
+    /// Feed the cursor position at `at`. Returns true when it completes a shake, after
+    /// which the detector starts over.
+    pub fn update(&mut self, x: f64, y: f64, at: Instant) -> bool {
+        let sample = self.project(x, y, at);
+        let last = self.last.replace(sample);
+
+        if let Some(last_reversal) = self.last_reversal {
+            if at.saturating_duration_since(last_reversal) > self.time_limit {
+                self.reversals = 0;
+                self.last_reversal = None;
+            }
+        }
+
+        let (Some(mut origin), Some(mut extreme)) = (self.origin, self.extreme) else {
+            self.origin = Some(sample);
+            self.extreme = Some(sample);
+            return false;
+        };
+
+        let reach = extreme.distance(&origin);
+        let distance = sample.distance(&origin);
+        let idle = last.is_some_and(|last| last.distance(&sample) < 0.5);
+        if idle && reach < self.min_amplitude {
+            // A stroke starts when the cursor starts moving, not where it rested
+            origin = sample;
+            extreme = sample;
+        } else if distance >= reach {
+            extreme = sample;
+        } else if reach - distance >= self.min_amplitude * REVERSAL_FRACTION {
+            self.end_stroke(&origin, &extreme);
+            origin = extreme;
+            extreme = sample;
+        }
+        self.origin = Some(origin);
+        self.extreme = Some(extreme);
+
+        if self.reversals >= self.required_reversals {
+            self.reset();
+            return true;
+        }
+        false
+    }
+
+    // Count the stroke from `origin` to `extreme` if it is long and fast enough
+    fn end_stroke(&mut self, origin: &Sample, extreme: &Sample) {
+        let length = extreme.distance(origin);
+        let seconds = extreme
+            .at
+            .saturating_duration_since(origin.at)
+            .as_secs_f64();
+        let fast_enough = seconds == 0.0 || length / seconds >= self.min_speed;
+        if length >= self.min_amplitude && fast_enough {
+            self.reversals += 1;
+            self.last_reversal = Some(extreme.at);
+        } else {
+            self.reversals = 0;
+            self.last_reversal = None;
+        }
+    }
+
+    // Only the movement along the configured axis matters
+    fn project(&self, x: f64, y: f64, at: Instant) -> Sample {
+        match self.axis {
+            ShakeAxis::Horizontal => Sample { x, y: 0.0, at },
+            ShakeAxis::Vertical => Sample { x: 0.0, y, at },
+            ShakeAxis::Any => Sample { x, y, at },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::TAU;
+
+    // The polling intervals of the fastest and slowest backends
+    const INTERVALS_MS: [u64; 2] = [8, 50];
+
+    fn detector(axis: ShakeAxis) -> ShakeDetector {
+        ShakeDetector::new(axis, 100.0, 800.0, Duration::from_millis(1500), 5)
+    }
+
+    // Sample the cursor path `trace` (position at a time in seconds) every
+    // `interval_ms` for `duration_ms`, and return when the first shake was recognised
+    fn run(
+        mut detector: ShakeDetector,
+        trace: impl Fn(f64) -> (f64, f64),
+        duration_ms: u64,
+        interval_ms: u64,
+    ) -> Option<u64> {
+        let start = Instant::now();
+        (0..=duration_ms).step_by(interval_ms as usize).find(|&ms| {
+            let (x, y) = trace(ms as f64 / 1000.0);
+            detector.update(500.0 + x, 500.0 + y, start + Duration::from_millis(ms))
+        })
+    }
+
+    // Back and forth `amplitude` pixels either side of the start, `hz` times a second
+    fn oscillation(amplitude: f64, hz: f64) -> impl Fn(f64) -> f64 {
+        move |t| amplitude * (TAU * hz * t).sin()
+    }
+
+    #[test]
+    fn recognises_a_horizontal_shake() {
+        for interval in INTERVALS_MS {
+            let shake = oscillation(150.0, 4.0);
+            let fired = run(
+                detector(ShakeAxis::Horizontal),
+                |t| (shake(t), 0.0),
+                2000,
+                interval,
+            );
+            // Five strokes of an eighth of a second, plus the start of a sixth
+            assert!(
+                fired.is_some_and(|ms| ms <= 800),
+                "{} ms sampling: {:?}",
+                interval,
+                fired
+            );
+        }
+    }
+
+    #[test]
+    fn recognises_a_vertical_shake_on_the_vertical_axis_only() {
+        for interval in INTERVALS_MS {
+            let shake = oscillation(150.0, 4.0);
+            let trace = |t| (0.0, shake(t));
+            let vertical = run(detector(ShakeAxis::Vertical), trace, 2000, interval);
+            let horizontal = run(detector(ShakeAxis::Horizontal), trace, 2000, interval);
+            let any = run(detector(ShakeAxis::Any), trace, 2000, interval);
+            assert!(vertical.is_some(), "{} ms sampling", interval);
+            assert!(horizontal.is_none(), "{} ms sampling", interval);
+            assert!(any.is_some(), "{} ms sampling", interval);
+        }
+    }
+
+    #[test]
+    fn recognises_circling() {
+        for interval in INTERVALS_MS {
+            let radius = 120.0;
+            let turn = |t: f64| TAU * 3.0 * t;
+            let trace = |t| (radius * turn(t).cos(), radius * turn(t).sin());
+            let fired = run(detector(ShakeAxis::Any), trace, 2000, interval);
+            assert!(fired.is_some(), "{} ms sampling", interval);
+        }
+    }
+
+    #[test]
+    fn ignores_slow_drift() {
+        for interval in INTERVALS_MS {
+            // 600 px back and forth at 300 px/s, too slow to count
+            let trace = |t: f64| {
+                let leg = (t * 300.0) % 1200.0;
+                (if leg < 600.0 { leg } else { 1200.0 - leg }, 0.0)
+            };
+            let fired = run(detector(ShakeAxis::Horizontal), trace, 10_000, interval);
+            assert!(fired.is_none(), "{} ms sampling: {:?}", interval, fired);
+
+            // A steady drift in one direction never reverses
+            let fired = run(
+                detector(ShakeAxis::Any),
+                |t| (t * 2000.0, t * 500.0),
+                2000,
+                interval,
+            );
+            assert!(fired.is_none(), "{} ms sampling: {:?}", interval, fired);
+        }
+    }
+
+    #[test]
+    fn ignores_jitter_below_the_amplitude() {
+        for interval in INTERVALS_MS {
+            // Fast, but only 80 px from one side to the other
+            let jitter = oscillation(40.0, 7.0);
+            let tremor = oscillation(5.0, 23.0);
+            let trace = |t| (jitter(t) + tremor(t), tremor(t));
+            let fired = run(detector(ShakeAxis::Any), trace, 5000, interval);
+            assert!(fired.is_none(), "{} ms sampling: {:?}", interval, fired);
+        }
+    }
+
+    #[test]
+    fn starts_over_after_the_time_limit() {
+        for interval in INTERVALS_MS {
+            // Three quick strokes, a rest longer than the time limit, then three more
+            let shake = oscillation(150.0, 4.0);
+            let trace = |t: f64| {
+                let x = if t < 0.375 {
+                    shake(t)
+                } else if t < 2.375 {
+                    shake(0.375)
+                } else {
+                    shake(t - 2.0)
+                };
+                (x, 0.0)
+            };
+            let fired = run(detector(ShakeAxis::Horizontal), trace, 2750, interval);
+            assert!(fired.is_none(), "{} ms sampling: {:?}", interval, fired);
+
+            // The same strokes without the rest are a shake
+            let fired = run(
+                detector(ShakeAxis::Horizontal),
+                |t| (shake(t), 0.0),
+                750,
+                interval,
+            );
+            assert!(fired.is_some(), "{} ms sampling", interval);
+        }
+    }
+
+    // Feed a trace of `ms,x,y` lines (see `traces/`), and return when the first shake
+    // was recognised
+    fn replay(mut detector: ShakeDetector, trace: &str) -> Option<u64> {
+        let start = Instant::now();
+        trace
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+            .map(|line| {
+                let fields: Vec<f64> = line.split(',').map(|f| f.trim().parse().unwrap()).collect();
+                (fields[0] as u64, fields[1], fields[2])
+            })
+            .find(|&(ms, x, y)| detector.update(x, y, start + Duration::from_millis(ms)))
+            .map(|(ms, _, _)| ms)
+    }
+
+    fn default_detector() -> ShakeDetector {
+        ShakeDetector::from_config(&crate::config::AppConfig::default().mouse_monitor)
+    }
+
+    #[test]
+    fn recognises_a_trackpad_shake_trace() {
+        let fired = replay(
+            default_detector(),
+            include_str!("traces/trackpad_shake.csv"),
+        );
+        assert!(fired.is_some_and(|ms| ms < 1200), "{:?}", fired);
+    }
+
+    #[test]
+    fn ignores_a_mouse_drag_with_a_pause() {
+        let trace = include_str!("traces/mouse_drag_pause.csv");
+        assert_eq!(replay(default_detector(), trace), None);
+
+        let mut any_axis = default_detector();
+        any_axis.configure(&MouseMonitorConfig {
+            shake_axis: ShakeAxis::Any,
+            ..crate::config::AppConfig::default().mouse_monitor
+        });
+        assert_eq!(replay(any_axis, trace), None);
+    }
+
+    #[test]
+    fn starts_over_after_a_reset() {
+        let mut detector = detector(ShakeAxis::Horizontal);
+        let shake = oscillation(150.0, 4.0);
+        let start = Instant::now();
+        for ms in (0..=400).step_by(8) {
+            let at = start + Duration::from_millis(ms);
+            assert!(!detector.update(shake(ms as f64 / 1000.0), 0.0, at));
+        }
+        detector.reset();
+        let fired = (408..=800).step_by(8).any(|ms| {
+            let at = start + Duration::from_millis(ms);
+            detector.update(shake(ms as f64 / 1000.0), 0.0, at)
+        });
+        assert!(!fired);
+    }
+}
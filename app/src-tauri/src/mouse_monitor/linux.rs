@@ -0,0 +1,577 @@
+// X11 backend. Pointer motion and button changes arrive as XInput2 raw events on the
+// root window, so the thread sleeps while the pointer is still; during a drag, and
+// without XInput 2.1, the pointer is polled instead. A drag has started when a drag
+// source takes the XdndSelection while the left button is down.
+
+use super::app_rules::{self, SourceApp};
+use super::triggers::{Trigger, Triggers};
+use crate::analytics;
+use crate::commands::config_ops::ParsedHotkey;
+use crate::config::{AppConfig, MouseMonitorConfig};
+use crate::DragState;
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_uchar, c_uint, c_ulong};
+use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, PhysicalPosition, State};
+use tracing::{info, warn};
+use x11::{xfixes, xinput2, xlib};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(30);
+
+// From Xfixes.h, which the x11 crate does not translate
+const XFIXES_SELECTION_NOTIFY: c_int = 0;
+const XFIXES_SET_SELECTION_OWNER_NOTIFY_MASK: c_ulong = 1;
+
+struct Pointer {
+    x: i32,
+    y: i32,
+    button_down: bool,
+    // Modifier and button state, as in X events
+    mask: c_uint,
+}
+
+// A connection of its own to the X server, used only by the monitor thread
+struct X11 {
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    xdnd_selection: xlib::Atom,
+    net_active_window: xlib::Atom,
+    net_wm_pid: xlib::Atom,
+    raw_events: bool,
+    // Event base of XFixes, when the server tells us about selection owner changes
+    xfixes_event_base: Option<c_int>,
+    selection_taken: bool,
+}
+
+impl X11 {
+    fn open() -> Option<Self> {
+        unsafe {
+            let display = xlib::XOpenDisplay(ptr::null());
+            if display.is_null() {
+                return None;
+            }
+            let mut x11 = X11 {
+                display,
+                root: xlib::XDefaultRootWindow(display),
+                xdnd_selection: intern(display, "XdndSelection"),
+                net_active_window: intern(display, "_NET_ACTIVE_WINDOW"),
+                net_wm_pid: intern(display, "_NET_WM_PID"),
+                raw_events: false,
+                xfixes_event_base: None,
+                selection_taken: false,
+            };
+            x11.raw_events = x11.select_raw_events();
+            x11.xfixes_event_base = x11.select_selection_events();
+            xlib::XFlush(display);
+            Some(x11)
+        }
+    }
+
+    // Raw events reach the root window even while a drag source grabs the pointer,
+    // but only from XInput 2.1 on
+    unsafe fn select_raw_events(&self) -> bool {
+        let name = CString::new("XInputExtension").unwrap();
+        let (mut opcode, mut event, mut error) = (0, 0, 0);
+        if xlib::XQueryExtension(
+            self.display,
+            name.as_ptr(),
+            &mut opcode,
+            &mut event,
+            &mut error,
+        ) == 0
+        {
+            return false;
+        }
+        let (mut major, mut minor) = (2, 1);
+        if xinput2::XIQueryVersion(self.display, &mut major, &mut minor) != xlib::Success as c_int
+            || (major, minor) < (2, 1)
+        {
+            return false;
+        }
+
+        let mut mask = [0 as c_uchar; 4];
+        for event in [
+            xinput2::XI_RawMotion,
+            xinput2::XI_RawButtonPress,
+            xinput2::XI_RawButtonRelease,
+        ] {
+            xinput2::XISetMask(&mut mask, event);
+        }
+        let mut event_mask = xinput2::XIEventMask {
+            deviceid: xinput2::XIAllMasterDevices,
+            mask_len: mask.len() as c_int,
+            mask: mask.as_mut_ptr(),
+        };
+        xinput2::XISelectEvents(self.display, self.root, &mut event_mask, 1)
+            == xlib::Success as c_int
+    }
+
+    unsafe fn select_selection_events(&self) -> Option<c_int> {
+        let (mut event_base, mut error_base) = (0, 0);
+        if xfixes::XFixesQueryExtension(self.display, &mut event_base, &mut error_base) == 0 {
+            return None;
+        }
+        xfixes::XFixesSelectSelectionInput(
+            self.display,
+            self.root,
+            self.xdnd_selection,
+            XFIXES_SET_SELECTION_OWNER_NOTIFY_MASK,
+        );
+        Some(event_base)
+    }
+
+    // Sleep until the pointer moves or a button changes, or for the poll interval when
+    // `poll` is set, then handle what is queued
+    fn wait(&mut self, poll: bool) {
+        unsafe {
+            if self.raw_events && !poll {
+                self.next_event();
+            } else {
+                thread::sleep(POLL_INTERVAL);
+            }
+            while xlib::XPending(self.display) > 0 {
+                self.next_event();
+            }
+        }
+    }
+
+    // Raw events only wake the thread up, the pointer is queried afterwards
+    unsafe fn next_event(&mut self) {
+        let mut event: xlib::XEvent = std::mem::zeroed();
+        xlib::XNextEvent(self.display, &mut event);
+        let Some(event_base) = self.xfixes_event_base else {
+            return;
+        };
+        if event.get_type() == event_base + XFIXES_SELECTION_NOTIFY {
+            let notify =
+                &*(&event as *const xlib::XEvent as *const xfixes::XFixesSelectionNotifyEvent);
+            if notify.owner != 0 {
+                self.selection_taken = true;
+            }
+        }
+    }
+
+    fn pointer(&self) -> Pointer {
+        let (mut root, mut child) = (0, 0);
+        let (mut x, mut y, mut window_x, mut window_y) = (0, 0, 0, 0);
+        let mut mask: c_uint = 0;
+        unsafe {
+            xlib::XQueryPointer(
+                self.display,
+                self.root,
+                &mut root,
+                &mut child,
+                &mut x,
+                &mut y,
+                &mut window_x,
+                &mut window_y,
+                &mut mask,
+            );
+        }
+        Pointer {
+            x,
+            y,
+            button_down: mask & xlib::Button1Mask != 0,
+            mask,
+        }
+    }
+
+    // Mod1 and Mod4 are Alt and Super under every common keyboard mapping
+    fn are_keys_down(&self, hotkey: &ParsedHotkey, mask: c_uint) -> bool {
+        let key_down = match hotkey.key.as_deref() {
+            Some(key) => self.is_key_down(key),
+            None => true,
+        };
+        key_down
+            && (!hotkey.ctrl || mask & xlib::ControlMask != 0)
+            && (!hotkey.shift || mask & xlib::ShiftMask != 0)
+            && (!hotkey.alt || mask & xlib::Mod1Mask != 0)
+            && (!hotkey.meta || mask & xlib::Mod4Mask != 0)
+    }
+
+    fn is_key_down(&self, key: &str) -> bool {
+        let Ok(name) = CString::new(keysym_name(key)) else {
+            return false;
+        };
+        let mut keys = [0 as c_char; 32];
+        unsafe {
+            let keysym = xlib::XStringToKeysym(name.as_ptr());
+            if keysym == 0 {
+                return false;
+            }
+            let keycode = xlib::XKeysymToKeycode(self.display, keysym) as usize;
+            if keycode == 0 {
+                return false;
+            }
+            xlib::XQueryKeymap(self.display, keys.as_mut_ptr());
+            keys[keycode / 8] as u8 & (1 << (keycode % 8)) != 0
+        }
+    }
+
+    // Whether a drag source took the XdndSelection since the last call. Without XFixes
+    // this can only tell that some window owns it.
+    fn take_drag_offer(&mut self) -> bool {
+        if self.xfixes_event_base.is_some() {
+            return std::mem::take(&mut self.selection_taken);
+        }
+        unsafe { xlib::XGetSelectionOwner(self.display, self.xdnd_selection) != 0 }
+    }
+
+    // Whether `pointer` shows a drag that started since the last call. This has to be
+    // called on every wake-up, so an offer made before the button went down is dropped.
+    fn drag_started(&mut self, pointer: &Pointer) -> bool {
+        let drag_offered = self.take_drag_offer();
+        pointer.button_down && drag_offered
+    }
+
+    fn active_source_app(&self) -> SourceApp {
+        let window = self.cardinal_property(self.root, self.net_active_window, xlib::XA_WINDOW);
+        let pid = window
+            .and_then(|window| self.cardinal_property(window, self.net_wm_pid, xlib::XA_CARDINAL));
+        pid.map_or_else(SourceApp::default, |pid| {
+            SourceApp::from_process(pid as u32)
+        })
+    }
+
+    // The first value of a 32-bit window property
+    fn cardinal_property(
+        &self,
+        window: xlib::Window,
+        property: xlib::Atom,
+        kind: xlib::Atom,
+    ) -> Option<c_ulong> {
+        let mut actual_type = 0;
+        let mut actual_format = 0;
+        let (mut items, mut bytes_after) = (0, 0);
+        let mut data: *mut c_uchar = ptr::null_mut();
+        unsafe {
+            let status = xlib::XGetWindowProperty(
+                self.display,
+                window,
+                property,
+                0,
+                1,
+                xlib::False,
+                kind,
+                &mut actual_type,
+                &mut actual_format,
+                &mut items,
+                &mut bytes_after,
+                &mut data,
+            );
+            if status != xlib::Success as c_int || data.is_null() {
+                return None;
+            }
+            // Xlib hands out 32-bit properties as longs
+            let value = (actual_format == 32 && items > 0).then(|| *(data as *const c_ulong));
+            xlib::XFree(data as *mut _);
+            value.filter(|value| *value != 0)
+        }
+    }
+}
+
+impl Drop for X11 {
+    fn drop(&mut self) {
+        unsafe {
+            xlib::XCloseDisplay(self.display);
+        }
+    }
+}
+
+// Keysym name of a hotkey key, which is upper-cased like "A", "SPACE" or "F5"
+fn keysym_name(key: &str) -> String {
+    match key {
+        "SPACE" => "space".to_string(),
+        "RETURN" | "ENTER" => "Return".to_string(),
+        "TAB" => "Tab".to_string(),
+        "ESCAPE" | "ESC" => "Escape".to_string(),
+        "DELETE" => "Delete".to_string(),
+        "UP" | "ARROWUP" => "Up".to_string(),
+        "DOWN" | "ARROWDOWN" => "Down".to_string(),
+        "LEFT" | "ARROWLEFT" => "Left".to_string(),
+        "RIGHT" | "ARROWRIGHT" => "Right".to_string(),
+        _ if key.len() == 1 => key.to_lowercase(),
+        _ => key.to_string(),
+    }
+}
+
+unsafe fn intern(display: *mut xlib::Display, name: &str) -> xlib::Atom {
+    let name = CString::new(name).unwrap();
+    xlib::XInternAtom(display, name.as_ptr(), xlib::False)
+}
+
+fn hide_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+    }
+}
+
+// The drop is reported by GTK after the button is released, so give it time to arrive
+fn hide_main_window_after_delay(
+    app_handle: AppHandle,
+    drag_state: Arc<DragState>,
+    drag_started_at: Instant,
+    delay_ms: u64,
+) {
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(delay_ms));
+        if !drag_state.dropped_since(drag_started_at) {
+            hide_main_window(&app_handle);
+        }
+    });
+}
+
+fn show_main_window(app: &AppHandle, pointer: &Pointer) {
+    if let Some(window) = app.get_webview_window("main") {
+        let (mut x, mut y) = (pointer.x, pointer.y);
+
+        // Keep the window on the monitor under the cursor
+        if let (Ok(Some(monitor)), Ok(size)) = (
+            window.monitor_from_point(x as f64, y as f64),
+            window.outer_size(),
+        ) {
+            let origin = monitor.position();
+            let area = monitor.size();
+            x = x
+                .min(origin.x + area.width as i32 - size.width as i32)
+                .max(origin.x);
+            y = y
+                .min(origin.y + area.height as i32 - size.height as i32)
+                .max(origin.y);
+        }
+
+        let _ = window.set_position(PhysicalPosition { x, y });
+        let _ = window.show();
+        let _ = window.unminimize();
+        info!("Opened main window at position ({}, {})", x, y);
+    }
+}
+
+pub fn start_mouse_monitor(
+    config: MouseMonitorConfig,
+    app_handle: AppHandle,
+    drag_state: Arc<DragState>,
+) {
+    info!(
+        "Starting X11 mouse monitor (min_stroke_px={}, required_shakes={}, time_limit_ms={})",
+        config.shake_threshold, config.required_shakes, config.shake_time_limit
+    );
+
+    thread::spawn(move || {
+        let Some(mut x11) = X11::open() else {
+            warn!("Could not open the X display, mouse monitor exiting");
+            return;
+        };
+        if !x11.raw_events {
+            info!("XInput 2.1 is not available, polling the pointer");
+        }
+        if x11.xfixes_event_base.is_none() {
+            info!("XFixes is not available, drags are detected by the XdndSelection owner");
+        }
+
+        let mut window_opened_by_trigger = false;
+        let mut triggers = Triggers::from_config(&config);
+        triggers.configure(&app_handle, &config);
+        let mut is_drag_active = false;
+        let mut drag_started_at = Instant::now();
+        // The settings for the drag in progress, resolved from the app rules when it
+        // started; `None` if they ignore the app it came from
+        let mut drag_config: Option<MouseMonitorConfig> = None;
+
+        loop {
+            // Edge dwell has to see the time pass while the pointer rests
+            x11.wait(is_drag_active);
+
+            let config = {
+                let state: State<Arc<Mutex<AppConfig>>> = app_handle.state();
+                let lock = state.lock().unwrap();
+                lock.mouse_monitor.clone()
+            };
+            triggers.configure(&app_handle, &config);
+
+            let pointer = x11.pointer();
+            let drag_started = x11.drag_started(&pointer);
+
+            // --- Detect drag start ---
+            if !is_drag_active && drag_started {
+                is_drag_active = true;
+                drag_started_at = Instant::now();
+                triggers.reset(&app_handle);
+                drag_config = app_rules::decide(&config, &x11.active_source_app());
+            }
+
+            // --- Detect drag end (mouse released) ---
+            if is_drag_active && !pointer.button_down {
+                // If the files weren't dropped in our window, close it again
+                if window_opened_by_trigger {
+                    hide_main_window_after_delay(
+                        app_handle.clone(),
+                        Arc::clone(&drag_state),
+                        drag_started_at,
+                        config.window_close_delay,
+                    );
+                }
+
+                is_drag_active = false;
+                triggers.reset(&app_handle);
+                window_opened_by_trigger = false;
+            }
+
+            // --- Shake, edge, drop strip and key detection while dragging ---
+            if !is_drag_active || window_opened_by_trigger {
+                continue;
+            }
+            let Some(config) = &drag_config else {
+                continue;
+            };
+            triggers.configure(&app_handle, config);
+            let Some(trigger) = triggers.update(
+                &app_handle,
+                pointer.x as f64,
+                pointer.y as f64,
+                |hotkey| x11.are_keys_down(hotkey, pointer.mask),
+                Instant::now(),
+            ) else {
+                continue;
+            };
+
+            let app_clone = app_handle.clone();
+            let shake_count = config.required_shakes;
+            tauri::async_runtime::spawn(async move {
+                if trigger == Trigger::Shake {
+                    let _ =
+                        analytics::send_mouse_shake_detected_event(&app_clone, shake_count).await;
+                }
+                let _ =
+                    analytics::send_window_opened_event(&app_clone, trigger.window_type()).await;
+            });
+
+            show_main_window(&app_handle, &pointer);
+            window_opened_by_trigger = true;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use x11::xtest;
+
+    #[test]
+    fn maps_hotkey_keys_to_keysym_names() {
+        assert_eq!(keysym_name("A"), "a");
+        assert_eq!(keysym_name("7"), "7");
+        assert_eq!(keysym_name("SPACE"), "space");
+        assert_eq!(keysym_name("RETURN"), "Return");
+        assert_eq!(keysym_name("ENTER"), "Return");
+        assert_eq!(keysym_name("TAB"), "Tab");
+        assert_eq!(keysym_name("ESC"), "Escape");
+        assert_eq!(keysym_name("DELETE"), "Delete");
+        assert_eq!(keysym_name("UP"), "Up");
+        assert_eq!(keysym_name("ARROWDOWN"), "Down");
+        assert_eq!(keysym_name("LEFT"), "Left");
+        assert_eq!(keysym_name("ARROWRIGHT"), "Right");
+        assert_eq!(keysym_name("F5"), "F5");
+    }
+
+    // A client of its own that plays the drag source, pressing the button with XTest
+    struct DragSource {
+        display: *mut xlib::Display,
+        window: xlib::Window,
+        xdnd_selection: xlib::Atom,
+    }
+
+    impl DragSource {
+        fn open() -> Self {
+            unsafe {
+                let display = xlib::XOpenDisplay(ptr::null());
+                assert!(!display.is_null(), "no X server, run under xvfb-run");
+                let root = xlib::XDefaultRootWindow(display);
+                let window = xlib::XCreateSimpleWindow(display, root, 0, 0, 10, 10, 0, 0, 0);
+                DragSource {
+                    display,
+                    window,
+                    xdnd_selection: intern(display, "XdndSelection"),
+                }
+            }
+        }
+
+        fn button(&self, down: bool) {
+            unsafe {
+                xtest::XTestFakeButtonEvent(self.display, 1, down as c_int, 0);
+                xlib::XSync(self.display, xlib::False);
+            }
+        }
+
+        fn offer_drag(&self) {
+            unsafe {
+                xlib::XSetSelectionOwner(
+                    self.display,
+                    self.xdnd_selection,
+                    self.window,
+                    xlib::CurrentTime,
+                );
+                xlib::XSync(self.display, xlib::False);
+            }
+        }
+    }
+
+    impl Drop for DragSource {
+        fn drop(&mut self) {
+            self.button(false);
+            unsafe {
+                xlib::XDestroyWindow(self.display, self.window);
+                xlib::XCloseDisplay(self.display);
+            }
+        }
+    }
+
+    // Let the monitor see what the source did
+    fn poll(x11: &mut X11) -> bool {
+        x11.wait(true);
+        let pointer = x11.pointer();
+        x11.drag_started(&pointer)
+    }
+
+    // Needs an X server with XTest, e.g. `xvfb-run cargo test -- --ignored drag_start`.
+    // One test, as the pointer is shared by everything on the server.
+    #[test]
+    #[ignore]
+    fn detects_drag_start_from_xdnd_selection_and_button() {
+        let mut x11 = X11::open().expect("no X server, run under xvfb-run");
+        let source = DragSource::open();
+        // Without XFixes only the current owner is known, not when it took over
+        let xfixes = x11.xfixes_event_base.is_some();
+        assert!(!poll(&mut x11));
+
+        if xfixes {
+            // An offer while the button is up is not a drag, even once it goes down
+            source.offer_drag();
+            assert!(!poll(&mut x11));
+            source.button(true);
+            assert!(!poll(&mut x11));
+            source.button(false);
+        }
+
+        // A press alone is a click
+        source.button(true);
+        assert!(x11.pointer().button_down);
+        if xfixes {
+            assert!(!poll(&mut x11));
+        }
+
+        source.offer_drag();
+        assert!(poll(&mut x11));
+        if xfixes {
+            // Reported once per offer
+            assert!(!poll(&mut x11));
+        }
+
+        source.button(false);
+        assert!(!x11.pointer().button_down);
+        assert!(!poll(&mut x11));
+    }
+}
@@ -1,23 +1,29 @@
 use crate::analytics;
+use crate::commands::config_ops::{parse_keycode, ParsedHotkey};
 use crate::config::{AppConfig, MouseMonitorConfig};
+use crate::mouse_monitor::app_rules::{self, SourceApp};
 use crate::mouse_monitor::common::DRAG_PASTEBOARD_NAME;
+use crate::mouse_monitor::triggers::{Trigger, Triggers};
 use crate::DragState;
-use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Manager, PhysicalPosition, State};
 
 use objc2::rc::Retained;
-use objc2_app_kit::NSPasteboard;
+use objc2_app_kit::{NSPasteboard, NSWorkspace};
 use objc2_foundation::{NSArray, NSString};
 use tracing::{info, warn};
 
 #[link(name = "CoreGraphics", kind = "framework")]
 extern "C" {
     fn CGEventSourceButtonState(stateID: u32, button: u32) -> bool;
+    fn CGEventSourceKeyState(stateID: u32, key: u16) -> bool;
+    fn CGEventSourceFlagsState(stateID: u32) -> u64;
 }
 
+const K_CG_EVENT_SOURCE_STATE_HID_SYSTEM_STATE: u32 = 1;
+
 fn get_cursor_position(app_handle: &AppHandle) -> (f64, f64) {
     if let Some(window) = app_handle.get_webview_window("main") {
         if let Ok(pos) = window.cursor_position() {
@@ -28,7 +34,6 @@ fn get_cursor_position(app_handle: &AppHandle) -> (f64, f64) {
 }
 
 fn is_mouse_button_down() -> bool {
-    const K_CG_EVENT_SOURCE_STATE_HID_SYSTEM_STATE: u32 = 1;
     const K_CG_MOUSE_BUTTON_LEFT: u32 = 0;
     unsafe {
         CGEventSourceButtonState(
@@ -38,6 +43,44 @@ fn is_mouse_button_down() -> bool {
     }
 }
 
+fn are_keys_down(hotkey: &ParsedHotkey) -> bool {
+    const K_CG_EVENT_FLAG_MASK_SHIFT: u64 = 0x0002_0000;
+    const K_CG_EVENT_FLAG_MASK_CONTROL: u64 = 0x0004_0000;
+    const K_CG_EVENT_FLAG_MASK_ALTERNATE: u64 = 0x0008_0000;
+    const K_CG_EVENT_FLAG_MASK_COMMAND: u64 = 0x0010_0000;
+
+    let flags = unsafe { CGEventSourceFlagsState(K_CG_EVENT_SOURCE_STATE_HID_SYSTEM_STATE) };
+    let key_down = match hotkey.key.as_deref() {
+        Some(key) => parse_keycode(key).is_some_and(|keycode| unsafe {
+            CGEventSourceKeyState(K_CG_EVENT_SOURCE_STATE_HID_SYSTEM_STATE, keycode as u16)
+        }),
+        None => true,
+    };
+    key_down
+        && (!hotkey.ctrl || flags & K_CG_EVENT_FLAG_MASK_CONTROL != 0)
+        && (!hotkey.shift || flags & K_CG_EVENT_FLAG_MASK_SHIFT != 0)
+        && (!hotkey.alt || flags & K_CG_EVENT_FLAG_MASK_ALTERNATE != 0)
+        && (!hotkey.meta || flags & K_CG_EVENT_FLAG_MASK_COMMAND != 0)
+}
+
+// Drags start in the frontmost app
+fn frontmost_app() -> SourceApp {
+    let Some(app) = NSWorkspace::sharedWorkspace().frontmostApplication() else {
+        return SourceApp::default();
+    };
+    let path = app
+        .executableURL()
+        .and_then(|url| url.path())
+        .map(|path| path.to_string());
+    let name = path
+        .as_deref()
+        .and_then(|path| std::path::Path::new(path).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .or_else(|| app.localizedName().map(|name| name.to_string()))
+        .unwrap_or_default();
+    SourceApp { name, path }
+}
+
 fn get_drag_pasteboard() -> Option<Retained<NSPasteboard>> {
     let name = NSString::from_str(DRAG_PASTEBOARD_NAME);
     Some(NSPasteboard::pasteboardWithName(&name))
@@ -105,17 +148,14 @@ pub fn start_mouse_monitor(
     drag_state: Arc<DragState>,
 ) {
     info!(
-        "Starting macOS mouse monitor (threshold={}, required_shakes={}, time_limit_ms={})",
+        "Starting macOS mouse monitor (min_stroke_px={}, required_shakes={}, time_limit_ms={})",
         config.shake_threshold, config.required_shakes, config.shake_time_limit
     );
 
     thread::spawn(move || {
-        let mut window_opened_by_shake = false;
-        let mut last_position = get_cursor_position(&app_handle);
+        let mut window_opened_by_trigger = false;
         let check_interval = Duration::from_millis(50);
-        let mut shake_count = 0u32;
-        let mut last_shake_time = Instant::now();
-        let mut last_direction: Option<i32> = None;
+        let mut triggers = Triggers::from_config(&config);
 
         let pasteboard = match get_drag_pasteboard() {
             Some(pb) => pb,
@@ -127,6 +167,10 @@ pub fn start_mouse_monitor(
 
         let mut last_change_count = get_pasteboard_change_count(&pasteboard);
         let mut is_drag_active = false;
+        let mut drag_started_at = Instant::now();
+        // The settings for the drag in progress, resolved from the app rules when it
+        // started; `None` if they ignore the app it came from
+        let mut drag_config: Option<MouseMonitorConfig> = None;
 
         loop {
             let config = {
@@ -134,8 +178,7 @@ pub fn start_mouse_monitor(
                 let lock = state.lock().unwrap();
                 lock.mouse_monitor.clone()
             };
-            let shake_threshold_x = config.shake_threshold as f64;
-            let movement_time_limit = Duration::from_millis(config.shake_time_limit);
+            triggers.configure(&app_handle, &config);
 
             let current_position = get_cursor_position(&app_handle);
             let current_change_count = get_pasteboard_change_count(&pasteboard);
@@ -148,9 +191,10 @@ pub fn start_mouse_monitor(
             // --- Detect drag start ---
             if !is_drag_active && change_count_changed && has_files {
                 is_drag_active = true;
-                drag_state.successful_drop.store(false, Ordering::Relaxed);
+                drag_started_at = Instant::now();
                 last_change_count = current_change_count;
-                last_shake_time = Instant::now();
+                triggers.reset(&app_handle);
+                drag_config = app_rules::decide(&config, &frontmost_app());
             }
 
             // --- Detect drag end (mouse released) ---
@@ -158,66 +202,47 @@ pub fn start_mouse_monitor(
             let drag_ended = is_drag_active && !mouse_down;
 
             if drag_ended {
-                let successful_drop = drag_state.successful_drop.load(Ordering::Relaxed);
-
-                // Reset drag state flags for next drag
-                drag_state.drag_started.store(false, Ordering::Relaxed);
+                let successful_drop = drag_state.dropped_since(drag_started_at);
 
                 // If drag ended but files weren't dropped in our window, close the window
-                if window_opened_by_shake && !successful_drop {
+                if window_opened_by_trigger && !successful_drop {
                     hide_main_window(app_handle.app_handle());
                 }
 
                 // Reset state
                 is_drag_active = false;
-                shake_count = 0;
-                last_direction = None;
+                triggers.reset(&app_handle);
                 last_change_count = current_change_count;
-                window_opened_by_shake = false;
+                window_opened_by_trigger = false;
             }
 
-            // --- Shake detection while dragging ---
-            if is_drag_active {
-                let distance_x = current_position.0 - last_position.0;
-
-                let direction = if distance_x > shake_threshold_x {
-                    1
-                } else if distance_x < -shake_threshold_x {
-                    -1
-                } else {
-                    0
-                };
-
-                if direction != 0 {
-                    if let Some(last_dir) = last_direction {
-                        if last_dir != direction {
-                            last_shake_time = Instant::now();
-                            shake_count += 1;
-                        }
+            // --- Shake, edge, drop strip and key detection while dragging ---
+            if let Some(config) = drag_config.as_ref().filter(|_| is_drag_active) {
+                triggers.configure(&app_handle, config);
+                let trigger = triggers.update(
+                    &app_handle,
+                    current_position.0,
+                    current_position.1,
+                    are_keys_down,
+                    Instant::now(),
+                );
+
+                // Trigger window open
+                if let Some(trigger) = trigger.filter(|_| !window_opened_by_trigger) {
+                    if trigger == Trigger::Shake {
+                        let app_clone = app_handle.clone();
+                        let shake_count_clone = config.required_shakes;
+                        tauri::async_runtime::spawn(async move {
+                            let _ = analytics::send_mouse_shake_detected_event(
+                                &app_clone,
+                                shake_count_clone,
+                            )
+                            .await;
+                        });
                     }
-                    last_direction = Some(direction);
-                }
-
-                // Reset shake if too much time passes between wiggles
-                if last_shake_time.elapsed() > movement_time_limit {
-                    shake_count = 0;
-                    last_direction = None;
-                }
 
-                // Trigger window open on shake
-                if shake_count >= config.required_shakes && !window_opened_by_shake {
-                    let app_clone = app_handle.clone();
-                    let shake_count_clone = shake_count;
-                    tauri::async_runtime::spawn(async move {
-                        let _ = analytics::send_mouse_shake_detected_event(
-                            &app_clone,
-                            shake_count_clone,
-                        )
-                        .await;
-                    });
-
-                    show_main_window(&app_handle, current_position, &config);
-                    window_opened_by_shake = true;
+                    show_main_window(&app_handle, current_position, config);
+                    window_opened_by_trigger = true;
 
                     // Spawn timeout thread to auto-hide if no drop (and mouse is released)
                     let app_handle_clone = app_handle.clone();
@@ -225,8 +250,7 @@ pub fn start_mouse_monitor(
                     let delay_ms = config.window_close_delay;
                     thread::spawn(move || {
                         thread::sleep(Duration::from_millis(delay_ms));
-                        let successful_drop =
-                            drag_state_clone.successful_drop.load(Ordering::Relaxed);
+                        let successful_drop = drag_state_clone.dropped_since(drag_started_at);
                         let mouse_down = is_mouse_button_down();
                         if !successful_drop && !mouse_down {
                             hide_main_window(app_handle_clone.app_handle());
@@ -236,15 +260,12 @@ pub fn start_mouse_monitor(
                     let app_clone2 = app_handle.clone();
                     tauri::async_runtime::spawn(async move {
                         let _ =
-                            analytics::send_window_opened_event(&app_clone2, "main_shake").await;
+                            analytics::send_window_opened_event(&app_clone2, trigger.window_type())
+                                .await;
                     });
-
-                    shake_count = 0;
-                    last_direction = None;
                 }
             }
 
-            last_position = current_position;
             thread::sleep(check_interval);
         }
     });
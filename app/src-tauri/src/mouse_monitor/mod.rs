@@ -1,4 +1,6 @@
+mod app_rules;
 mod common;
+mod triggers;
 
 #[cfg(target_os = "windows")]
 mod win;
@@ -6,6 +8,9 @@ mod win;
 #[cfg(target_os = "macos")]
 mod mac;
 
+#[cfg(target_os = "linux")]
+mod linux;
+
 use crate::config::MouseMonitorConfig;
 use crate::DragState;
 use std::sync::Arc;
@@ -25,4 +30,9 @@ pub fn start_mouse_monitor(
     {
         mac::start_mouse_monitor(config, app_handle, drag_state);
     }
+
+    #[cfg(target_os = "linux")]
+    {
+        linux::start_mouse_monitor(config, app_handle, drag_state);
+    }
 }
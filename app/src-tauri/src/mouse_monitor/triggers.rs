@@ -0,0 +1,623 @@
+// Everything that opens the shelf during a drag: shaking, resting at a screen edge or
+// corner, entering the drop strip and holding keys. The backends feed cursor positions
+// in physical screen coordinates while a drag is in progress, and tell which keys are
+// down.
+
+use super::common::ShakeDetector;
+use crate::commands::config_ops::ParsedHotkey;
+use crate::config::{
+    DropStripConfig, EdgeDwellConfig, KeyTriggerConfig, MouseMonitorConfig, ScreenEdge, ScreenSide,
+};
+use std::time::{Duration, Instant};
+use tauri::{
+    AppHandle, Manager, Monitor, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindowBuilder,
+};
+use tracing::{error, info};
+
+const DROP_STRIP_LABEL: &str = "drop_strip";
+// How far the cursor has to move with the button down before the drop strip shows, so
+// clicks don't flash it
+const DROP_STRIP_DRAG_DISTANCE: f64 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Shake,
+    EdgeDwell,
+    DropStrip,
+    Keys,
+}
+
+impl Trigger {
+    /// Window type reported to analytics when this trigger opens the shelf.
+    pub fn window_type(&self) -> &'static str {
+        match self {
+            Trigger::Shake => "main_shake",
+            Trigger::EdgeDwell => "main_edge_dwell",
+            Trigger::DropStrip => "main_drop_strip",
+            Trigger::Keys => "main_keys",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Rect {
+    fn from_monitor(monitor: &Monitor) -> Self {
+        Rect {
+            x: monitor.position().x as f64,
+            y: monitor.position().y as f64,
+            width: monitor.size().width as f64,
+            height: monitor.size().height as f64,
+        }
+    }
+
+    fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+/// Watches a drag for the triggers enabled in the configuration.
+pub struct Triggers {
+    shake: ShakeDetector,
+    dwell: EdgeDwell,
+    strip: DropStrip,
+    keys: KeyTrigger,
+    // Where the drag in progress was first seen
+    drag_origin: Option<(f64, f64)>,
+}
+
+impl Triggers {
+    pub fn from_config(config: &MouseMonitorConfig) -> Self {
+        Triggers {
+            shake: ShakeDetector::from_config(config),
+            dwell: EdgeDwell::new(config.edge_dwell.clone()),
+            strip: DropStrip::default(),
+            keys: KeyTrigger::new(config.key_trigger.clone()),
+            drag_origin: None,
+        }
+    }
+
+    /// Take the thresholds from `config`, keeping the gesture in progress, and place
+    /// or remove the drop strip to match it.
+    pub fn configure(&mut self, app: &AppHandle, config: &MouseMonitorConfig) {
+        self.shake.configure(config);
+        self.dwell.config = config.edge_dwell.clone();
+        self.strip.sync(app, &config.drop_strip);
+        self.keys.configure(&config.key_trigger);
+    }
+
+    /// Forget the drag in progress, and hide the drop strip.
+    pub fn reset(&mut self, app: &AppHandle) {
+        self.shake.reset();
+        self.dwell.reset();
+        self.keys.reset();
+        self.strip.hide(app);
+        self.drag_origin = None;
+    }
+
+    /// Feed the cursor position at `at`, and whether the keys of a hotkey are all held
+    /// down. Returns the trigger that fired, if any. The drop strip shows once the
+    /// cursor has moved a little, and stays until the next [`Triggers::reset`].
+    pub fn update(
+        &mut self,
+        app: &AppHandle,
+        x: f64,
+        y: f64,
+        keys_down: impl Fn(&ParsedHotkey) -> bool,
+        at: Instant,
+    ) -> Option<Trigger> {
+        let (origin_x, origin_y) = *self.drag_origin.get_or_insert((x, y));
+        if (x - origin_x).hypot(y - origin_y) >= DROP_STRIP_DRAG_DISTANCE {
+            self.strip.show(app);
+        }
+
+        if self.keys.update(keys_down, at) {
+            return Some(Trigger::Keys);
+        }
+        if self.shake.update(x, y, at) {
+            return Some(Trigger::Shake);
+        }
+        if self.strip.contains(x, y) {
+            return Some(Trigger::DropStrip);
+        }
+        if self.dwell.update(app, x, y, at) {
+            return Some(Trigger::EdgeDwell);
+        }
+        None
+    }
+}
+
+// Fires once the cursor has stayed at one of the configured edges for the dwell time
+struct EdgeDwell {
+    config: EdgeDwellConfig,
+    screen: Option<Rect>,
+    current: Option<(ScreenEdge, Instant)>,
+    fired: bool,
+}
+
+impl EdgeDwell {
+    fn new(config: EdgeDwellConfig) -> Self {
+        EdgeDwell {
+            config,
+            screen: None,
+            current: None,
+            fired: false,
+        }
+    }
+
+    fn reset(&mut self) {
+        self.current = None;
+        self.fired = false;
+    }
+
+    fn update(&mut self, app: &AppHandle, x: f64, y: f64, at: Instant) -> bool {
+        if !self.config.enabled || self.config.edges.is_empty() {
+            return false;
+        }
+        // Looking monitors up goes through the event loop, so only do it when the cursor
+        // leaves the screen it was on
+        if !self.screen.is_some_and(|screen| screen.contains(x, y)) {
+            self.screen = app
+                .monitor_from_point(x, y)
+                .ok()
+                .flatten()
+                .map(|monitor| Rect::from_monitor(&monitor));
+        }
+
+        let edge = self.screen.and_then(|screen| self.edge_at(&screen, x, y));
+        match (edge, self.current) {
+            (Some(edge), Some((current, since))) if edge == current => {
+                let dwell = Duration::from_millis(self.config.dwell_ms);
+                if !self.fired && at.saturating_duration_since(since) >= dwell {
+                    self.fired = true;
+                    return true;
+                }
+            }
+            (Some(edge), _) => {
+                self.current = Some((edge, at));
+                self.fired = false;
+            }
+            (None, _) => self.reset(),
+        }
+        false
+    }
+
+    // The configured edge the cursor is at. Corners win over the sides they join.
+    fn edge_at(&self, screen: &Rect, x: f64, y: f64) -> Option<ScreenEdge> {
+        let left = x - screen.x;
+        let right = screen.x + screen.width - 1.0 - x;
+        let top = y - screen.y;
+        let bottom = screen.y + screen.height - 1.0 - y;
+
+        let corner = self.config.corner_size.max(1) as f64;
+        let corner_edge = match (top < corner, bottom < corner, left < corner, right < corner) {
+            (true, _, true, _) => Some(ScreenEdge::TopLeft),
+            (true, _, _, true) => Some(ScreenEdge::TopRight),
+            (_, true, true, _) => Some(ScreenEdge::BottomLeft),
+            (_, true, _, true) => Some(ScreenEdge::BottomRight),
+            _ => None,
+        };
+        let side = self.config.edge_size.max(1) as f64;
+        let side_edge = [
+            (top, ScreenEdge::Top),
+            (bottom, ScreenEdge::Bottom),
+            (left, ScreenEdge::Left),
+            (right, ScreenEdge::Right),
+        ]
+        .into_iter()
+        .find(|(distance, _)| *distance < side)
+        .map(|(_, edge)| edge);
+
+        [corner_edge, side_edge]
+            .into_iter()
+            .flatten()
+            .find(|edge| self.config.edges.contains(edge))
+    }
+}
+
+// Fires when the configured keys go down, or go down for the second time in a row
+struct KeyTrigger {
+    config: KeyTriggerConfig,
+    hotkey: ParsedHotkey,
+    held: bool,
+    last_press: Option<Instant>,
+}
+
+impl KeyTrigger {
+    fn new(config: KeyTriggerConfig) -> Self {
+        KeyTrigger {
+            hotkey: ParsedHotkey::parse(&config.keys),
+            config,
+            held: false,
+            last_press: None,
+        }
+    }
+
+    fn configure(&mut self, config: &KeyTriggerConfig) {
+        if self.config != *config {
+            *self = KeyTrigger::new(config.clone());
+        }
+    }
+
+    // Keys already held when a drag starts count as pressed
+    fn reset(&mut self) {
+        self.held = false;
+        self.last_press = None;
+    }
+
+    fn update(&mut self, keys_down: impl Fn(&ParsedHotkey) -> bool, at: Instant) -> bool {
+        if !self.config.enabled || self.hotkey.is_empty() {
+            return false;
+        }
+        let held = keys_down(&self.hotkey);
+        let pressed = held && !self.held;
+        self.held = held;
+        if !pressed {
+            return false;
+        }
+        if !self.config.double_tap {
+            return true;
+        }
+
+        let window = Duration::from_millis(self.config.double_tap_ms);
+        match self.last_press.take() {
+            Some(last) if at.saturating_duration_since(last) <= window => true,
+            _ => {
+                self.last_press = Some(at);
+                false
+            }
+        }
+    }
+}
+
+// The drop strip window, where it was put, and whether it is showing. The window is
+// created hidden when the strip is enabled, and only shown during drags.
+#[derive(Default)]
+struct DropStrip {
+    applied: Option<DropStripConfig>,
+    rect: Option<Rect>,
+    shown: bool,
+}
+
+impl DropStrip {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        self.shown && self.rect.is_some_and(|rect| rect.contains(x, y))
+    }
+
+    fn sync(&mut self, app: &AppHandle, config: &DropStripConfig) {
+        if self.applied.as_ref() == Some(config) {
+            return;
+        }
+        self.applied = Some(config.clone());
+        self.rect = None;
+        self.hide(app);
+
+        if !config.enabled {
+            return;
+        }
+        match place_drop_strip(app, config) {
+            Ok(rect) => {
+                info!(
+                    "Placed drop strip at ({}, {}), {}x{}",
+                    rect.x, rect.y, rect.width, rect.height
+                );
+                self.rect = Some(rect);
+            }
+            Err(e) => error!("Failed to place drop strip: {}", e),
+        }
+    }
+
+    fn show(&mut self, app: &AppHandle) {
+        if self.shown || self.rect.is_none() {
+            return;
+        }
+        let Some(window) = app.get_webview_window(DROP_STRIP_LABEL) else {
+            return;
+        };
+        match window.show() {
+            Ok(()) => self.shown = true,
+            Err(e) => error!("Failed to show drop strip: {}", e),
+        }
+    }
+
+    fn hide(&mut self, app: &AppHandle) {
+        if !std::mem::take(&mut self.shown) {
+            return;
+        }
+        if let Some(window) = app.get_webview_window(DROP_STRIP_LABEL) {
+            if let Err(e) = window.hide() {
+                error!("Failed to hide drop strip: {}", e);
+            }
+        }
+    }
+}
+
+// Create the strip window if needed and move it into place, without showing it
+fn place_drop_strip(app: &AppHandle, config: &DropStripConfig) -> Result<Rect, String> {
+    let monitor = app
+        .primary_monitor()
+        .map_err(|e| e.to_string())?
+        .ok_or("No primary monitor")?;
+    let rect = strip_rect(&Rect::from_monitor(&monitor), config);
+
+    let window = match app.get_webview_window(DROP_STRIP_LABEL) {
+        Some(window) => window,
+        None => {
+            WebviewWindowBuilder::new(app, DROP_STRIP_LABEL, WebviewUrl::App("/drop-strip".into()))
+                .title("Drop Strip")
+                .decorations(false)
+                .shadow(false)
+                .resizable(false)
+                .always_on_top(true)
+                .skip_taskbar(true)
+                .focused(false)
+                .visible(false)
+                .visible_on_all_workspaces(true)
+                .build()
+                .map_err(|e| e.to_string())?
+        }
+    };
+    window
+        .set_size(PhysicalSize::new(rect.width as u32, rect.height as u32))
+        .map_err(|e| e.to_string())?;
+    window
+        .set_position(PhysicalPosition::new(rect.x as i32, rect.y as i32))
+        .map_err(|e| e.to_string())?;
+    Ok(rect)
+}
+
+// Centred on the configured side of `screen`; a length of 0 spans the whole side
+fn strip_rect(screen: &Rect, config: &DropStripConfig) -> Rect {
+    let thickness = config.thickness.max(1) as f64;
+    let along = |extent: f64| match config.length {
+        0 => extent,
+        length => (length as f64).min(extent),
+    };
+    match config.side {
+        ScreenSide::Top | ScreenSide::Bottom => {
+            let width = along(screen.width);
+            let y = match config.side {
+                ScreenSide::Top => screen.y,
+                _ => screen.y + screen.height - thickness,
+            };
+            Rect {
+                x: screen.x + (screen.width - width) / 2.0,
+                y,
+                width,
+                height: thickness,
+            }
+        }
+        ScreenSide::Left | ScreenSide::Right => {
+            let height = along(screen.height);
+            let x = match config.side {
+                ScreenSide::Left => screen.x,
+                _ => screen.x + screen.width - thickness,
+            };
+            Rect {
+                x,
+                y: screen.y + (screen.height - height) / 2.0,
+                width: thickness,
+                height,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 1920x1080 screen right of the primary one
+    const SCREEN: Rect = Rect {
+        x: 1920.0,
+        y: 0.0,
+        width: 1920.0,
+        height: 1080.0,
+    };
+
+    fn dwell(edges: &[ScreenEdge]) -> EdgeDwell {
+        EdgeDwell::new(EdgeDwellConfig {
+            enabled: true,
+            edges: edges.to_vec(),
+            ..Default::default()
+        })
+    }
+
+    const ALL_EDGES: [ScreenEdge; 8] = [
+        ScreenEdge::Top,
+        ScreenEdge::Bottom,
+        ScreenEdge::Left,
+        ScreenEdge::Right,
+        ScreenEdge::TopLeft,
+        ScreenEdge::TopRight,
+        ScreenEdge::BottomLeft,
+        ScreenEdge::BottomRight,
+    ];
+
+    #[test]
+    fn finds_the_sides_within_the_edge_size() {
+        let dwell = dwell(&ALL_EDGES);
+        let edge = |x, y| dwell.edge_at(&SCREEN, x, y);
+
+        assert_eq!(edge(2800.0, 0.0), Some(ScreenEdge::Top));
+        assert_eq!(edge(2800.0, 1.0), Some(ScreenEdge::Top));
+        assert_eq!(edge(2800.0, 2.0), None);
+        assert_eq!(edge(2800.0, 1079.0), Some(ScreenEdge::Bottom));
+        assert_eq!(edge(1920.0, 500.0), Some(ScreenEdge::Left));
+        assert_eq!(edge(3839.0, 500.0), Some(ScreenEdge::Right));
+        assert_eq!(edge(2800.0, 500.0), None);
+    }
+
+    #[test]
+    fn corners_win_over_their_sides() {
+        let dwell = dwell(&ALL_EDGES);
+        let edge = |x, y| dwell.edge_at(&SCREEN, x, y);
+
+        assert_eq!(edge(1920.0, 0.0), Some(ScreenEdge::TopLeft));
+        assert_eq!(edge(3839.0, 0.0), Some(ScreenEdge::TopRight));
+        // Anywhere in the corner square, not only on the sides
+        assert_eq!(edge(3800.0, 39.0), Some(ScreenEdge::TopRight));
+        assert_eq!(edge(3799.0, 0.0), Some(ScreenEdge::Top));
+        assert_eq!(edge(1959.0, 1079.0), Some(ScreenEdge::BottomLeft));
+        assert_eq!(edge(3839.0, 1040.0), Some(ScreenEdge::BottomRight));
+    }
+
+    #[test]
+    fn only_reports_configured_edges() {
+        // Without its corner, the top right counts as the top
+        let top = dwell(&[ScreenEdge::Top]);
+        assert_eq!(top.edge_at(&SCREEN, 3839.0, 0.0), Some(ScreenEdge::Top));
+        assert_eq!(top.edge_at(&SCREEN, 3839.0, 500.0), None);
+
+        let corner = dwell(&[ScreenEdge::TopRight]);
+        assert_eq!(corner.edge_at(&SCREEN, 2800.0, 0.0), None);
+        assert_eq!(
+            corner.edge_at(&SCREEN, 3830.0, 10.0),
+            Some(ScreenEdge::TopRight)
+        );
+    }
+
+    fn strip(side: ScreenSide, thickness: u32, length: u32) -> Rect {
+        strip_rect(
+            &SCREEN,
+            &DropStripConfig {
+                enabled: true,
+                side,
+                thickness,
+                length,
+            },
+        )
+    }
+
+    #[test]
+    fn centres_the_strip_on_its_side() {
+        let rect = |x, y, width, height| Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        assert_eq!(
+            strip(ScreenSide::Top, 4, 240),
+            rect(2760.0, 0.0, 240.0, 4.0)
+        );
+        assert_eq!(
+            strip(ScreenSide::Bottom, 4, 240),
+            rect(2760.0, 1076.0, 240.0, 4.0)
+        );
+        assert_eq!(
+            strip(ScreenSide::Left, 6, 200),
+            rect(1920.0, 440.0, 6.0, 200.0)
+        );
+        assert_eq!(
+            strip(ScreenSide::Right, 6, 200),
+            rect(3834.0, 440.0, 6.0, 200.0)
+        );
+    }
+
+    #[test]
+    fn spans_the_side_when_the_length_is_zero_or_too_long() {
+        let top = strip(ScreenSide::Top, 4, 0);
+        assert_eq!((top.x, top.width), (1920.0, 1920.0));
+        let left = strip(ScreenSide::Left, 4, 5000);
+        assert_eq!((left.y, left.height), (0.0, 1080.0));
+    }
+
+    #[test]
+    fn keeps_the_strip_at_least_a_pixel_thick() {
+        assert_eq!(strip(ScreenSide::Bottom, 0, 240).height, 1.0);
+        assert_eq!(strip(ScreenSide::Bottom, 0, 240).y, 1079.0);
+    }
+
+    fn key_trigger(double_tap: bool) -> KeyTrigger {
+        KeyTrigger::new(KeyTriggerConfig {
+            enabled: true,
+            keys: "Ctrl+Shift".to_string(),
+            double_tap,
+            double_tap_ms: 400,
+        })
+    }
+
+    // Feed key states (down or up) at the given milliseconds, and return when it fired
+    fn press(trigger: &mut KeyTrigger, events: &[(u64, bool)]) -> Vec<u64> {
+        let start = Instant::now();
+        events
+            .iter()
+            .filter(|&&(ms, down)| trigger.update(|_| down, start + Duration::from_millis(ms)))
+            .map(|&(ms, _)| ms)
+            .collect()
+    }
+
+    #[test]
+    fn key_trigger_fires_when_the_keys_go_down() {
+        let mut trigger = key_trigger(false);
+        let fired = press(
+            &mut trigger,
+            &[(0, false), (10, true), (20, true), (30, false), (40, true)],
+        );
+        assert_eq!(fired, [10, 40]);
+    }
+
+    #[test]
+    fn key_trigger_fires_on_a_double_tap_within_the_window() {
+        let mut trigger = key_trigger(true);
+        let fired = press(
+            &mut trigger,
+            &[
+                (0, true),
+                (100, false),
+                (400, true),
+                (500, false),
+                (600, true),
+            ],
+        );
+        // The third press starts a new double tap
+        assert_eq!(fired, [400]);
+
+        let mut trigger = key_trigger(true);
+        let fired = press(
+            &mut trigger,
+            &[
+                (0, true),
+                (100, false),
+                (401, true),
+                (500, false),
+                (700, true),
+            ],
+        );
+        // Too slow, so the second press is the first of the next double tap
+        assert_eq!(fired, [700]);
+    }
+
+    #[test]
+    fn key_trigger_counts_keys_held_at_a_reset_as_pressed() {
+        let mut trigger = key_trigger(true);
+        press(&mut trigger, &[(0, true)]);
+        trigger.reset();
+        // Still held after the reset, so this is a new first press
+        assert_eq!(press(&mut trigger, &[(100, true)]), Vec::<u64>::new());
+        assert_eq!(press(&mut trigger, &[(200, false), (300, true)]), [300]);
+    }
+
+    #[test]
+    fn key_trigger_does_nothing_when_disabled_or_unset() {
+        let mut trigger = KeyTrigger::new(KeyTriggerConfig {
+            enabled: false,
+            ..KeyTriggerConfig::default()
+        });
+        assert!(press(&mut trigger, &[(0, false), (10, true)]).is_empty());
+
+        let mut trigger = KeyTrigger::new(KeyTriggerConfig {
+            enabled: true,
+            keys: " + ".to_string(),
+            ..KeyTriggerConfig::default()
+        });
+        assert!(press(&mut trigger, &[(0, false), (10, true)]).is_empty());
+    }
+}
@@ -1,16 +1,18 @@
+use super::app_rules::{self, SourceApp};
+use super::triggers::Triggers;
+use crate::commands::config_ops::ParsedHotkey;
 use crate::config::{AppConfig, MouseMonitorConfig};
 use crate::DragState;
-use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 use tauri::{AppHandle, Manager, PhysicalPosition, State};
 use tracing::info;
 use windows::Win32::Foundation::POINT;
-use windows::Win32::System::Threading::{
-    OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetAsyncKeyState, VIRTUAL_KEY, VK_CONTROL, VK_DELETE, VK_DOWN, VK_ESCAPE, VK_F1, VK_LBUTTON,
+    VK_LEFT, VK_LWIN, VK_MENU, VK_RETURN, VK_RIGHT, VK_RWIN, VK_SHIFT, VK_SPACE, VK_TAB, VK_UP,
 };
-use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_LBUTTON};
 use windows::Win32::UI::WindowsAndMessaging::{
     GetCursorPos, GetForegroundWindow, GetSystemMetrics, GetWindowThreadProcessId, SM_CXSCREEN,
     SM_CYSCREEN,
@@ -24,47 +26,59 @@ fn get_mouse_pos() -> POINT {
     pos
 }
 
-fn get_active_process_name() -> Option<String> {
+fn get_active_source_app() -> SourceApp {
     unsafe {
         let hwnd = GetForegroundWindow();
         if hwnd.0 == std::ptr::null_mut() {
-            return None;
+            return SourceApp::default();
         }
         let mut process_id = 0;
         GetWindowThreadProcessId(hwnd, Some(&mut process_id));
-        if process_id == 0 {
-            return None;
-        }
+        SourceApp::from_process(process_id)
+    }
+}
 
-        let process_handle =
-            OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
-        let mut buffer = [0u16; 1024];
-        let mut size = buffer.len() as u32;
-
-        // Use QueryFullProcessImageNameW (requires Win32_System_Threading)
-        let success = QueryFullProcessImageNameW(
-            process_handle,
-            PROCESS_NAME_WIN32,
-            windows::core::PWSTR(buffer.as_mut_ptr()),
-            &mut size,
-        );
+fn is_key_down(key: VIRTUAL_KEY) -> bool {
+    unsafe { GetAsyncKeyState(key.0 as i32) as u16 & 0x8000 != 0 }
+}
 
-        let _ = windows::Win32::Foundation::CloseHandle(process_handle);
+fn is_mouse_button_down() -> bool {
+    is_key_down(VK_LBUTTON)
+}
 
-        if success.is_ok() && size > 0 {
-            let path = String::from_utf16_lossy(&buffer[..size as usize]);
-            let name = std::path::Path::new(&path)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .map(|s| s.to_string());
-            return name;
-        }
-        None
+// Letters and digits are their own virtual key codes
+fn virtual_key(key: &str) -> Option<VIRTUAL_KEY> {
+    match key {
+        "SPACE" => Some(VK_SPACE),
+        "RETURN" | "ENTER" => Some(VK_RETURN),
+        "TAB" => Some(VK_TAB),
+        "ESCAPE" | "ESC" => Some(VK_ESCAPE),
+        "DELETE" => Some(VK_DELETE),
+        "UP" | "ARROWUP" => Some(VK_UP),
+        "DOWN" | "ARROWDOWN" => Some(VK_DOWN),
+        "LEFT" | "ARROWLEFT" => Some(VK_LEFT),
+        "RIGHT" | "ARROWRIGHT" => Some(VK_RIGHT),
+        _ => match key.as_bytes() {
+            [c] if c.is_ascii_alphanumeric() => Some(VIRTUAL_KEY(c.to_ascii_uppercase() as u16)),
+            [b'F', ..] => match key[1..].parse::<u16>() {
+                Ok(n @ 1..=24) => Some(VIRTUAL_KEY(VK_F1.0 + n - 1)),
+                _ => None,
+            },
+            _ => None,
+        },
     }
 }
 
-fn is_mouse_button_down() -> bool {
-    unsafe { GetAsyncKeyState(VK_LBUTTON.0 as i32) as u16 & 0x8000 != 0 }
+fn are_keys_down(hotkey: &ParsedHotkey) -> bool {
+    let key_down = match hotkey.key.as_deref() {
+        Some(key) => virtual_key(key).is_some_and(is_key_down),
+        None => true,
+    };
+    key_down
+        && (!hotkey.ctrl || is_key_down(VK_CONTROL))
+        && (!hotkey.shift || is_key_down(VK_SHIFT))
+        && (!hotkey.alt || is_key_down(VK_MENU))
+        && (!hotkey.meta || is_key_down(VK_LWIN) || is_key_down(VK_RWIN))
 }
 
 fn hide_main_window(app: &AppHandle) {
@@ -73,10 +87,15 @@ fn hide_main_window(app: &AppHandle) {
     }
 }
 
-fn hide_main_window_after_delay(app_handle: AppHandle, drag_state: Arc<DragState>, delay_ms: u64) {
+fn hide_main_window_after_delay(
+    app_handle: AppHandle,
+    drag_state: Arc<DragState>,
+    opened_at: Instant,
+    delay_ms: u64,
+) {
     thread::spawn(move || {
         thread::sleep(Duration::from_millis(delay_ms));
-        let successful_drop = drag_state.successful_drop.load(Ordering::Relaxed);
+        let successful_drop = drag_state.dropped_since(opened_at);
         let mouse_down = is_mouse_button_down();
         if !successful_drop && !mouse_down {
             hide_main_window(&app_handle);
@@ -118,16 +137,17 @@ pub fn start_mouse_monitor(
     drag_state: Arc<DragState>,
 ) {
     info!(
-        "Starting Windows mouse monitor (threshold={}, required_shakes={}, time_limit_ms={})",
+        "Starting Windows mouse monitor (min_stroke_px={}, required_shakes={}, time_limit_ms={})",
         config.shake_threshold, config.required_shakes, config.shake_time_limit
     );
 
     thread::spawn(move || {
-        let mut window_opened_by_shake = false;
-        let mut last_position = get_mouse_pos();
-        let mut shake_count = 0;
-        let mut last_shake_time = Instant::now();
-        let mut last_direction: Option<i32> = None;
+        let mut window_opened_by_trigger = false;
+        let mut window_opened_at = Instant::now();
+        let mut triggers = Triggers::from_config(&config);
+        // The settings for the button press in progress, resolved from the app rules for
+        // the app focused when it went down; `Some(None)` if they ignore that app
+        let mut drag_config: Option<Option<MouseMonitorConfig>> = None;
 
         loop {
             let config = {
@@ -137,90 +157,57 @@ pub fn start_mouse_monitor(
             };
 
             let check_interval = Duration::from_millis(30);
-            let movement_time_limit = Duration::from_millis(config.shake_time_limit);
+            triggers.configure(&app_handle, &config);
 
             let current_pos = get_mouse_pos();
             let mouse_down = is_mouse_button_down();
 
             // --- CASE 1: USER RELEASES MOUSE ---
             if !mouse_down {
-                // If window was opened by shake and drag didn't result in a drop, hide it
-                if window_opened_by_shake {
-                    let successful_drop = drag_state.successful_drop.load(Ordering::Relaxed);
-
-                    if !successful_drop {
+                // If window was opened by a trigger and drag didn't result in a drop, hide it
+                if window_opened_by_trigger {
+                    if !drag_state.dropped_since(window_opened_at) {
                         hide_main_window_after_delay(
                             app_handle.clone(),
                             Arc::clone(&drag_state),
+                            window_opened_at,
                             config.window_close_delay,
                         );
                     }
-
-                    // Reset drag state flags for next drag
-                    drag_state.drag_started.store(false, Ordering::Relaxed);
                 }
 
                 // Reset state
-                window_opened_by_shake = false;
-                shake_count = 0;
-                last_direction = None;
-                last_position = current_pos;
+                window_opened_by_trigger = false;
+                triggers.reset(&app_handle);
+                drag_config = None;
                 thread::sleep(check_interval);
                 continue;
             }
 
-            // --- CASE 2: USER IS DRAGGING (Shake Detection) ---
-            let distance_x = current_pos.x - last_position.x;
-            let direction = if distance_x > config.shake_threshold {
-                1
-            } else if distance_x < -config.shake_threshold {
-                -1
-            } else {
-                0
+            let resolved = drag_config
+                .get_or_insert_with(|| app_rules::decide(&config, &get_active_source_app()));
+            let Some(config) = resolved.as_ref() else {
+                thread::sleep(check_interval);
+                continue;
             };
+            triggers.configure(&app_handle, config);
 
-            if direction != 0 {
-                if let Some(last_dir) = last_direction {
-                    if last_dir != direction {
-                        shake_count += 1;
-                        last_shake_time = Instant::now();
-                    }
-                }
-                last_direction = Some(direction);
-            }
-
-            // Reset shake if too much time passes between wiggles
-            if last_shake_time.elapsed() > movement_time_limit {
-                shake_count = 0;
-            }
+            // --- CASE 2: USER IS DRAGGING (Shake, Edge, Drop Strip and Key Detection) ---
+            let trigger = triggers.update(
+                &app_handle,
+                current_pos.x as f64,
+                current_pos.y as f64,
+                are_keys_down,
+                Instant::now(),
+            );
 
             // Trigger Window
-            if shake_count >= config.required_shakes && !window_opened_by_shake {
-                // Check if current active window is in the whitelist.
-                // If whitelist is empty, we assume it's disabled or allows everything?
-                // The frontend says "No apps whitelisted", so if it's empty, no app should trigger it.
-                // But wait, what if the user expects it to work everywhere if empty?
-                // Usually an empty whitelist means nothing is allowed.
-                // Let's implement strict whitelist: if not empty, must be in it.
-                // If it is empty, maybe allow nothing.
-                let active_app = get_active_process_name().unwrap_or_default();
-                let is_whitelisted = config.whitelist.is_empty()
-                    || config.whitelist.iter().any(|app| {
-                        app.eq_ignore_ascii_case(&active_app)
-                            || active_app.to_lowercase().contains(&app.to_lowercase())
-                    });
-
-                if is_whitelisted {
-                    drag_state.successful_drop.store(false, Ordering::Relaxed);
-                    show_main_window(&app_handle, current_pos, &config);
-                    window_opened_by_shake = true;
-                }
-
-                shake_count = 0;
-                last_direction = None;
+            if trigger.is_some() && !window_opened_by_trigger {
+                window_opened_at = Instant::now();
+                show_main_window(&app_handle, current_pos, config);
+                window_opened_by_trigger = true;
             }
 
-            last_position = current_pos;
             thread::sleep(check_interval);
         }
     });
@@ -0,0 +1,317 @@
+use crate::config::LinkFormat;
+use crate::file::{unique_path, FileMetadata};
+use crate::ingest::links::write_link_file;
+use crate::ingest::text::TextFormat;
+use reqwest::Url;
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Something held on the shelf.
+///
+/// Files and folders are references to paths on disk. Text, URLs and image data are
+/// kept in memory and only written to the staging folder when something needs a file
+/// (see [`ShelfItem::materialize`]), after which `path` points at that file.
+///
+/// Every variant serialises with `kind`, `id`, `name`, `size`, `file_type` and `path`
+/// so the frontend can list them uniformly.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ShelfItem {
+    File(FileMetadata),
+    Folder(FileMetadata),
+    Text(TextItem),
+    Url(UrlItem),
+    Image(ImageItem),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TextItem {
+    pub id: u64,
+    pub name: String,
+    pub text: String,
+    pub size: u64,
+    /// Extension used when the text is written to a file
+    pub file_type: String,
+    /// MIME type of the detected format
+    pub mime: String,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UrlItem {
+    pub id: u64,
+    pub name: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub size: u64,
+    pub file_type: String,
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageItem {
+    pub id: u64,
+    pub name: String,
+    pub mime: String,
+    /// Encoded image (PNG, JPEG, ...), never sent to the frontend
+    #[serde(skip)]
+    pub bytes: Vec<u8>,
+    pub size: u64,
+    pub file_type: String,
+    pub path: Option<PathBuf>,
+}
+
+impl TextItem {
+    pub fn new(name: String, text: String, format: TextFormat) -> Self {
+        Self {
+            id: 0,
+            name,
+            size: text.len() as u64,
+            text,
+            file_type: format.extension().to_string(),
+            mime: format.mime().to_string(),
+            path: None,
+        }
+    }
+
+    pub fn set_format(&mut self, format: TextFormat) {
+        self.file_type = format.extension().to_string();
+        self.mime = format.mime().to_string();
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        self.size = text.len() as u64;
+        self.text = text;
+        // A file written for the old text is stale now
+        self.path = None;
+    }
+}
+
+impl UrlItem {
+    pub fn new(url: String, title: Option<String>) -> Self {
+        Self {
+            id: 0,
+            name: title.clone().unwrap_or_else(|| url.clone()),
+            size: url.len() as u64,
+            url,
+            title,
+            file_type: "url".to_string(),
+            path: None,
+        }
+    }
+}
+
+impl ImageItem {
+    pub fn new(name: String, mime: String, bytes: Vec<u8>, file_type: String) -> Self {
+        Self {
+            id: 0,
+            name,
+            mime,
+            size: bytes.len() as u64,
+            bytes,
+            file_type,
+            path: None,
+        }
+    }
+}
+
+impl ShelfItem {
+    /// Shelf entry for a file or folder on disk. The id is assigned when it is added.
+    pub fn from_path(path: &Path) -> io::Result<Self> {
+        let file = FileMetadata::from_path(path)?;
+        if path.is_dir() {
+            Ok(ShelfItem::Folder(file))
+        } else {
+            Ok(ShelfItem::File(file))
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        match self {
+            ShelfItem::File(f) | ShelfItem::Folder(f) => f.id,
+            ShelfItem::Text(t) => t.id,
+            ShelfItem::Url(u) => u.id,
+            ShelfItem::Image(i) => i.id,
+        }
+    }
+
+    pub fn set_id(&mut self, id: u64) {
+        match self {
+            ShelfItem::File(f) | ShelfItem::Folder(f) => f.id = id,
+            ShelfItem::Text(t) => t.id = id,
+            ShelfItem::Url(u) => u.id = id,
+            ShelfItem::Image(i) => i.id = id,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            ShelfItem::File(f) | ShelfItem::Folder(f) => &f.name,
+            ShelfItem::Text(t) => &t.name,
+            ShelfItem::Url(u) => &u.name,
+            ShelfItem::Image(i) => &i.name,
+        }
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        match self {
+            ShelfItem::File(f) | ShelfItem::Folder(f) => f.name = name,
+            ShelfItem::Text(t) => t.name = name,
+            ShelfItem::Url(u) => u.name = name,
+            ShelfItem::Image(i) => i.name = name,
+        }
+    }
+
+    /// Path on disk, if the item is backed by (or has been written to) a file.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ShelfItem::File(f) | ShelfItem::Folder(f) => Some(&f.path),
+            ShelfItem::Text(t) => t.path.as_deref(),
+            ShelfItem::Url(u) => u.path.as_deref(),
+            ShelfItem::Image(i) => i.path.as_deref(),
+        }
+    }
+
+    /// Whether the item references a file or folder the user owns, as opposed
+    /// to content that lives on the shelf itself.
+    pub fn is_file_backed(&self) -> bool {
+        matches!(self, ShelfItem::File(_) | ShelfItem::Folder(_))
+    }
+
+    /// Make sure the item exists as a file and return its path. In-memory items are
+    /// written to `dir` the first time this is called, URLs as a `link_format` shortcut.
+    pub fn materialize(&mut self, dir: &Path, link_format: LinkFormat) -> Result<PathBuf, String> {
+        if let Some(path) = self.path().filter(|p| p.exists()) {
+            return Ok(path.to_path_buf());
+        }
+
+        let (path, contents): (PathBuf, Vec<u8>) = match self {
+            ShelfItem::File(f) | ShelfItem::Folder(f) => {
+                return Err(format!("File no longer exists: {}", f.path.display()))
+            }
+            ShelfItem::Text(t) => (
+                unique_path(dir, &file_name_with_extension(&t.name, &t.file_type)),
+                t.text.as_bytes().to_vec(),
+            ),
+            ShelfItem::Url(u) => {
+                let url = Url::parse(&u.url).map_err(|e| e.to_string())?;
+                let path = write_link_file(&url, u.title.as_deref(), link_format, dir)?;
+                u.path = Some(path.clone());
+                return Ok(path);
+            }
+            ShelfItem::Image(i) => (
+                unique_path(dir, &file_name_with_extension(&i.name, &i.file_type)),
+                i.bytes.clone(),
+            ),
+        };
+
+        std::fs::write(&path, contents).map_err(|e| e.to_string())?;
+        match self {
+            ShelfItem::Text(t) => t.path = Some(path.clone()),
+            ShelfItem::Image(i) => i.path = Some(path.clone()),
+            ShelfItem::File(_) | ShelfItem::Folder(_) | ShelfItem::Url(_) => {}
+        }
+        Ok(path)
+    }
+}
+
+// Next free id; ids stay unique even after items were removed from the middle
+pub fn next_id(items: &[ShelfItem]) -> u64 {
+    items.iter().map(|item| item.id() + 1).max().unwrap_or(0)
+}
+
+fn file_name_with_extension(name: &str, extension: &str) -> String {
+    let name = crate::file::sanitize_file_name(name);
+    let name = if name.is_empty() {
+        "snippet".to_string()
+    } else {
+        name
+    };
+    if Path::new(&name)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+    {
+        name
+    } else {
+        format!("{}.{}", name, extension)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_written_once_when_first_needed() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut item = ShelfItem::Text(TextItem::new(
+            "notes".to_string(),
+            "# Notes\n".to_string(),
+            TextFormat::Markdown,
+        ));
+        assert_eq!(item.path(), None);
+
+        let path = item.materialize(dir.path(), LinkFormat::Auto).unwrap();
+        assert_eq!(path, dir.path().join("notes.md"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Notes\n");
+        assert_eq!(item.path(), Some(path.as_path()));
+
+        // Already written, so the same file comes back
+        let again = item.materialize(dir.path(), LinkFormat::Auto).unwrap();
+        assert_eq!(again, path);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn edited_or_deleted_text_is_written_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut text = TextItem::new("a.txt".to_string(), "one".to_string(), TextFormat::Plain);
+        text.path = Some(dir.path().join("a.txt"));
+        let mut item = ShelfItem::Text(text);
+
+        // The recorded file is gone
+        let path = item.materialize(dir.path(), LinkFormat::Auto).unwrap();
+        assert_eq!(path, dir.path().join("a.txt"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "one");
+
+        if let ShelfItem::Text(text) = &mut item {
+            text.set_text("two".to_string());
+        }
+        let edited = item.materialize(dir.path(), LinkFormat::Auto).unwrap();
+        assert_eq!(edited, dir.path().join("a (1).txt"));
+        assert_eq!(std::fs::read_to_string(&edited).unwrap(), "two");
+    }
+
+    #[test]
+    fn url_is_written_in_the_configured_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut item = ShelfItem::Url(UrlItem::new(
+            "https://example.com/".to_string(),
+            Some("Example".to_string()),
+        ));
+        assert_eq!(item.path(), None);
+
+        let path = item.materialize(dir.path(), LinkFormat::Url).unwrap();
+        assert_eq!(path, dir.path().join("Example.url"));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[InternetShortcut]\r\nURL=https://example.com/\r\n"
+        );
+        assert_eq!(item.path(), Some(path.as_path()));
+
+        let again = item.materialize(dir.path(), LinkFormat::Desktop).unwrap();
+        assert_eq!(again, path);
+    }
+
+    #[test]
+    fn missing_files_are_not_materialized() {
+        let dir = tempfile::tempdir().unwrap();
+        let gone = dir.path().join("gone.txt");
+        std::fs::write(&gone, "x").unwrap();
+        let mut item = ShelfItem::from_path(&gone).unwrap();
+        std::fs::remove_file(&gone).unwrap();
+
+        assert!(item.materialize(dir.path(), LinkFormat::Auto).is_err());
+    }
+}
@@ -1,11 +1,15 @@
 use base64::{engine::general_purpose, Engine as _};
-use image::{ImageBuffer, Rgba};
+use image::{ImageBuffer, Rgba, RgbaImage};
 use thumb_rs::{get_thumbnail, ThumbnailScale};
 
-pub fn get_thumbnail_base64(file_path: &str) -> Result<String, String> {
+pub fn get_thumbnail_image(file_path: &str) -> Result<RgbaImage, String> {
     let thumb = get_thumbnail(file_path, ThumbnailScale::default()).map_err(|e| e.to_string())?;
-    let img = ImageBuffer::<Rgba<u8>, _>::from_raw(thumb.width, thumb.height, thumb.rgba)
-        .ok_or_else(|| "Failed to create image buffer".to_string())?;
+    ImageBuffer::<Rgba<u8>, _>::from_raw(thumb.width, thumb.height, thumb.rgba)
+        .ok_or_else(|| "Failed to create image buffer".to_string())
+}
+
+pub fn get_thumbnail_base64(file_path: &str) -> Result<String, String> {
+    let img = get_thumbnail_image(file_path)?;
     let mut png_data = Vec::new();
     img.write_to(
         &mut std::io::Cursor::new(&mut png_data),
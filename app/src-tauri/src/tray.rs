@@ -1,17 +1,38 @@
+use crate::clipboard::watcher::ClipboardWatcher;
+use crate::config::AppConfig;
+use std::sync::{Arc, Mutex};
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Manager, Runtime, WebviewUrl, WebviewWindowBuilder,
+    AppHandle, Manager, WebviewUrl, WebviewWindowBuilder,
 };
+use tracing::warn;
 // use tauri_plugin_dialog::DialogExt;
 // use tauri_plugin_updater::UpdaterExt;
 // use tauri::Emitter;
 
-pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
+// Kept so the check mark follows changes made from the settings window
+struct ClipboardWatchMenuItem(CheckMenuItem<tauri::Wry>);
+
+pub fn create_tray(app: &AppHandle) -> tauri::Result<()> {
     let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
     let check_update_i =
         MenuItem::with_id(app, "check_update", "Check for Updates", true, None::<&str>)?;
-    let menu = Menu::with_items(app, &[&check_update_i, &quit_i])?;
+    let watching = app
+        .state::<Arc<Mutex<AppConfig>>>()
+        .lock()
+        .map(|config| config.clipboard_watch.enabled)
+        .unwrap_or(false);
+    let clipboard_watch_i = CheckMenuItem::with_id(
+        app,
+        "clipboard_watch",
+        "Collect Clipboard",
+        true,
+        watching,
+        None::<&str>,
+    )?;
+    let menu = Menu::with_items(app, &[&clipboard_watch_i, &check_update_i, &quit_i])?;
+    app.manage(ClipboardWatchMenuItem(clipboard_watch_i));
 
     let _ = TrayIconBuilder::with_id("tray")
         .icon(app.default_window_icon().unwrap().clone())
@@ -21,6 +42,22 @@ pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
             "quit" => {
                 app.exit(0);
             }
+            "clipboard_watch" => {
+                // The menu item has already toggled its own check mark
+                let enabled = app
+                    .state::<ClipboardWatchMenuItem>()
+                    .0
+                    .is_checked()
+                    .unwrap_or(false);
+                let config_state = app.state::<Arc<Mutex<AppConfig>>>();
+                if let Ok(mut config) = config_state.lock() {
+                    config.clipboard_watch.enabled = enabled;
+                    if let Err(e) = config.save(app) {
+                        warn!("Failed to save config: {}", e);
+                    }
+                }
+                app.state::<ClipboardWatcher>().apply(app, enabled);
+            }
             "check_update" => {
                 // Open a dedicated updater window
                 // First check if window already exists
@@ -63,3 +100,9 @@ pub fn create_tray<R: Runtime>(app: &tauri::AppHandle<R>) -> tauri::Result<()> {
 
     Ok(())
 }
+
+pub fn set_clipboard_watch_checked(app: &AppHandle, enabled: bool) {
+    if let Some(item) = app.try_state::<ClipboardWatchMenuItem>() {
+        let _ = item.0.set_checked(enabled);
+    }
+}
@@ -38,3 +38,115 @@ impl ScreenBounds {
         (constrained_x, constrained_y)
     }
 }
+
+/// Whether `name` is the app `pattern` names, ignoring case and a `.exe` suffix on
+/// either side (so `brave` and `Brave.exe` are the same app).
+pub fn same_app_name(name: &str, pattern: &str) -> bool {
+    !pattern.is_empty() && without_exe(name).eq_ignore_ascii_case(without_exe(pattern))
+}
+
+fn without_exe(name: &str) -> &str {
+    match name.len().checked_sub(4) {
+        Some(stem) if name.is_char_boundary(stem) && name[stem..].eq_ignore_ascii_case(".exe") => {
+            &name[..stem]
+        }
+        _ => name,
+    }
+}
+
+/// File name of the executable of a process (e.g. `explorer.exe`).
+#[cfg(target_os = "windows")]
+pub fn process_name(process_id: u32) -> Option<String> {
+    process_path(process_id)?
+        .file_name()
+        .and_then(|n| n.to_str())
+        .map(|s| s.to_string())
+}
+
+/// Full path of the executable of a process.
+#[cfg(target_os = "windows")]
+pub fn process_path(process_id: u32) -> Option<std::path::PathBuf> {
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    if process_id == 0 {
+        return None;
+    }
+    unsafe {
+        let process_handle =
+            OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut size = buffer.len() as u32;
+
+        // Use QueryFullProcessImageNameW (requires Win32_System_Threading)
+        let success = QueryFullProcessImageNameW(
+            process_handle,
+            PROCESS_NAME_WIN32,
+            windows::core::PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        );
+
+        let _ = windows::Win32::Foundation::CloseHandle(process_handle);
+
+        if success.is_ok() && size > 0 {
+            let path = String::from_utf16_lossy(&buffer[..size as usize]);
+            return Some(path.into());
+        }
+        None
+    }
+}
+
+/// File name of the executable of a process (e.g. `nautilus`).
+#[cfg(target_os = "linux")]
+pub fn process_name(process_id: u32) -> Option<String> {
+    if process_id == 0 {
+        return None;
+    }
+    // The executable may be unreadable for other users' processes; `comm` is truncated
+    // to 15 bytes but always readable
+    let exe = process_path(process_id);
+    match exe.as_deref().and_then(|path| path.file_name()) {
+        Some(name) => Some(name.to_string_lossy().into_owned()),
+        None => std::fs::read_to_string(format!("/proc/{}/comm", process_id))
+            .ok()
+            .map(|comm| comm.trim_end().to_string()),
+    }
+}
+
+/// Full path of the executable of a process.
+#[cfg(target_os = "linux")]
+pub fn process_path(process_id: u32) -> Option<std::path::PathBuf> {
+    if process_id == 0 {
+        return None;
+    }
+    std::fs::read_link(format!("/proc/{}/exe", process_id)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn app_names_ignore_case_and_exe() {
+        assert!(same_app_name("Code.exe", "code"));
+        assert!(same_app_name("code", "CODE.EXE"));
+        assert!(same_app_name("KeePassXC", "keepassxc"));
+    }
+
+    #[test]
+    fn app_names_match_whole_names() {
+        assert!(!same_app_name("vscode-helper", "code"));
+        assert!(!same_app_name("barcode.exe", "code"));
+        assert!(!same_app_name("code", ""));
+    }
+
+    #[test]
+    fn strips_only_a_trailing_exe() {
+        assert_eq!(without_exe("explorer.EXE"), "explorer");
+        assert_eq!(without_exe("exe"), "exe");
+        assert_eq!(without_exe(".exe.txt"), ".exe.txt");
+        assert_eq!(without_exe("é.exe"), "é");
+    }
+}
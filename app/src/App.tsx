@@ -25,7 +25,7 @@ function App() {
   const listenerSetup = useRef(false);
   const [isModalOpen, setIsModalOpen] = useState(false);
   const [showSettings, setShowSettings] = useState(false);
-  const { files, addFiles, getFileIcon, clearFiles, droppedFiles } = useFileManagement();
+  const { files, isDraggingOut, addFiles, addTextItem, getFileIcon, clearFiles, droppedFiles } = useFileManagement();
   const navigate = useNavigate();
 
   // Check analytics consent on mount
@@ -64,12 +64,33 @@ function App() {
 
     setupFileListener();
 
-    // Listen to native Windows drag drops
-    const unlistenNativeDrop = listen<{ type: 'Files', data: string[] } | { type: 'Text', data: string } | { type: 'Html', data: string }>('native_drop', (event) => {
+    // Listen to native drops (Windows and Linux)
+    const unlistenNativeDrop = listen<
+      | { type: 'Files', data: string[] }
+      | { type: 'Text', data: string }
+      | { type: 'Html', data: string }
+      | { type: 'Image', data: { mime: string, data_base64: string, html: string | null, url: string | null } }
+      | { type: 'Url', data: { url: string, title: string | null } }
+    >('native_drop', (event) => {
       const payload = event.payload;
       if (payload.type === 'Files') {
         invoke('add_files', { files: payload.data });
         droppedFiles();
+      } else if (payload.type === 'Image') {
+        invoke<string>('add_dropped_image', {
+          dataBase64: payload.data.data_base64,
+          mime: payload.data.mime,
+          html: payload.data.html,
+          url: payload.data.url
+        }).then(() => {
+          droppedFiles();
+        }).catch(err => console.error('Failed to save dropped image', err));
+      } else if (payload.type === 'Url') {
+        const { url, title } = payload.data;
+        invoke<string[]>('add_links_from_text', { text: url, title: title })
+          .then(() => {
+            droppedFiles();
+          }).catch(err => console.error('Failed to add dropped link', err));
       } else if (payload.type === 'Html') {
         const html = payload.data;
         
@@ -109,30 +130,16 @@ function App() {
             }
           }
 
-          if (src.startsWith('data:image/')) {
-            const b64match = src.match(/^data:image\/([a-zA-Z]*);base64,(.*)$/);
-            if (b64match && b64match[2]) {
-              invoke<string>('save_pasted_data_base64', {
-                dataBase64: b64match[2],
-                extension: b64match[1] || 'png'
-              }).then(path => {
-                invoke('add_files', { files: [path] });
-                droppedFiles();
-              }).catch(err => {
-                console.error('Failed to save data URI', err);
-                toast.error('Failed to save data image');
-              });
-              return;
+          if (src.startsWith('data:') || src.match(/^https?:\/\//i)) {
+            if (!src.startsWith('data:')) {
+              toast.info('Downloading image...');
             }
-          } else if (src.match(/^https?:\/\//i)) {
-            toast.info('Downloading image...');
-            invoke<string>('download_image_to_shelf', { url: src })
-              .then(path => {
-                invoke('add_files', { files: [path] });
+            invoke<string>('add_from_url', { url: src })
+              .then(() => {
                 droppedFiles();
               }).catch(err => {
-                console.error('Failed to download image from HTML', err);
-                toast.error('Could not download image: ' + err);
+                console.error('Failed to add image from HTML', err);
+                toast.error('Could not add image: ' + err);
                 // Fallback to text link
                 invoke<string>('save_pasted_text', { text: src, extension: 'txt' }).then(p => { invoke('add_files', {files:[p]}); droppedFiles(); });
               });
@@ -140,36 +147,39 @@ function App() {
           }
         }
         
-        // Fallback if no image found in HTML
-        invoke<string>('save_pasted_text', {
-          text: html,
-          extension: 'html'
-        }).then(path => {
-          invoke('add_files', { files: [path] });
+        // Fallback if no image found in HTML: save it as a clean .html/.md clipping
+        invoke<string[]>('ingest_html', {
+          html: html,
+          sourceUrl: sourceUrl || null
+        }).then(() => {
           droppedFiles();
         }).catch(err => console.error('Failed to save dropped HTML', err));
       } else if (payload.type === 'Text') {
         const text = payload.data.trim();
-        if (text.startsWith('data:image/')) {
-          const match = text.match(/^data:image\/([a-zA-Z]*);base64,(.*)$/);
-          if (match && match[2]) {
-            invoke<string>('save_pasted_data_base64', {
-              dataBase64: match[2],
-              extension: match[1] || 'png'
-            }).then(path => {
-              invoke('add_files', { files: [path] });
+        if (text.match(/^https?:\/\//i)) {
+          // Dropped browser links become .url/.webloc/.desktop shortcuts
+          invoke<string[]>('add_links_from_text', { text: text })
+            .then(() => {
               droppedFiles();
-            }).catch(err => console.error('Failed to save data URI', err));
-            return;
-          }
-        } else if (text.match(/^https?:\/\//i)) {
-          // It's a URL. Let's download it.
-          invoke<string>('download_image_to_shelf', { url: text })
-            .then(path => {
-              invoke('add_files', { files: [path] });
+            }).catch(() => {
+              invoke<string>('save_pasted_text', {
+                text: text,
+                extension: 'txt'
+              }).then(path => {
+                invoke('add_files', { files: [path] });
+                droppedFiles();
+              });
+            });
+          return;
+        }
+
+        if (text.startsWith('data:') || text.match(/^file:\/\//i)) {
+          // data: and file:// URLs are resolved to a file by the backend
+          invoke<string>('add_from_url', { url: text })
+            .then(() => {
               droppedFiles();
             }).catch(err => {
-              console.error('Failed to download image', err);
+              console.error('Failed to add URL', err);
               // Fallback to text
               invoke<string>('save_pasted_text', {
                 text: text,
@@ -182,16 +192,18 @@ function App() {
           return;
         }
 
-        invoke<string>('save_pasted_text', {
-          text: text,
-          extension: 'txt'
-        }).then(path => {
-          invoke('add_files', { files: [path] });
+        // Plain text stays an editable snippet until it is dragged out
+        addTextItem(text).then(() => {
           droppedFiles();
-        }).catch(err => console.error('Failed to save dropped text', err));
+        });
       }
     });
 
+    // Drops that break the drop rules in the settings
+    const unlistenDropRejected = listen<{ reason: string }>('drop_rejected', (event) => {
+      toast.error(event.payload.reason, { id: 'drop_rejected' });
+    });
+
     // Set up navigation event listener
     const unlisten = listen<string>("navigate_to", (event) => {
       if (event.payload) {
@@ -201,9 +213,10 @@ function App() {
 
     return () => {
       unlistenNativeDrop.then(fn => fn());
+      unlistenDropRejected.then(fn => fn());
       // unlisten.then(fn => fn());
     };
-  }, [addFiles, getFileIcon, navigate, droppedFiles]);
+  }, [addFiles, addTextItem, getFileIcon, navigate, droppedFiles]);
 
   const handleDragEnter = useCallback((e: React.DragEvent<HTMLDivElement>) => {
     e.preventDefault();
@@ -252,12 +265,7 @@ function App() {
         } else if (item.type === 'text/plain') {
           item.getAsString((text) => {
             if (!text) return;
-            invoke<string>('save_pasted_text', {
-              text: text,
-              extension: 'txt'
-            }).then(path => {
-              invoke('add_files', { files: [path] });
-            }).catch(err => console.error('Failed to paste text', err));
+            addTextItem(text);
           });
         }
       }
@@ -265,7 +273,7 @@ function App() {
 
     window.addEventListener('paste', handleGlobalPaste);
     return () => window.removeEventListener('paste', handleGlobalPaste);
-  }, []);
+  }, [addTextItem]);
 
   const handleDrop = useCallback(async (e: React.DragEvent<HTMLDivElement>) => {
     e.preventDefault();
@@ -369,20 +377,12 @@ function App() {
           text = e.dataTransfer.getData("text");
       }
       if (text) {
-        try {
-          const path = await invoke<string>('save_pasted_text', {
-            text: text,
-            extension: 'txt'
-          });
-          await invoke('add_files', { files: [path] });
-        } catch (err) {
-          console.error('Failed to drop text', err);
-        }
+        await addTextItem(text);
       }
       return;
     }
 
-  }, [addFiles]);
+  }, [addFiles, addTextItem]);
 
   const openPopup = () => {
     invoke('open_popup_window').catch((err) => console.error(err));
@@ -438,7 +438,7 @@ function App() {
           onDragLeave={handleDragLeave}
           onDrop={handleDrop}>
           {files.length > 0 ? (
-            <div ref={stackedIconsRef} className="relative w-10 h-10 flex items-center justify-center">
+            <div ref={stackedIconsRef} className={`relative w-10 h-10 flex items-center justify-center ${isDraggingOut ? "opacity-50" : ""}`}>
               <StackedIcons files={files} />
             </div>
           ) : (
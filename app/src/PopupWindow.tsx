@@ -1,9 +1,11 @@
 import { DynamicFileIcon } from "@/components/FileIcon";
 import { Button } from "@/components/ui/button";
 import { useFileManagement } from "@/hooks/useFileManagement";
+import { FilePreview } from "@/types";
+import { ClipboardShelf } from "@/components/ClipboardShelf";
 import { setPendingFiles, prepareDragImage, triggerNativeDrag } from "@/lib/fileUtils";
 import { invoke } from "@tauri-apps/api/core";
-import { MoreHorizontal, List as ListIcon, Grid as GridIcon, Trash2 } from 'lucide-react';
+import { MoreHorizontal, List as ListIcon, Grid as GridIcon, Trash2, Pencil } from 'lucide-react';
 import React, { useEffect, useState, useCallback, useRef } from "react";
 import { Toaster } from "sonner";
 import * as ContextMenu from '@radix-ui/react-context-menu';
@@ -12,13 +14,14 @@ import SimpleBar from 'simplebar-react';
 import 'simplebar-react/dist/simplebar.min.css';
 
 const PopupWindow: React.FC = () => {
-  const { files } = useFileManagement();
+  const { files, updateTextItem } = useFileManagement();
   const [selectedFiles, setSelectedFiles] = useState<Set<string>>(new Set());
   const [hasInteracted, setHasInteracted] = useState(false);
   const [viewMode, setViewMode] = useState<'list' | 'grid'>('list');
   const [lastSelectedFile, setLastSelectedFile] = useState<string | null>(null);
   const fileRefs = useRef<{ [key: string]: HTMLDivElement | null }>({});
   const dragTimeoutRef = useRef<number | null>(null);
+  const [editingSnippet, setEditingSnippet] = useState<{ id: number, text: string } | null>(null);
 
   useEffect(() => {
     const timeoutId = setTimeout(() => {
@@ -152,6 +155,44 @@ const PopupWindow: React.FC = () => {
       });
   }, [selectedFiles]);
 
+  const selectedSnippet = selectedFiles.size === 1
+    ? files.find(f => selectedFiles.has(f.id.toString()) && f.kind === 'text')
+    : undefined;
+
+  const editSnippet = useCallback((file: FilePreview | undefined) => {
+    if (file && file.kind === 'text') {
+      setEditingSnippet({ id: file.id, text: file.text ?? '' });
+    }
+  }, []);
+
+  const saveSnippet = useCallback(() => {
+    if (!editingSnippet) return;
+    updateTextItem(editingSnippet.id, editingSnippet.text).then(() => {
+      setEditingSnippet(null);
+    });
+  }, [editingSnippet, updateTextItem]);
+
+  if (editingSnippet) {
+    return (
+      <div className="fixed inset-0 bg-background p-2 rounded border border-border flex flex-col gap-2">
+        <textarea
+          autoFocus
+          value={editingSnippet.text}
+          onChange={(e) => setEditingSnippet({ ...editingSnippet, text: e.target.value })}
+          className="flex-grow w-full resize-none rounded border border-border bg-background p-2 text-xs font-mono text-primary focus:outline-none"
+        />
+        <div className="flex justify-end space-x-2">
+          <Button variant="ghost" size="sm" onClick={() => setEditingSnippet(null)}>
+            Cancel
+          </Button>
+          <Button size="sm" onClick={saveSnippet}>
+            Save
+          </Button>
+        </div>
+      </div>
+    );
+  }
+
   return (
     <div className="fixed inset-0 bg-background  p-2 rounded border border-border">
       <div className="flex justify-between items-center mb-2">
@@ -198,6 +239,7 @@ const PopupWindow: React.FC = () => {
                   cursor-grab active:cursor-grabbing
                 `}
                 onClick={(e) => handleFileClick(file.id.toString(), e)}
+                onDoubleClick={() => editSnippet(file)}
                 onMouseDown={(e) => handleMouseDown(e, file)}
                 onMouseUp={handleMouseUp}
                 onMouseLeave={handleMouseUp}
@@ -229,6 +271,14 @@ const PopupWindow: React.FC = () => {
         </ContextMenu.Trigger>
         <ContextMenu.Portal>
           <ContextMenu.Content className="min-w-[200px] bg-background rounded-md overflow-hidden p-1">
+            <ContextMenu.Item
+              className="text-sm  hover:bg-secondary rounded flex items-center px-2 py-1 cursor-pointer text-primary"
+              onClick={() => editSnippet(selectedSnippet)}
+              disabled={!selectedSnippet}
+            >
+              <Pencil className="w-4 h-4 mr-2" />
+              Edit Snippet
+            </ContextMenu.Item>
             <ContextMenu.Item 
               className="text-sm  hover:bg-secondary rounded flex items-center px-2 py-1 cursor-pointer text-primary"
               onClick={handleRemoveSelectedFiles}
@@ -240,6 +290,8 @@ const PopupWindow: React.FC = () => {
           </ContextMenu.Content>
         </ContextMenu.Portal>
       </ContextMenu.Root>
+
+      <ClipboardShelf />
       
       <Toaster />
       </div>
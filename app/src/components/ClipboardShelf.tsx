@@ -0,0 +1,62 @@
+import { ArrowUp, ClipboardList, Trash2 } from "lucide-react";
+import { Button } from "./ui/button";
+import { useClipboardShelf } from "@/hooks/useClipboardShelf";
+
+// What the clipboard watcher collected; entries move onto the main shelf on request
+export function ClipboardShelf() {
+  const { items, moveToShelf, clear } = useClipboardShelf();
+
+  if (items.length === 0) {
+    return null;
+  }
+
+  return (
+    <div className="mt-2 pt-2 border-t border-border">
+      <div className="flex justify-between items-center mb-1">
+        <span className="flex items-center text-xs text-primary font-medium">
+          <ClipboardList className="h-3.5 w-3.5 mr-1" />
+          Clipboard ({items.length})
+        </span>
+        <div className="flex space-x-1">
+          <Button
+            variant="ghost"
+            size="sm"
+            className="h-6 px-2 text-xs"
+            onClick={() => moveToShelf(items.map(item => item.id))}
+            title="Add all to shelf"
+          >
+            <ArrowUp className="h-3.5 w-3.5 mr-1" />
+            All
+          </Button>
+          <Button
+            variant="ghost"
+            size="sm"
+            className="h-6 w-6 p-0 text-destructive hover:bg-destructive/10"
+            onClick={clear}
+            title="Clear clipboard shelf"
+          >
+            <Trash2 className="h-3.5 w-3.5" />
+          </Button>
+        </div>
+      </div>
+      <div className="space-y-1">
+        {items.slice().reverse().map(item => (
+          <div key={item.id} className="group flex items-center justify-between p-1 rounded hover:bg-secondary/50">
+            <p className="text-xs text-primary truncate" title={item.text ?? item.url ?? item.name}>
+              {item.kind === 'text' && item.text ? item.text : item.name}
+            </p>
+            <Button
+              variant="ghost"
+              size="sm"
+              className="h-5 w-5 p-0 opacity-0 group-hover:opacity-100 shrink-0"
+              onClick={() => moveToShelf([item.id])}
+              title="Add to shelf"
+            >
+              <ArrowUp className="h-3 w-3" />
+            </Button>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
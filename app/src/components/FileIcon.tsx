@@ -34,10 +34,11 @@ export const DynamicFileIcon: React.FC<DynamicFileIconProps> = ({ file, ...props
   }, []);
 
   useEffect(() => {
-    if (isVisible) {
+    const path = file.path;
+    if (isVisible && path) {
       const fetchIcon = async () => {
         try {
-          const base64Icon = await getFileIcon(file.path);
+          const base64Icon = await getFileIcon(path);
           setIconBase64(base64Icon);
         } catch (error) {
           console.error('Error fetching file icon:', error);
@@ -0,0 +1,46 @@
+import { useState, useCallback, useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { invoke } from '@tauri-apps/api/core';
+import { FilePreview } from '../types.ts';
+
+// Items the clipboard watcher collected, kept apart from the main shelf
+export const useClipboardShelf = () => {
+  const [items, setItems] = useState<FilePreview[]>([]);
+
+  const fetchItems = useCallback(async () => {
+    try {
+      setItems(await invoke<FilePreview[]>('get_clipboard_shelf'));
+    } catch (error) {
+      console.error('Error fetching clipboard shelf:', error);
+    }
+  }, []);
+
+  useEffect(() => {
+    fetchItems();
+    const unlisten = listen('clipboard_shelf_updated', () => {
+      fetchItems();
+    });
+    return () => {
+      unlisten.then(fn => fn());
+    };
+  }, [fetchItems]);
+
+  const moveToShelf = useCallback(async (ids: number[]) => {
+    try {
+      await invoke('move_clipboard_items_to_shelf', { itemIds: ids });
+      // The backend will emit 'clipboard_shelf_updated' and 'files_updated'
+    } catch (error) {
+      console.error('Error moving clipboard items to the shelf:', error);
+    }
+  }, []);
+
+  const clear = useCallback(async () => {
+    try {
+      await invoke('clear_clipboard_shelf');
+    } catch (error) {
+      console.error('Error clearing clipboard shelf:', error);
+    }
+  }, []);
+
+  return { items, moveToShelf, clear };
+};
@@ -1,11 +1,12 @@
 import { useState, useCallback, useEffect } from 'react';
-import { FilePreview } from '../types.ts';
+import { DragSessionEvent, FilePreview } from '../types.ts';
 import { emit, listen } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
 
 export const useFileManagement = () => {
   const [files, setFiles] = useState<FilePreview[]>([]);
   const [isWindowVisible, setIsWindowVisible] = useState(true);
+  const [isDraggingOut, setIsDraggingOut] = useState(false);
 
   const fetchFiles = useCallback(async () => {
     try {
@@ -51,11 +52,25 @@ export const useFileManagement = () => {
         fetchFiles();
       });
 
+      // Progress of a drag out of the shelf, reported by the drag_ops commands
+      const unlistenDragSession = await listen<DragSessionEvent>('drag_session', (event) => {
+        if (event.payload.type === 'started') {
+          setIsDraggingOut(true);
+        } else if (event.payload.type === 'ended') {
+          setIsDraggingOut(false);
+          const removed = event.payload.removed;
+          if (removed.length > 0) {
+            setFiles(prevFiles => prevFiles.filter(file => !removed.includes(file.id)));
+          }
+        }
+      });
+
       return () => {
         unlistenAdded();
         unlistenRemoved();
         unlistenRenamed();
         unlistenUpdated();
+        unlistenDragSession();
       };
     };
 
@@ -81,7 +96,7 @@ export const useFileManagement = () => {
   }, [fetchFiles, refreshFiles]);
 
   const addFiles = useCallback(async (newFiles: FilePreview[]) => {
-    const paths = newFiles.map(file => file.path);
+    const paths = newFiles.map(file => file.path).filter((path): path is string => path !== null);
     try {
       await invoke('add_files', { files: paths });
       // The backend will emit a 'file_added' event, so we don't need to update the state here
@@ -120,6 +135,24 @@ export const useFileManagement = () => {
     }
   }, []);
 
+  const addTextItem = useCallback(async (text: string, name?: string): Promise<number | null> => {
+    try {
+      return await invoke<number>('add_text_item', { text, name: name ?? null });
+    } catch (error) {
+      console.error('Error adding text snippet:', error);
+      return null;
+    }
+  }, []);
+
+  const updateTextItem = useCallback(async (id: number, text: string) => {
+    try {
+      await invoke('update_text_item', { itemId: id, text });
+      // The backend will emit a 'files_updated' event, so we don't need to update the state here
+    } catch (error) {
+      console.error('Error updating text snippet:', error);
+    }
+  }, []);
+
   const getFileIcon = useCallback(async (filePath: string): Promise<string> => {
     try {
       const iconBase64: string = await invoke('get_file_icon_base64', { filePath });
@@ -130,5 +163,22 @@ export const useFileManagement = () => {
     }
   }, []);
 
-  return { files, addFiles, remove_files, renameFile, getFileIcon, clearFiles, droppedFiles };
+  const copyToClipboard = useCallback(async (ids: number[], format: 'files' | 'text' | 'image' = 'files') => {
+    try {
+      await invoke('copy_items_to_clipboard', { itemIds: ids, format });
+    } catch (error) {
+      console.error('Error copying to clipboard:', error);
+    }
+  }, []);
+
+  const pasteFromClipboard = useCallback(async (): Promise<number[]> => {
+    try {
+      return await invoke<number[]>('paste_from_clipboard');
+    } catch (error) {
+      console.error('Error pasting from clipboard:', error);
+      return [];
+    }
+  }, []);
+
+  return { files, isDraggingOut, addFiles, remove_files, renameFile, addTextItem, updateTextItem, getFileIcon, clearFiles, droppedFiles, copyToClipboard, pasteFromClipboard };
 };
\ No newline at end of file
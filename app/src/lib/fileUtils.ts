@@ -99,6 +99,10 @@ export const prepareDragImage = async (element: HTMLElement): Promise<void> => {
   pendingDragImage = await captureElementAsImage(element);
 };
 
+// In-memory items (snippets, links, images) need a file before they can be dragged
+const itemPath = (file: FilePreview): Promise<string> =>
+  file.path ? Promise.resolve(file.path) : invoke<string>('materialize_item', { itemId: file.id });
+
 export const setPendingFiles = (files: FilePreview[]): void => {
   pendingFiles = files;
 };
@@ -132,10 +136,23 @@ export const triggerNativeDrag = (): void => {
 
   // Start native drag after a small delay to ensure window is focused
   setTimeout(async () => {
-    if (filesToDrag.length === 1 && filesToDrag[0].name.startsWith('pasted_') && filesToDrag[0].name.endsWith('.txt')) {
+    if (filesToDrag.length === 1 && filesToDrag[0].kind === 'text') {
+      try {
+        await invoke('start_text_drag', {
+          itemId: filesToDrag[0].id,
+          dragImage
+        });
+        return;
+      } catch (error) {
+        console.error('Failed to drag text snippet:', error);
+      }
+    }
+
+    const first = filesToDrag[0];
+    if (filesToDrag.length === 1 && first.path && first.name.startsWith('pasted_') && first.name.endsWith('.txt')) {
         try {
             const { readTextFile } = await import('@tauri-apps/plugin-fs');
-            const text = await readTextFile(filesToDrag[0].path);
+            const text = await readTextFile(first.path);
             await invoke('start_text_drag', {
                 text,
                 dragImage
@@ -146,8 +163,16 @@ export const triggerNativeDrag = (): void => {
         }
     }
 
+    const filePaths = await Promise.all(filesToDrag.map(itemPath)).catch((error) => {
+      console.error('Failed to prepare items for drag:', error);
+      return null;
+    });
+    if (!filePaths) {
+      return;
+    }
+
     invoke('start_multi_drag', {
-      filePaths: filesToDrag.map(file => file.path),
+      filePaths,
       dragImage,
     }).catch((error) => {
       console.error('Failed to start native drag:', error);
@@ -168,10 +193,10 @@ export const handleDragStart = (e: React.DragEvent<HTMLDivElement>, file: FilePr
       console.error('Failed to focus window before starting drag:', error);
     });
     setTimeout(() => {
-      invoke('start_multi_drag', { 
-        filePaths: [file.path], 
-        dragImage: null 
-      }).catch((error) => {
+      itemPath(file).then(path => invoke('start_multi_drag', {
+        filePaths: [path],
+        dragImage: null
+      })).catch((error) => {
         console.error('Failed to start native drag:', error);
       });
     }, 50);
@@ -198,10 +223,10 @@ export const handleMultiFileDragStart = (
       console.error('Failed to focus window before starting drag:', error);
     });
     setTimeout(() => {
-      invoke('start_multi_drag', {
-        filePaths: files.map(file => file.path),
+      Promise.all(files.map(itemPath)).then(filePaths => invoke('start_multi_drag', {
+        filePaths,
         dragImage,
-      }).catch((error) => {
+      })).catch((error) => {
         console.error('Failed to start native multi-file drag:', error);
       });
     }, 50);
@@ -9,6 +9,7 @@ import { ThemeProvider } from "./components/theme-provider";
 import SettingsWindow from "./pages/Settings";
 import Updater from "./pages/Updater";
 import Consent from "./pages/Consent";
+import DropStrip from "./pages/DropStrip";
 
 const Main =  () => {
   return( 
@@ -22,6 +23,7 @@ const Main =  () => {
           <Route path="/settings" element={<SettingsWindow />} />
           <Route path="/updater" element={<Updater />} />
           <Route path="/consent" element={<Consent />} />
+          <Route path="/drop-strip" element={<DropStrip />} />
         </Routes>
       </Router>
     </ThemeProvider>
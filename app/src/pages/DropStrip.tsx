@@ -0,0 +1,4 @@
+// Thin always-on-top bar; the mouse monitor opens the shelf when a drag enters it
+export default function DropStrip() {
+  return <div className="h-screen w-screen bg-primary/70" />;
+}
@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { X, Plus, Trash2, Keyboard, Monitor, Settings, Info, AlertTriangle } from 'lucide-react';
+import { X, Plus, Trash2, Keyboard, Monitor, Settings, Info, AlertTriangle, ArrowUp, MousePointer2, SlidersHorizontal } from 'lucide-react';
 import { useEffect, useState } from 'react';
 import { Input } from "@/components/ui/input"
 import { Button } from "@/components/ui/button"
@@ -13,16 +13,86 @@ import {
 } from "@/components/ui/card"
 
 const isMac = navigator.platform.toLowerCase().includes('mac');
+const isLinux = navigator.platform.toLowerCase().includes('linux');
 
 
+type RuleAction = 'allow' | 'deny';
+type ShakeAxis = 'horizontal' | 'vertical' | 'any';
+type ScreenSide = 'top' | 'bottom' | 'left' | 'right';
+type ScreenEdge = ScreenSide | 'top_left' | 'top_right' | 'bottom_left' | 'bottom_right';
+
+const SCREEN_EDGES: { edge: ScreenEdge; label: string }[] = [
+    { edge: 'top_left', label: 'Top left' },
+    { edge: 'top', label: 'Top' },
+    { edge: 'top_right', label: 'Top right' },
+    { edge: 'left', label: 'Left' },
+    { edge: 'right', label: 'Right' },
+    { edge: 'bottom_left', label: 'Bottom left' },
+    { edge: 'bottom', label: 'Bottom' },
+    { edge: 'bottom_right', label: 'Bottom right' },
+];
+
+// Opens the shelf when a drag rests at a screen edge or corner
+interface EdgeDwellConfig {
+    enabled: boolean;
+    edges: ScreenEdge[];
+    dwell_ms: number;
+    edge_size: number;
+    corner_size: number;
+}
+
+// A thin window on the primary screen, shown during drags, that opens the shelf when a drag enters it
+interface DropStripConfig {
+    enabled: boolean;
+    side: ScreenSide;
+    thickness: number;
+    // 0 spans the whole side
+    length: number;
+}
+
+// Opens the shelf when keys are held, or tapped twice, during a drag
+interface KeyTriggerConfig {
+    enabled: boolean;
+    keys: string;
+    double_tap: boolean;
+    double_tap_ms: number;
+}
+
+interface AppRule {
+    action: RuleAction;
+    pattern: string;
+    match_kind: 'exact' | 'glob' | 'regex';
+    match_on: 'name' | 'path';
+    required_shakes?: number | null;
+    shake_threshold?: number | null;
+    shake_time_limit?: number | null;
+    min_shake_speed?: number | null;
+}
+
 interface MouseMonitorConfig {
     required_shakes: number;
     shake_time_limit: number;
+    // Shortest stroke between two reversals, in pixels
     shake_threshold: number;
     window_close_delay: number;
-    whitelist: string[];
+    app_rules: AppRule[];
+    unmatched_action: RuleAction;
+    shake_axis: ShakeAxis;
+    // Slowest stroke that counts, in pixels per second
+    min_shake_speed: number;
+    edge_dwell: EdgeDwellConfig;
+    drop_strip: DropStripConfig;
+    key_trigger: KeyTriggerConfig;
 }
 
+// Same as default_app_rules in config.rs: the platform's file managers, any app on macOS
+const defaultAppRules = (): AppRule[] => {
+    const fileManagers = isMac ? [] : isLinux
+        ? ['nautilus', 'nemo', 'caja', 'dolphin', 'thunar', 'pcmanfm']
+        : ['explorer.exe'];
+    return fileManagers.map((pattern): AppRule => ({ action: 'allow', pattern, match_kind: 'exact', match_on: 'name' }));
+};
+
 interface AppConfig {
     mouse_monitor: MouseMonitorConfig;
     autostart: boolean;
@@ -36,7 +106,8 @@ export default function SettingsPage() {
     const [saving, setSaving] = useState(false);
     const [isListening, setIsListening] = useState(false);
     const [currentHotkey, setCurrentHotkey] = useState<string>('');
-    const [newWhitelistItem, setNewWhitelistItem] = useState('');
+    const [newRulePattern, setNewRulePattern] = useState('');
+    const [editingRuleSensitivity, setEditingRuleSensitivity] = useState<number | null>(null);
     const [platform] = useState(isMac ? 'mac' : 'win');
     const [inputMonitoringGranted, setInputMonitoringGranted] = useState<boolean | null>(null);
 
@@ -89,7 +160,13 @@ export default function SettingsPage() {
                     shake_time_limit: 1500,
                     shake_threshold: 100,
                     window_close_delay: 3000,
-                    whitelist: ['explorer.exe'],
+                    app_rules: defaultAppRules(),
+                    unmatched_action: isMac ? 'allow' : 'deny',
+                    shake_axis: 'horizontal',
+                    min_shake_speed: 800,
+                    edge_dwell: { enabled: false, edges: ['top_right'], dwell_ms: 600, edge_size: 2, corner_size: 40 },
+                    drop_strip: { enabled: false, side: 'top', thickness: 4, length: 240 },
+                    key_trigger: { enabled: false, keys: 'Shift+Ctrl', double_tap: false, double_tap_ms: 400 },
                 },
                 autostart: false,
                 hotkey: '',
@@ -142,6 +219,27 @@ export default function SettingsPage() {
         });
     };
 
+    const updateEdgeDwell = (changes: Partial<EdgeDwellConfig>) => {
+        if (!config) return;
+        updateConfig({ edge_dwell: { ...config.mouse_monitor.edge_dwell, ...changes } });
+    };
+
+    const toggleEdge = (edge: ScreenEdge) => {
+        if (!config) return;
+        const edges = config.mouse_monitor.edge_dwell.edges;
+        updateEdgeDwell({ edges: edges.includes(edge) ? edges.filter(e => e !== edge) : [...edges, edge] });
+    };
+
+    const updateDropStrip = (changes: Partial<DropStripConfig>) => {
+        if (!config) return;
+        updateConfig({ drop_strip: { ...config.mouse_monitor.drop_strip, ...changes } });
+    };
+
+    const updateKeyTrigger = (changes: Partial<KeyTriggerConfig>) => {
+        if (!config) return;
+        updateConfig({ key_trigger: { ...config.mouse_monitor.key_trigger, ...changes } });
+    };
+
     const toggleAutostart = () => {
         if (!config) return;
 
@@ -195,25 +293,53 @@ export default function SettingsPage() {
         });
     };
 
-    const addWhitelistItem = () => {
-        if (!config || !newWhitelistItem.trim()) return;
+    const addAppRule = () => {
+        if (!config || !newRulePattern.trim()) return;
 
-        const currentWhitelist = config.mouse_monitor.whitelist || [];
-        if (!currentWhitelist.includes(newWhitelistItem.trim())) {
-            updateConfig({
-                whitelist: [...currentWhitelist, newWhitelistItem.trim()]
-            });
-        }
-        setNewWhitelistItem('');
+        updateConfig({
+            app_rules: [
+                ...config.mouse_monitor.app_rules,
+                { action: 'allow', pattern: newRulePattern.trim(), match_kind: 'exact', match_on: 'name' },
+            ]
+        });
+        setNewRulePattern('');
+    };
+
+    const updateAppRule = (index: number, changes: Partial<AppRule>) => {
+        if (!config) return;
+
+        updateConfig({
+            app_rules: config.mouse_monitor.app_rules.map((rule, i) => i === index ? { ...rule, ...changes } : rule)
+        });
     };
 
-    const removeWhitelistItem = (itemToRemove: string) => {
+    const removeAppRule = (index: number) => {
         if (!config) return;
 
-        const currentWhitelist = config.mouse_monitor.whitelist || [];
         updateConfig({
-            whitelist: currentWhitelist.filter(item => item !== itemToRemove)
+            app_rules: config.mouse_monitor.app_rules.filter((_, i) => i !== index)
         });
+        setEditingRuleSensitivity(null);
+    };
+
+    // Rules are checked in order, so moving one up lets it win over the one above
+    const moveAppRuleUp = (index: number) => {
+        if (!config || index === 0) return;
+
+        const rules = [...config.mouse_monitor.app_rules];
+        [rules[index - 1], rules[index]] = [rules[index], rules[index - 1]];
+        updateConfig({ app_rules: rules });
+        setEditingRuleSensitivity(null);
+    };
+
+    const hasCustomSensitivity = (rule: AppRule) =>
+        [rule.required_shakes, rule.shake_threshold, rule.shake_time_limit, rule.min_shake_speed]
+            .some(value => value !== undefined && value !== null);
+
+    // An empty field falls back to the global setting
+    const parseRuleSetting = (value: string, parse: (value: string) => number) => {
+        const parsed = parse(value);
+        return value.trim() === '' || isNaN(parsed) ? null : parsed;
     };
 
     const buildHotkeyString = (e: KeyboardEvent): string => {
@@ -538,7 +664,7 @@ export default function SettingsPage() {
                                         />
                                     </div>
                                     <div className="space-y-1">
-                                        <Label htmlFor="threshold">Shake Threshold</Label>
+                                        <Label htmlFor="threshold">Stroke Length (px)</Label>
                                         <Input
                                             id="threshold"
                                             type="number"
@@ -547,6 +673,16 @@ export default function SettingsPage() {
                                             className="font-mono"
                                         />
                                     </div>
+                                    <div className="space-y-1">
+                                        <Label htmlFor="min-speed">Min Speed (px/s)</Label>
+                                        <Input
+                                            id="min-speed"
+                                            type="number"
+                                            value={config.mouse_monitor.min_shake_speed}
+                                            onChange={(e) => updateConfig({ min_shake_speed: parseFloat(e.target.value) })}
+                                            className="font-mono"
+                                        />
+                                    </div>
                                 </div>
                                 <div className="space-y-2">
                                     <Label className="text-xs font-medium text-muted-foreground uppercase tracking-wider">Timing</Label>
@@ -573,32 +709,51 @@ export default function SettingsPage() {
                                 </div>
                             </div>
 
+                            <div className="space-y-2">
+                                <div className="flex items-center justify-between gap-4">
+                                    <Label htmlFor="shake-axis">Shake Direction</Label>
+                                    <select
+                                        id="shake-axis"
+                                        value={config.mouse_monitor.shake_axis}
+                                        onChange={(e) => updateConfig({ shake_axis: e.target.value as ShakeAxis })}
+                                        className="h-8 rounded-md border bg-background px-2 text-sm"
+                                    >
+                                        <option value="horizontal">Left and right</option>
+                                        <option value="vertical">Up and down</option>
+                                        <option value="any">Any, circles included</option>
+                                    </select>
+                                </div>
+                                <p className="text-xs text-muted-foreground">
+                                    A shake is the required number of strokes in a row, each at least the stroke length and min speed, with no more than the time limit between them.
+                                </p>
+                            </div>
+
                             <div className="space-y-3 pt-4 border-t">
                                 <div className="space-y-1">
-                                    <Label>Whitelisted Apps</Label>
+                                    <Label>App Rules</Label>
                                     <p className="text-xs text-muted-foreground">
-                                        Shake detection will only be active when one of these apps is focused.
-                                        Accepts process names (e.g. <code className="bg-muted px-1 py-0.5 rounded text-foreground">brave</code>, <code className="bg-muted px-1 py-0.5 rounded text-foreground">brave.exe</code>). Case-insensitive.
+                                        Decide which apps a drag may open the shelf from. Rules are checked from the top and the first match wins.
+                                        Exact names may leave off <code className="bg-muted px-1 py-0.5 rounded text-foreground">.exe</code>; globs accept <code className="bg-muted px-1 py-0.5 rounded text-foreground">*</code> and <code className="bg-muted px-1 py-0.5 rounded text-foreground">?</code>. Case-insensitive.
                                     </p>
                                 </div>
 
                                 <div className="flex gap-2">
                                     <Input
                                         type="text"
-                                        value={newWhitelistItem}
-                                        onChange={(e) => setNewWhitelistItem(e.target.value)}
+                                        value={newRulePattern}
+                                        onChange={(e) => setNewRulePattern(e.target.value)}
                                         placeholder="Add process name..."
                                         onKeyDown={(e) => {
                                             if (e.key === 'Enter') {
-                                                addWhitelistItem();
+                                                addAppRule();
                                             }
                                         }}
                                         className="font-mono text-sm"
                                     />
                                     <Button
-                                        onClick={addWhitelistItem}
+                                        onClick={addAppRule}
                                         variant="secondary"
-                                        disabled={!newWhitelistItem.trim()}
+                                        disabled={!newRulePattern.trim()}
                                         className="shrink-0"
                                     >
                                         <Plus className="h-4 w-4 mr-2" />
@@ -606,28 +761,125 @@ export default function SettingsPage() {
                                     </Button>
                                 </div>
 
-                                <div className="bg-muted/30 rounded-lg border min-h-[100px] max-h-[200px] overflow-y-auto p-1">
-                                    {(config.mouse_monitor.whitelist || []).length > 0 ? (
+                                <div className="bg-muted/30 rounded-lg border min-h-[100px] max-h-[240px] overflow-y-auto p-1">
+                                    {config.mouse_monitor.app_rules.length > 0 ? (
                                         <div className="space-y-1">
-                                            {config.mouse_monitor.whitelist.map((app, index) => (
+                                            {config.mouse_monitor.app_rules.map((rule, index) => (
                                                 <div
                                                     key={index}
-                                                    className="group flex items-center justify-between p-2 rounded-md hover:bg-background hover:shadow-sm hover:border-border/50 border border-transparent transition-all"
+                                                    className="group p-2 rounded-md hover:bg-background hover:shadow-sm hover:border-border/50 border border-transparent transition-all"
                                                 >
-                                                    <div className="flex items-center gap-2 overflow-hidden">
-                                                        <div className="h-6 w-6 rounded bg-primary/10 flex items-center justify-center shrink-0">
-                                                            <span className="text-xs font-mono font-bold text-primary">{app.charAt(0).toUpperCase()}</span>
+                                                    <div className="flex items-center justify-between gap-2">
+                                                        <div className="flex items-center gap-2 overflow-hidden">
+                                                            <button
+                                                                type="button"
+                                                                onClick={() => updateAppRule(index, { action: rule.action === 'allow' ? 'deny' : 'allow' })}
+                                                                className={`text-xs font-medium px-2 py-0.5 rounded shrink-0 ${rule.action === 'allow' ? 'bg-primary/10 text-primary' : 'bg-destructive/10 text-destructive'}`}
+                                                            >
+                                                                {rule.action === 'allow' ? 'Allow' : 'Deny'}
+                                                            </button>
+                                                            <span className="text-sm font-mono truncate">{rule.pattern}</span>
+                                                            {hasCustomSensitivity(rule) && (
+                                                                <span className="text-xs text-muted-foreground shrink-0">custom sensitivity</span>
+                                                            )}
+                                                        </div>
+                                                        <div className="flex items-center gap-1 shrink-0">
+                                                            <select
+                                                                value={rule.match_kind}
+                                                                onChange={(e) => updateAppRule(index, { match_kind: e.target.value as AppRule['match_kind'] })}
+                                                                className="h-7 rounded-md border bg-background px-1 text-xs"
+                                                            >
+                                                                <option value="exact">Exact</option>
+                                                                <option value="glob">Glob</option>
+                                                                <option value="regex">Regex</option>
+                                                            </select>
+                                                            <select
+                                                                value={rule.match_on}
+                                                                onChange={(e) => updateAppRule(index, { match_on: e.target.value as AppRule['match_on'] })}
+                                                                className="h-7 rounded-md border bg-background px-1 text-xs"
+                                                            >
+                                                                <option value="name">Name</option>
+                                                                <option value="path">Path</option>
+                                                            </select>
+                                                            <Button
+                                                                variant="ghost"
+                                                                size="sm"
+                                                                onClick={() => setEditingRuleSensitivity(editingRuleSensitivity === index ? null : index)}
+                                                                title="Shake sensitivity for this app"
+                                                                className={`h-7 w-7 transition-all rounded-md ${editingRuleSensitivity === index || hasCustomSensitivity(rule) ? 'text-primary' : 'opacity-0 group-hover:opacity-100 text-muted-foreground'}`}
+                                                            >
+                                                                <SlidersHorizontal className="h-4 w-4" />
+                                                            </Button>
+                                                            <Button
+                                                                variant="ghost"
+                                                                size="sm"
+                                                                onClick={() => moveAppRuleUp(index)}
+                                                                disabled={index === 0}
+                                                                className="h-7 w-7 opacity-0 group-hover:opacity-100 text-muted-foreground transition-all rounded-md"
+                                                            >
+                                                                <ArrowUp className="h-4 w-4" />
+                                                            </Button>
+                                                            <Button
+                                                                variant="ghost"
+                                                                size="sm"
+                                                                onClick={() => removeAppRule(index)}
+                                                                className="h-7 w-7 opacity-0 group-hover:opacity-100 text-muted-foreground hover:text-destructive hover:bg-destructive/10 transition-all rounded-md"
+                                                            >
+                                                                <Trash2 className="h-4 w-4" />
+                                                            </Button>
                                                         </div>
-                                                        <span className="text-sm font-mono truncate">{app}</span>
                                                     </div>
-                                                    <Button
-                                                        variant="ghost"
-                                                        size="sm"
-                                                        onClick={() => removeWhitelistItem(app)}
-                                                        className="h-7 w-7 opacity-0 group-hover:opacity-100 text-muted-foreground hover:text-destructive hover:bg-destructive/10 transition-all rounded-md"
-                                                    >
-                                                        <Trash2 className="h-4 w-4" />
-                                                    </Button>
+                                                    {editingRuleSensitivity === index && (
+                                                        <div className="grid grid-cols-4 gap-2 pt-2">
+                                                            <div className="space-y-1">
+                                                                <Label htmlFor={`rule-${index}-shakes`} className="text-xs">Shakes</Label>
+                                                                <Input
+                                                                    id={`rule-${index}-shakes`}
+                                                                    type="number"
+                                                                    value={rule.required_shakes ?? ''}
+                                                                    placeholder={String(config.mouse_monitor.required_shakes)}
+                                                                    onChange={(e) => updateAppRule(index, { required_shakes: parseRuleSetting(e.target.value, parseInt) })}
+                                                                    className="h-7 font-mono text-xs"
+                                                                />
+                                                            </div>
+                                                            <div className="space-y-1">
+                                                                <Label htmlFor={`rule-${index}-threshold`} className="text-xs">Stroke (px)</Label>
+                                                                <Input
+                                                                    id={`rule-${index}-threshold`}
+                                                                    type="number"
+                                                                    value={rule.shake_threshold ?? ''}
+                                                                    placeholder={String(config.mouse_monitor.shake_threshold)}
+                                                                    onChange={(e) => updateAppRule(index, { shake_threshold: parseRuleSetting(e.target.value, parseInt) })}
+                                                                    className="h-7 font-mono text-xs"
+                                                                />
+                                                            </div>
+                                                            <div className="space-y-1">
+                                                                <Label htmlFor={`rule-${index}-speed`} className="text-xs">Speed (px/s)</Label>
+                                                                <Input
+                                                                    id={`rule-${index}-speed`}
+                                                                    type="number"
+                                                                    value={rule.min_shake_speed ?? ''}
+                                                                    placeholder={String(config.mouse_monitor.min_shake_speed)}
+                                                                    onChange={(e) => updateAppRule(index, { min_shake_speed: parseRuleSetting(e.target.value, parseFloat) })}
+                                                                    className="h-7 font-mono text-xs"
+                                                                />
+                                                            </div>
+                                                            <div className="space-y-1">
+                                                                <Label htmlFor={`rule-${index}-limit`} className="text-xs">Limit (ms)</Label>
+                                                                <Input
+                                                                    id={`rule-${index}-limit`}
+                                                                    type="number"
+                                                                    value={rule.shake_time_limit ?? ''}
+                                                                    placeholder={String(config.mouse_monitor.shake_time_limit)}
+                                                                    onChange={(e) => updateAppRule(index, { shake_time_limit: parseRuleSetting(e.target.value, parseInt) })}
+                                                                    className="h-7 font-mono text-xs"
+                                                                />
+                                                            </div>
+                                                            <p className="col-span-4 text-xs text-muted-foreground">
+                                                                Shake settings for drags from this app. Leave a field empty to use the one above.
+                                                            </p>
+                                                        </div>
+                                                    )}
                                                 </div>
                                             ))}
                                         </div>
@@ -636,11 +888,197 @@ export default function SettingsPage() {
                                             <div className="h-8 w-8 rounded-full bg-muted flex items-center justify-center mb-2">
                                                 <Monitor className="h-4 w-4 text-muted-foreground" />
                                             </div>
-                                            <p className="text-sm font-medium text-foreground">No apps whitelisted</p>
-                                            <p className="text-xs text-muted-foreground mt-1">Cursor shaking will be ignored globally</p>
+                                            <p className="text-sm font-medium text-foreground">No app rules</p>
+                                            <p className="text-xs text-muted-foreground mt-1">Every drag follows the setting below</p>
                                         </div>
                                     )}
                                 </div>
+
+                                <div className="flex items-center justify-between gap-4">
+                                    <Label htmlFor="unmatched-action">Drags from other apps</Label>
+                                    <select
+                                        id="unmatched-action"
+                                        value={config.mouse_monitor.unmatched_action}
+                                        onChange={(e) => updateConfig({ unmatched_action: e.target.value as RuleAction })}
+                                        className="h-8 rounded-md border bg-background px-2 text-sm"
+                                    >
+                                        <option value="allow">Allow</option>
+                                        <option value="deny">Ignore</option>
+                                    </select>
+                                </div>
+                            </div>
+                        </CardContent>
+                    </Card>
+
+                    <Card>
+                        <CardHeader>
+                            <CardTitle className="text-base flex items-center gap-2">
+                                <MousePointer2 className="w-4 h-4 text-primary" />
+                                Other Triggers
+                            </CardTitle>
+                            <CardDescription>More ways to open the shelf during a drag</CardDescription>
+                        </CardHeader>
+                        <CardContent className="space-y-6">
+                            <div className="space-y-3">
+                                <div className="flex items-center justify-between space-x-2">
+                                    <Label htmlFor="edge-dwell" className="flex flex-col space-y-1">
+                                        <span>Screen Edges</span>
+                                        <span className="font-normal text-xs text-muted-foreground">Open the shelf when a drag rests at one of these edges or corners</span>
+                                    </Label>
+                                    <Switch
+                                        id="edge-dwell"
+                                        checked={config.mouse_monitor.edge_dwell.enabled}
+                                        onCheckedChange={() => updateEdgeDwell({ enabled: !config.mouse_monitor.edge_dwell.enabled })}
+                                    />
+                                </div>
+                                {config.mouse_monitor.edge_dwell.enabled && (
+                                    <>
+                                        <div className="flex flex-wrap gap-1">
+                                            {SCREEN_EDGES.map(({ edge, label }) => (
+                                                <button
+                                                    key={edge}
+                                                    type="button"
+                                                    onClick={() => toggleEdge(edge)}
+                                                    className={`text-xs font-medium px-2 py-0.5 rounded border ${config.mouse_monitor.edge_dwell.edges.includes(edge) ? 'bg-primary/10 text-primary border-primary/30' : 'text-muted-foreground'}`}
+                                                >
+                                                    {label}
+                                                </button>
+                                            ))}
+                                        </div>
+                                        <div className="grid grid-cols-3 gap-4">
+                                            <div className="space-y-1">
+                                                <Label htmlFor="dwell-ms">Rest (ms)</Label>
+                                                <Input
+                                                    id="dwell-ms"
+                                                    type="number"
+                                                    value={config.mouse_monitor.edge_dwell.dwell_ms}
+                                                    onChange={(e) => updateEdgeDwell({ dwell_ms: parseInt(e.target.value) })}
+                                                    className="font-mono"
+                                                />
+                                            </div>
+                                            <div className="space-y-1">
+                                                <Label htmlFor="edge-size">Edge (px)</Label>
+                                                <Input
+                                                    id="edge-size"
+                                                    type="number"
+                                                    value={config.mouse_monitor.edge_dwell.edge_size}
+                                                    onChange={(e) => updateEdgeDwell({ edge_size: parseInt(e.target.value) })}
+                                                    className="font-mono"
+                                                />
+                                            </div>
+                                            <div className="space-y-1">
+                                                <Label htmlFor="corner-size">Corner (px)</Label>
+                                                <Input
+                                                    id="corner-size"
+                                                    type="number"
+                                                    value={config.mouse_monitor.edge_dwell.corner_size}
+                                                    onChange={(e) => updateEdgeDwell({ corner_size: parseInt(e.target.value) })}
+                                                    className="font-mono"
+                                                />
+                                            </div>
+                                        </div>
+                                    </>
+                                )}
+                            </div>
+
+                            <div className="space-y-3 pt-4 border-t">
+                                <div className="flex items-center justify-between space-x-2">
+                                    <Label htmlFor="drop-strip" className="flex flex-col space-y-1">
+                                        <span>Drop Strip</span>
+                                        <span className="font-normal text-xs text-muted-foreground">Show a thin strip on the primary screen during drags; dragging into it opens the shelf</span>
+                                    </Label>
+                                    <Switch
+                                        id="drop-strip"
+                                        checked={config.mouse_monitor.drop_strip.enabled}
+                                        onCheckedChange={() => updateDropStrip({ enabled: !config.mouse_monitor.drop_strip.enabled })}
+                                    />
+                                </div>
+                                {config.mouse_monitor.drop_strip.enabled && (
+                                    <div className="grid grid-cols-3 gap-4">
+                                        <div className="space-y-1">
+                                            <Label htmlFor="strip-side">Side</Label>
+                                            <select
+                                                id="strip-side"
+                                                value={config.mouse_monitor.drop_strip.side}
+                                                onChange={(e) => updateDropStrip({ side: e.target.value as ScreenSide })}
+                                                className="h-9 w-full rounded-md border bg-background px-2 text-sm"
+                                            >
+                                                <option value="top">Top</option>
+                                                <option value="bottom">Bottom</option>
+                                                <option value="left">Left</option>
+                                                <option value="right">Right</option>
+                                            </select>
+                                        </div>
+                                        <div className="space-y-1">
+                                            <Label htmlFor="strip-thickness">Thickness (px)</Label>
+                                            <Input
+                                                id="strip-thickness"
+                                                type="number"
+                                                value={config.mouse_monitor.drop_strip.thickness}
+                                                onChange={(e) => updateDropStrip({ thickness: parseInt(e.target.value) })}
+                                                className="font-mono"
+                                            />
+                                        </div>
+                                        <div className="space-y-1">
+                                            <Label htmlFor="strip-length">Length (px)</Label>
+                                            <Input
+                                                id="strip-length"
+                                                type="number"
+                                                value={config.mouse_monitor.drop_strip.length}
+                                                onChange={(e) => updateDropStrip({ length: parseInt(e.target.value) })}
+                                                placeholder="0 for the whole side"
+                                                className="font-mono"
+                                            />
+                                        </div>
+                                    </div>
+                                )}
+                            </div>
+
+                            <div className="space-y-3 pt-4 border-t">
+                                <div className="flex items-center justify-between space-x-2">
+                                    <Label htmlFor="key-trigger" className="flex flex-col space-y-1">
+                                        <span>Keys</span>
+                                        <span className="font-normal text-xs text-muted-foreground">Open the shelf when these keys are pressed during a drag</span>
+                                    </Label>
+                                    <Switch
+                                        id="key-trigger"
+                                        checked={config.mouse_monitor.key_trigger.enabled}
+                                        onCheckedChange={() => updateKeyTrigger({ enabled: !config.mouse_monitor.key_trigger.enabled })}
+                                    />
+                                </div>
+                                {config.mouse_monitor.key_trigger.enabled && (
+                                    <div className="grid grid-cols-2 gap-4">
+                                        <div className="space-y-1">
+                                            <Label htmlFor="trigger-keys">Keys</Label>
+                                            <Input
+                                                id="trigger-keys"
+                                                type="text"
+                                                value={config.mouse_monitor.key_trigger.keys}
+                                                onChange={(e) => updateKeyTrigger({ keys: e.target.value })}
+                                                placeholder="Shift+Ctrl"
+                                                className="font-mono"
+                                            />
+                                        </div>
+                                        <div className="space-y-1">
+                                            <Label htmlFor="double-tap-ms">Double Tap (ms)</Label>
+                                            <div className="flex items-center gap-2">
+                                                <Switch
+                                                    id="double-tap"
+                                                    checked={config.mouse_monitor.key_trigger.double_tap}
+                                                    onCheckedChange={() => updateKeyTrigger({ double_tap: !config.mouse_monitor.key_trigger.double_tap })}
+                                                />
+                                                <Input
+                                                    id="double-tap-ms"
+                                                    type="number"
+                                                    value={config.mouse_monitor.key_trigger.double_tap_ms}
+                                                    onChange={(e) => updateKeyTrigger({ double_tap_ms: parseInt(e.target.value) })}
+                                                    disabled={!config.mouse_monitor.key_trigger.double_tap}
+                                                    className="font-mono"
+                                                />
+                                            </div>
+                                        </div>
+                                    </div>
+                                )}
                             </div>
                         </CardContent>
                     </Card>
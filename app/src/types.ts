@@ -4,10 +4,21 @@ export interface FilePreview {
   preview: string;
   type: 'file' | 'folder';
   size: number;
-  path: string;
+  // Text, url and image items have no file until they are dragged or copied out
+  path: string | null;
   icon: string;
+  kind?: 'file' | 'folder' | 'text' | 'url' | 'image';
+  text?: string;
+  url?: string;
 }
 
 export interface FileWithPath extends File {
   path: string;
-}
\ No newline at end of file
+}
+
+// Emitted as 'drag_session' while shelf items are dragged out
+export type DragSessionEvent =
+  | { type: 'started' }
+  | { type: 'moved'; x: number; y: number }
+  | { type: 'entered_target'; x: number; y: number }
+  | { type: 'ended'; effect: 'copy' | 'move' | 'link' | null; removed: number[]; duration_ms: number };